@@ -1,41 +1,80 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
 
-use crate::node::{AigAndGate, AigInput, AigLatch, Node};
+use crate::aiger::SymbolType;
+use crate::node::{AigAndGate, AigInput, AigLatch, LatchInit, Node};
 use crate::reference::Ref;
-use crate::toposort::toposort_layers;
+use crate::symbol::SymbolTable;
 
 /// And-Inverter Graph.
+///
+/// Nodes are stored densely, indexed by their variable id.
+/// Structural information (topological order, fanouts and levels) is computed
+/// lazily on first use and cached until the graph is modified.
+#[derive(Clone)]
 pub struct Aig {
-    nodes: HashMap<u32, Node>,
+    /// Nodes indexed by id, `None` for unused ids. The constant (id 0) is not stored.
+    nodes: Vec<Option<Node>>,
     inputs: Vec<u32>,
     latches: Vec<u32>,
     outputs: Vec<Ref>,
+    bad: Vec<Ref>,
+    constraints: Vec<Ref>,
+    justice: Vec<Vec<Ref>>,
+    fairness: Vec<Ref>,
+    symbols: SymbolTable,
+    comments: Vec<String>,
+    structure: OnceLock<Structure>,
+}
+
+/// Cached structural information about the AND gates of an AIG.
+#[derive(Debug, Clone)]
+struct Structure {
+    /// AND gates in topological order, children before parents.
+    topological_order: Vec<u32>,
+    /// AND gates using each node as an argument, indexed by id.
+    fanouts: Vec<Vec<u32>>,
+    /// Logic level of each node, indexed by id: 0 for inputs, latches and the constant,
+    /// one more than the maximum level of the arguments for AND gates.
+    levels: Vec<u32>,
 }
 
 impl Aig {
-    pub const fn new(
-        nodes: HashMap<u32, Node>,
+    pub fn new(
+        nodes: impl IntoIterator<Item = Node>,
         inputs: Vec<u32>,
         latches: Vec<u32>,
         outputs: Vec<Ref>,
     ) -> Self {
-        Self {
-            nodes,
+        let mut aig = Self {
             inputs,
             latches,
             outputs,
+            ..Default::default()
+        };
+        for node in nodes {
+            aig.insert_node(node);
         }
+        aig
     }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Aig {
     fn default() -> Self {
         Self {
-            nodes: HashMap::new(),
+            nodes: Vec::new(),
             inputs: Vec::new(),
             latches: Vec::new(),
             outputs: Vec::new(),
+            bad: Vec::new(),
+            constraints: Vec::new(),
+            justice: Vec::new(),
+            fairness: Vec::new(),
+            symbols: SymbolTable::new(),
+            comments: Vec::new(),
+            structure: OnceLock::new(),
         }
     }
 }
@@ -61,12 +100,46 @@ impl Aig {
     pub fn outputs(&self) -> &[Ref] {
         &self.outputs
     }
-    pub fn nodes(&self) -> &HashMap<u32, Node> {
-        &self.nodes
+    /// Bad state properties.
+    pub fn bad(&self) -> &[Ref] {
+        &self.bad
+    }
+    /// Bad state properties to be checked: [`Aig::bad`], or the outputs
+    /// for AIGs without bad state properties (the AIGER 1.0 convention).
+    pub fn safety_properties(&self) -> &[Ref] {
+        if self.bad.is_empty() {
+            &self.outputs
+        } else {
+            &self.bad
+        }
+    }
+    /// Invariant constraints.
+    pub fn constraints(&self) -> &[Ref] {
+        &self.constraints
+    }
+    /// Justice properties, each given by a set of literals.
+    pub fn justice(&self) -> &[Vec<Ref>] {
+        &self.justice
+    }
+    /// Fairness constraints.
+    pub fn fairness(&self) -> &[Ref] {
+        &self.fairness
+    }
+    /// Iterate over all nodes (except the constant) in the order of their ids.
+    ///
+    /// Nodes are stored densely by id, so this no longer returns a `HashMap`:
+    /// use [`Aig::node`] and [`Aig::contains`] to look up a node by id.
+    pub fn nodes(&self) -> impl Iterator<Item = Node> + use<'_> {
+        self.nodes.iter().filter_map(|&node| node)
     }
+    /// Return the maximum variable index, or 0 for an empty AIG.
+    pub fn max_id(&self) -> u32 {
+        self.nodes.len().saturating_sub(1) as u32
+    }
+    /// Iterate over all AND gates in the order of their ids.
     pub fn and_gates(&self) -> impl Iterator<Item = AigAndGate> + use<'_> {
-        self.nodes.values().filter_map(|node| match node {
-            &Node::AndGate(gate) => Some(gate),
+        self.nodes.iter().filter_map(|node| match node {
+            &Some(Node::AndGate(gate)) => Some(gate),
             _ => None,
         })
     }
@@ -75,33 +148,36 @@ impl Aig {
         if id == 0 {
             return false;
         }
-        matches!(self.nodes[&id], Node::Input(..))
+        matches!(self.node(id), Node::Input(..))
     }
     pub fn is_latch(&self, id: u32) -> bool {
         if id == 0 {
             return false;
         }
-        matches!(self.nodes[&id], Node::Latch(..))
+        matches!(self.node(id), Node::Latch(..))
     }
     pub fn is_gate(&self, id: u32) -> bool {
         if id == 0 {
             return false;
         }
-        matches!(self.nodes[&id], Node::AndGate(..))
+        matches!(self.node(id), Node::AndGate(..))
     }
 
     pub fn contains(&self, id: u32) -> bool {
         if id == 0 {
             return true;
         }
-        self.nodes.contains_key(&id)
+        matches!(self.nodes.get(id as usize), Some(Some(_)))
     }
 
     pub fn node(&self, id: u32) -> Node {
         if id == 0 {
             return Node::Zero;
         }
-        self.nodes[&id]
+        match self.nodes.get(id as usize) {
+            Some(&Some(node)) => node,
+            _ => panic!("Node with id {} does not exist", id),
+        }
     }
     pub fn input(&self, id: u32) -> AigInput {
         match self.node(id) {
@@ -122,81 +198,320 @@ impl Aig {
         }
     }
 
+    fn insert_node(&mut self, node: Node) {
+        let id = node.id();
+        assert!(!self.contains(id));
+        let index = id as usize;
+        if index >= self.nodes.len() {
+            self.nodes.resize(index + 1, None);
+        }
+        self.nodes[index] = Some(node);
+        self.structure.take();
+    }
+
     pub fn add_input(&mut self, id: u32) {
         assert!(!self.contains(id));
         assert!(!self.inputs.contains(&id));
-        self.nodes.insert(id, Node::input(id));
+        self.insert_node(Node::input(id));
         self.inputs.push(id);
     }
 
-    pub fn add_latch(&mut self, id: u32, next: Ref) {
+    pub fn add_latch(&mut self, id: u32, next: Ref, init: LatchInit) {
         assert!(!self.contains(id));
         assert!(!self.latches.contains(&id));
-        self.nodes.insert(id, Node::latch(id, next));
+        self.insert_node(Node::latch(id, next, init));
         self.latches.push(id);
     }
 
-    pub fn add_output(&mut self, output: Ref) {
-        self.outputs.push(output);
+    pub fn set_latch_next(&mut self, id: u32, next: Ref) {
+        match self.nodes.get_mut(id as usize) {
+            Some(Some(Node::Latch(latch))) => latch.next = next,
+            _ => panic!("Node with id {} is not a latch", id),
+        }
     }
 
     pub fn add_and_gate(&mut self, id: u32, args: [Ref; 2]) {
         assert!(!self.contains(id));
         // NOTE: In some AIGER files, the gates are NOT defined in the topological order,
-        //       so the following assert might fail.
-        // for arg in args.iter() {
-        //     assert!(self.nodes.contains_key(&arg.id()));
-        // }
-        self.nodes.insert(id, Node::and_gate(id, args));
+        //       so the arguments might not exist yet.
+        self.insert_node(Node::and_gate(id, args));
+    }
+
+    pub fn add_output(&mut self, output: Ref) {
+        self.outputs.push(output);
+    }
+
+    pub fn add_bad(&mut self, bad: Ref) {
+        self.bad.push(bad);
+    }
+
+    pub fn add_constraint(&mut self, constraint: Ref) {
+        self.constraints.push(constraint);
+    }
+
+    pub fn add_justice(&mut self, justice: Vec<Ref>) {
+        self.justice.push(justice);
+    }
+
+    pub fn add_fairness(&mut self, fairness: Ref) {
+        self.fairness.push(fairness);
+    }
+
+    pub fn add_comment(&mut self, comment: String) {
+        self.comments.push(comment);
+    }
+}
+
+// Symbols
+impl Aig {
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// Set the name of the input, latch, output or property at the given position.
+    pub fn set_symbol(&mut self, type_spec: SymbolType, position: usize, name: String) {
+        self.symbols.insert(type_spec, position, name);
+    }
+
+    pub fn input_name(&self, id: u32) -> Option<&str> {
+        let position = self.inputs.iter().position(|&x| x == id)?;
+        self.symbols.name(SymbolType::Input, position)
+    }
+    pub fn latch_name(&self, id: u32) -> Option<&str> {
+        let position = self.latches.iter().position(|&x| x == id)?;
+        self.symbols.name(SymbolType::Latch, position)
+    }
+    pub fn output_name(&self, index: usize) -> Option<&str> {
+        self.symbols.name(SymbolType::Output, index)
+    }
+    pub fn bad_name(&self, index: usize) -> Option<&str> {
+        self.symbols.name(SymbolType::Bad, index)
+    }
+    pub fn constraint_name(&self, index: usize) -> Option<&str> {
+        self.symbols.name(SymbolType::Constraint, index)
+    }
+    pub fn justice_name(&self, index: usize) -> Option<&str> {
+        self.symbols.name(SymbolType::Justice, index)
+    }
+    pub fn fairness_name(&self, index: usize) -> Option<&str> {
+        self.symbols.name(SymbolType::Fairness, index)
+    }
+
+    /// Find the id of the input with the given name.
+    pub fn find_input(&self, name: &str) -> Option<u32> {
+        let position = self.symbols.position(SymbolType::Input, name)?;
+        self.inputs.get(position).copied()
+    }
+    /// Find the id of the latch with the given name.
+    pub fn find_latch(&self, name: &str) -> Option<u32> {
+        let position = self.symbols.position(SymbolType::Latch, name)?;
+        self.latches.get(position).copied()
+    }
+    /// Find the index of the output with the given name.
+    pub fn find_output(&self, name: &str) -> Option<usize> {
+        self.find_index(SymbolType::Output, name, self.outputs.len())
+    }
+    /// Find the index of the bad state property with the given name.
+    pub fn find_bad(&self, name: &str) -> Option<usize> {
+        self.find_index(SymbolType::Bad, name, self.bad.len())
+    }
+    /// Find the index of the invariant constraint with the given name.
+    pub fn find_constraint(&self, name: &str) -> Option<usize> {
+        self.find_index(SymbolType::Constraint, name, self.constraints.len())
+    }
+    /// Find the index of the justice property with the given name.
+    pub fn find_justice(&self, name: &str) -> Option<usize> {
+        self.find_index(SymbolType::Justice, name, self.justice.len())
+    }
+    /// Find the index of the fairness constraint with the given name.
+    pub fn find_fairness(&self, name: &str) -> Option<usize> {
+        self.find_index(SymbolType::Fairness, name, self.fairness.len())
+    }
+
+    fn find_index(&self, type_spec: SymbolType, name: &str, len: usize) -> Option<usize> {
+        let position = self.symbols.position(type_spec, name)?;
+        (position < len).then_some(position)
+    }
+}
+
+// Structure
+impl Aig {
+    fn structure(&self) -> &Structure {
+        self.structure.get_or_init(|| Structure::compute(self))
+    }
+
+    /// Return the AND gates in topological order, children before parents.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the AIG contains a combinational cycle.
+    pub fn topological_order(&self) -> &[u32] {
+        &self.structure().topological_order
+    }
+
+    /// Return the AND gates that use the given node as an argument.
+    ///
+    /// Note that latches referencing the node via their next state are not included.
+    pub fn fanouts(&self, id: u32) -> &[u32] {
+        self.structure()
+            .fanouts
+            .get(id as usize)
+            .map_or(&[], |f| f.as_slice())
+    }
+
+    /// Return the logic level of the node: 0 for inputs, latches and the constant,
+    /// one more than the maximum level of the arguments for AND gates.
+    pub fn level(&self, id: u32) -> u32 {
+        self.structure()
+            .levels
+            .get(id as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Return the maximum logic level in the AIG.
+    pub fn depth(&self) -> u32 {
+        self.structure().levels.iter().copied().max().unwrap_or(0)
+    }
+}
+
+impl Structure {
+    fn compute(aig: &Aig) -> Self {
+        let n = aig.nodes.len();
+
+        // Iterative DFS, emitting each gate after all its arguments.
+        const UNVISITED: u8 = 0;
+        const ON_STACK: u8 = 1;
+        const DONE: u8 = 2;
+
+        let mut state = vec![UNVISITED; n];
+        let mut topological_order = Vec::new();
+        let mut stack = Vec::new();
+        for gate in aig.and_gates() {
+            if state[gate.id as usize] != UNVISITED {
+                continue;
+            }
+            stack.push((gate.id, 0));
+            state[gate.id as usize] = ON_STACK;
+            while let Some(&mut (id, ref mut k)) = stack.last_mut() {
+                let args = aig.gate(id).args;
+                if *k < args.len() {
+                    let child = args[*k].id();
+                    *k += 1;
+                    if !matches!(aig.nodes.get(child as usize), Some(Some(Node::AndGate(_)))) {
+                        continue;
+                    }
+                    match state[child as usize] {
+                        UNVISITED => {
+                            state[child as usize] = ON_STACK;
+                            stack.push((child, 0));
+                        }
+                        ON_STACK => {
+                            panic!("AIG contains a combinational cycle through {}", child)
+                        }
+                        _ => {}
+                    }
+                } else {
+                    stack.pop();
+                    state[id as usize] = DONE;
+                    topological_order.push(id);
+                }
+            }
+        }
+
+        let mut fanouts = vec![Vec::new(); n];
+        let mut levels = vec![0; n];
+        for &id in &topological_order {
+            let [left, right] = aig.gate(id).args;
+            let mut level = 0;
+            for arg in [left, right] {
+                let index = arg.id() as usize;
+                if index < n {
+                    level = level.max(levels[index]);
+                    if fanouts[index].last() != Some(&id) {
+                        fanouts[index].push(id);
+                    }
+                }
+            }
+            levels[id as usize] = level + 1;
+        }
+
+        Structure {
+            topological_order,
+            fanouts,
+            levels,
+        }
     }
 }
 
 // Layers
 impl Aig {
     /// Return the iterator of 'backward' layers in the AIG.
-    /// The first 'backward' layer consists of all inputs.
+    /// The first 'backward' layer consists of all inputs, latches and the constant
+    /// used by AND gates, each next layer consists of the AND gates of the next logic level
+    /// (see [`Aig::level`]). Nodes not connected to any AND gate are not included.
     pub fn layers_input(&self) -> impl Iterator<Item = Vec<u32>> {
-        let graph = self.reverse_dependency_graph();
-        toposort_layers(&graph)
-            .map(|mut xs| {
-                xs.sort();
-                xs
-            })
-            .collect::<Vec<_>>()
-            .into_iter()
+        let mut layers = Vec::<Vec<u32>>::new();
+        for &id in self.topological_order() {
+            if layers.is_empty() {
+                layers.push(Vec::new());
+            }
+            for arg in self.gate(id).args {
+                if !self.is_gate(arg.id()) {
+                    layers[0].push(arg.id());
+                }
+            }
+            let level = self.level(id) as usize;
+            if layers.len() <= level {
+                layers.resize(level + 1, Vec::new());
+            }
+            layers[level].push(id);
+        }
+        for layer in layers.iter_mut() {
+            layer.sort();
+            layer.dedup();
+        }
+        layers.into_iter()
     }
 
     /// Return the iterator of 'forward' layers in the AIG.
-    /// The first 'forward' layer consists of all outputs.
+    /// The first 'forward' layer consists of all nodes which are not used by AND gates
+    /// (e.g. the gates driving the outputs), each next layer consists of the nodes whose
+    /// fanouts are all in the previous layers. The constant is included when it is used
+    /// by some AND gate.
     pub fn layers_output(&self) -> impl Iterator<Item = Vec<u32>> {
-        let graph = self.dependency_graph();
-        toposort_layers(&graph)
-            .map(|mut xs| {
-                xs.sort();
-                xs
-            })
-            .collect::<Vec<_>>()
-            .into_iter()
-    }
-
-    fn dependency_graph(&self) -> HashMap<u32, Vec<u32>> {
-        self.nodes()
-            .iter()
-            .map(|(&id, node)| {
-                let deps = node.children().iter().map(|c| c.id()).collect::<Vec<_>>();
-                (id, deps)
-            })
-            .collect()
-    }
+        // Reverse level: 0 for nodes without fanouts,
+        // one more than the maximum reverse level of the fanouts otherwise.
+        let mut reverse_levels = vec![None; self.nodes.len()];
+        for &id in self.topological_order().iter().rev() {
+            let level = reverse_levels[id as usize].unwrap_or(0);
+            for arg in self.gate(id).args {
+                let index = arg.id() as usize;
+                if index < reverse_levels.len() {
+                    reverse_levels[index] = reverse_levels[index].max(Some(level + 1));
+                }
+            }
+        }
 
-    fn reverse_dependency_graph(&self) -> HashMap<u32, HashSet<u32>> {
-        let mut result = HashMap::<u32, HashSet<u32>>::new();
-        for (&id, node) in self.nodes() {
-            for c in node.children() {
-                result.entry(c.id()).or_default().insert(id);
+        let mut layers = Vec::<Vec<u32>>::new();
+        let constant = reverse_levels
+            .first()
+            .copied()
+            .flatten()
+            .map(|level| (0, level));
+        let nodes = self
+            .nodes()
+            .map(|node| (node.id(), reverse_levels[node.id() as usize].unwrap_or(0)));
+        for (id, level) in constant.into_iter().chain(nodes) {
+            if layers.len() <= level {
+                layers.resize(level + 1, Vec::new());
             }
+            layers[level].push(id);
         }
-        result
+        layers.into_iter()
     }
 }
 
@@ -205,46 +520,37 @@ impl Aig {
     pub fn eval(&self, input_values: Vec<bool>) -> BTreeMap<u32, bool> {
         assert_eq!(input_values.len(), self.inputs.len());
 
-        let mut values = BTreeMap::new();
+        let mut values = vec![None; self.nodes.len()];
 
         for (&id, value) in self.inputs.iter().zip(input_values) {
-            values.insert(id, value);
+            values[id as usize] = Some(value);
         }
 
-        fn get_value(r: Ref, values: &BTreeMap<u32, bool>) -> bool {
+        fn get_value(r: Ref, values: &[Option<bool>]) -> bool {
             if r.is_false() {
                 false
             } else if r.is_true() {
                 true
             } else {
-                values[&r.id()] ^ r.is_negated()
+                match values.get(r.id() as usize) {
+                    Some(Some(value)) => value ^ r.is_negated(),
+                    _ => panic!("Value of node {} is not available", r.id()),
+                }
             }
         }
 
-        for (i, layer) in self.layers_input().enumerate().skip(1) {
-            for id in layer {
-                match self.node(id) {
-                    Node::Zero => {
-                        panic!("Unexpected zero on layer {}", i);
-                    }
-                    Node::Input(input) => {
-                        panic!("Unexpected input on layer {}: {:?}", i, input);
-                    }
-                    Node::Latch(latch) => {
-                        panic!("Unexpected latch on layer {}: {:?}", i, latch);
-                    }
-                    Node::AndGate(gate) => {
-                        let [left, right] = gate.args;
-                        let left = get_value(left, &values);
-                        let right = get_value(right, &values);
-                        let value = left && right;
-                        values.insert(id, value);
-                    }
-                }
-            }
+        for &id in self.topological_order() {
+            let [left, right] = self.gate(id).args;
+            let left = get_value(left, &values);
+            let right = get_value(right, &values);
+            values[id as usize] = Some(left && right);
         }
 
         values
+            .into_iter()
+            .enumerate()
+            .filter_map(|(id, value)| Some((id as u32, value?)))
+            .collect()
     }
 }
 
@@ -280,6 +586,32 @@ mod tests {
     }
 
     #[test]
+    fn test_layers_sequential() {
+        // Input 2 only drives an output, input 3 is unused, latch 4 and the constant
+        // are used by gates.
+        let mut aig = Aig::default();
+        aig.add_input(1);
+        aig.add_input(2);
+        aig.add_input(3);
+        aig.add_latch(4, Ref::positive(7), LatchInit::Zero);
+        aig.add_and_gate(5, [Ref::positive(1), Ref::positive(4)]);
+        aig.add_and_gate(6, [Ref::negative(5), Ref::FALSE]);
+        aig.add_and_gate(7, [Ref::positive(6), Ref::positive(5)]);
+        aig.add_output(Ref::positive(2));
+        aig.add_output(Ref::negative(7));
+
+        let layers_input = aig.layers_input().collect::<Vec<_>>();
+        assert_eq!(layers_input, vec![vec![0, 1, 4], vec![5], vec![6], vec![7]]);
+
+        let layers_output = aig.layers_output().collect::<Vec<_>>();
+        assert_eq!(
+            layers_output,
+            vec![vec![2, 3, 7], vec![6], vec![0, 5], vec![1, 4]]
+        );
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_eval() {
         let mut aig = Aig::default();
 
@@ -310,4 +642,42 @@ mod tests {
         assert_eq!(values[&6], false); // g3 = x1 and ~g2
         assert_eq!(values[&7], false); // g4 = g3 and 0
     }
+
+    #[test]
+    fn test_structure() {
+        let mut aig = Aig::default();
+
+        // Gates are defined out of topological order:
+        aig.add_input(1);
+        aig.add_and_gate(2, [Ref::positive(1), Ref::negative(4)]);
+        aig.add_input(3);
+        aig.add_and_gate(4, [Ref::positive(3), Ref::negative(1)]);
+        aig.add_and_gate(6, [Ref::positive(2), Ref::positive(4)]);
+        aig.add_output(Ref::positive(6));
+
+        assert_eq!(aig.max_id(), 6);
+        assert!(!aig.contains(5));
+        assert_eq!(aig.topological_order(), &[4, 2, 6]);
+        assert_eq!(aig.fanouts(1), &[4, 2]);
+        assert_eq!(aig.fanouts(4), &[2, 6]);
+        assert!(aig.fanouts(6).is_empty());
+        assert_eq!(aig.level(3), 0);
+        assert_eq!(aig.level(2), 2);
+        assert_eq!(aig.depth(), 3);
+
+        // Modification invalidates the cached structure:
+        aig.add_and_gate(5, [Ref::positive(6), Ref::positive(1)]);
+        assert_eq!(aig.topological_order(), &[4, 2, 6, 5]);
+        assert_eq!(aig.depth(), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cycle() {
+        let mut aig = Aig::default();
+        aig.add_input(1);
+        aig.add_and_gate(2, [Ref::positive(1), Ref::positive(3)]);
+        aig.add_and_gate(3, [Ref::positive(1), Ref::positive(2)]);
+        aig.topological_order();
+    }
 }
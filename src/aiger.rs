@@ -1,9 +1,11 @@
 use std::fmt::{Display, Formatter};
-use std::io::{BufRead, Lines};
+use std::io::BufRead;
 use std::str::FromStr;
 
 use eyre::{eyre, WrapErr};
 
+use crate::node::LatchInit;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[repr(transparent)]
 pub struct Literal(u32);
@@ -28,9 +30,29 @@ impl Literal {
     }
 }
 
+/// AIGER file format.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Format {
+    /// ASCII format, tagged with `aag`.
+    Ascii,
+    /// Binary format, tagged with `aig`.
+    Binary,
+}
+
+impl Format {
+    pub const fn tag(self) -> &'static str {
+        match self {
+            Format::Ascii => TAG,
+            Format::Binary => BINARY_TAG,
+        }
+    }
+}
+
 /// AIGER header.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Header {
+    /// The file format.
+    pub format: Format,
     /// The maximum variable index.
     pub m: usize,
     /// The number of inputs.
@@ -41,9 +63,18 @@ pub struct Header {
     pub o: usize,
     /// The number of AND gates.
     pub a: usize,
+    /// The number of bad state properties.
+    pub b: usize,
+    /// The number of invariant constraints.
+    pub c: usize,
+    /// The number of justice properties.
+    pub j: usize,
+    /// The number of fairness constraints.
+    pub f: usize,
 }
 
 pub const TAG: &str = "aag";
+pub const BINARY_TAG: &str = "aig";
 
 impl FromStr for Header {
     type Err = eyre::Error;
@@ -52,9 +83,18 @@ impl FromStr for Header {
         let mut components = line.split(' ');
 
         let tag = components.next().ok_or_else(|| eyre!("Tag is missing"))?;
-        if tag != TAG {
-            return Err(eyre!("Invalid tag '{}', expected '{}'", tag, TAG));
-        }
+        let format = match tag {
+            TAG => Format::Ascii,
+            BINARY_TAG => Format::Binary,
+            _ => {
+                return Err(eyre!(
+                    "Invalid tag '{}', expected '{}' or '{}'",
+                    tag,
+                    TAG,
+                    BINARY_TAG
+                ))
+            }
+        };
 
         let mut components = components.map(|s| {
             s.parse::<usize>()
@@ -64,7 +104,7 @@ impl FromStr for Header {
         let mut next_component = || {
             components
                 .next()
-                .ok_or_else(|| eyre!("Not enough components, expected 'aag m i l o a'"))?
+                .ok_or_else(|| eyre!("Not enough components, expected '{} m i l o a'", tag))?
         };
         let m = next_component()?;
         let i = next_component()?;
@@ -72,12 +112,43 @@ impl FromStr for Header {
         let o = next_component()?;
         let a = next_component()?;
 
+        // Optional AIGER 1.9 components:
+        let mut next_optional_component = || components.next().transpose();
+        let b = next_optional_component()?.unwrap_or(0);
+        let c = next_optional_component()?.unwrap_or(0);
+        let j = next_optional_component()?.unwrap_or(0);
+        let f = next_optional_component()?.unwrap_or(0);
+
         if components.next().is_some() {
             // There are more components than expected.
-            return Err(eyre!("Too many components, expected 'aag m i l o a'"));
+            return Err(eyre!(
+                "Too many components, expected '{} m i l o a [b [c [j [f]]]]'",
+                tag
+            ));
+        }
+
+        if format == Format::Binary && m != i + l + a {
+            return Err(eyre!(
+                "Invalid binary header, expected m = i + l + a, got {} != {} + {} + {}",
+                m,
+                i,
+                l,
+                a
+            ));
         }
 
-        Ok(Header { m, i, l, o, a })
+        Ok(Header {
+            format,
+            m,
+            i,
+            l,
+            o,
+            a,
+            b,
+            c,
+            j,
+            f,
+        })
     }
 }
 
@@ -86,16 +157,47 @@ impl Display for Header {
         write!(
             f,
             "{} {} {} {} {} {}",
-            TAG, self.m, self.i, self.l, self.o, self.a
-        )
+            self.format.tag(),
+            self.m,
+            self.i,
+            self.l,
+            self.o,
+            self.a
+        )?;
+        // Optional AIGER 1.9 components, up to the last non-zero one:
+        let optional = [self.b, self.c, self.j, self.f];
+        let n = optional.iter().rposition(|&x| x != 0).map_or(0, |k| k + 1);
+        for x in &optional[..n] {
+            write!(f, " {}", x)?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum SymbolType {
     Input,
     Latch,
     Output,
+    Bad,
+    Constraint,
+    Justice,
+    Fairness,
+}
+
+impl SymbolType {
+    /// The prefix of the symbol in the symbol table, e.g. `i` for inputs.
+    pub const fn prefix(self) -> char {
+        match self {
+            SymbolType::Input => 'i',
+            SymbolType::Latch => 'l',
+            SymbolType::Output => 'o',
+            SymbolType::Bad => 'b',
+            SymbolType::Constraint => 'c',
+            SymbolType::Justice => 'j',
+            SymbolType::Fairness => 'f',
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -108,10 +210,24 @@ pub enum Record {
         id: Literal,
         /// The next state.
         next: Literal,
+        /// The initial value.
+        init: LatchInit,
     },
     Output {
         id: Literal,
     },
+    Bad {
+        id: Literal,
+    },
+    Constraint {
+        id: Literal,
+    },
+    Justice {
+        literals: Vec<Literal>,
+    },
+    Fairness {
+        id: Literal,
+    },
     AndGate {
         id: Literal,
         inputs: [Literal; 2],
@@ -121,6 +237,10 @@ pub enum Record {
         position: usize,
         symbol: String,
     },
+    /// A single line in the comment section.
+    Comment {
+        text: String,
+    },
 }
 
 impl Record {
@@ -135,10 +255,29 @@ impl Record {
     }
 
     fn parse_latch(literals: &[Literal]) -> eyre::Result<Record> {
-        match literals {
-            &[id, next] => Ok(Record::Latch { id, next }),
+        match *literals {
+            [id, next] => Ok(Record::Latch {
+                id,
+                next,
+                init: LatchInit::Zero,
+            }),
+            [id, next, reset] => {
+                let init = match reset.raw() {
+                    0 => LatchInit::Zero,
+                    1 => LatchInit::One,
+                    r if r == id.raw() => LatchInit::Nondet,
+                    _ => {
+                        return Err(eyre!(
+                            "Invalid latch reset {}, expected 0, 1 or {}",
+                            reset.raw(),
+                            id.raw()
+                        ))
+                    }
+                };
+                Ok(Record::Latch { id, next, init })
+            }
             _ => Err(eyre!(
-                "Invalid number of literals for latch: expected 2, got {}",
+                "Invalid number of literals for latch: expected 2 or 3, got {}",
                 literals.len()
             )),
         }
@@ -154,6 +293,36 @@ impl Record {
         }
     }
 
+    fn parse_bad(literals: &[Literal]) -> eyre::Result<Record> {
+        match literals {
+            &[id] => Ok(Record::Bad { id }),
+            _ => Err(eyre!(
+                "Invalid number of literals for bad state property: expected 1, got {}",
+                literals.len()
+            )),
+        }
+    }
+
+    fn parse_constraint(literals: &[Literal]) -> eyre::Result<Record> {
+        match literals {
+            &[id] => Ok(Record::Constraint { id }),
+            _ => Err(eyre!(
+                "Invalid number of literals for invariant constraint: expected 1, got {}",
+                literals.len()
+            )),
+        }
+    }
+
+    fn parse_fairness(literals: &[Literal]) -> eyre::Result<Record> {
+        match literals {
+            &[id] => Ok(Record::Fairness { id }),
+            _ => Err(eyre!(
+                "Invalid number of literals for fairness constraint: expected 1, got {}",
+                literals.len()
+            )),
+        }
+    }
+
     fn parse_and_gate(literals: &[Literal]) -> eyre::Result<Record> {
         match literals {
             &[id, left, right] => Ok(Record::AndGate {
@@ -173,9 +342,13 @@ impl Record {
             "i" => SymbolType::Input,
             "l" => SymbolType::Latch,
             "o" => SymbolType::Output,
+            "b" => SymbolType::Bad,
+            "c" => SymbolType::Constraint,
+            "j" => SymbolType::Justice,
+            "f" => SymbolType::Fairness,
             _ => {
                 return Err(eyre!(
-                    "Invalid type '{}', expected 'i', 'l' or 'o'",
+                    "Invalid type '{}', expected 'i', 'l', 'o', 'b', 'c', 'j' or 'f'",
                     type_spec
                 ))
             }
@@ -215,7 +388,7 @@ impl Record {
                     return Err(eyre!("Input {} is inverted", id.index()));
                 }
             }
-            Record::Latch { id, next } => {
+            Record::Latch { id, next, .. } => {
                 if id.index() == 0 {
                     return Err(eyre!("Latch index must be non-zero"));
                 }
@@ -249,6 +422,26 @@ impl Record {
                     ));
                 }
             }
+            Record::Bad { id } | Record::Constraint { id } | Record::Fairness { id } => {
+                if id.index() > header.m as u32 {
+                    return Err(eyre!(
+                        "Property {} is out of range (1..{})",
+                        id.index(),
+                        header.m
+                    ));
+                }
+            }
+            Record::Justice { literals } => {
+                for lit in literals {
+                    if lit.index() > header.m as u32 {
+                        return Err(eyre!(
+                            "Justice literal {} is out of range (1..{})",
+                            lit.index(),
+                            header.m
+                        ));
+                    }
+                }
+            }
             Record::AndGate { id, inputs } => {
                 if id.index() > header.m as u32 {
                     return Err(eyre!(
@@ -274,6 +467,7 @@ impl Record {
                 }
             }
             Record::Symbol { .. } => {}
+            Record::Comment { .. } => {}
         }
 
         Ok(self)
@@ -281,23 +475,22 @@ impl Record {
 }
 
 /// A reader for AIGER files.
+///
+/// Both the ASCII (`aag`) and the binary (`aig`) formats are supported,
+/// the format is detected from the header tag.
 pub struct Reader<R> {
-    lines: Lines<R>,
+    reader: R,
     header: Header,
 }
 
 impl<R: BufRead> Reader<R> {
-    pub fn new(reader: R) -> eyre::Result<Reader<R>> {
-        let mut lines = reader.lines();
-
-        let header_line = lines
-            .next()
-            .ok_or_else(|| eyre!("Header line is missing"))??;
+    pub fn new(mut reader: R) -> eyre::Result<Reader<R>> {
+        let header_line = read_line(&mut reader)?.ok_or_else(|| eyre!("Header line is missing"))?;
         let header = header_line
             .parse::<Header>()
             .wrap_err_with(|| format!("Invalid header '{}'", header_line))?;
 
-        Ok(Reader { lines, header })
+        Ok(Reader { reader, header })
     }
 }
 
@@ -307,58 +500,145 @@ impl<R> Reader<R> {
     }
 
     pub fn records(self) -> RecordsIter<R> {
-        RecordsIter::new(self.lines, self.header)
+        RecordsIter::new(self.reader, self.header)
+    }
+}
+
+/// Read a single line, without the trailing newline.
+/// Returns `None` at the end of the input.
+fn read_line(reader: &mut impl BufRead) -> eyre::Result<Option<String>> {
+    let mut buf = Vec::new();
+    if reader.read_until(b'\n', &mut buf)? == 0 {
+        return Ok(None);
+    }
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    }
+    let line = String::from_utf8(buf).map_err(|_| eyre!("Line is not valid UTF-8"))?;
+    Ok(Some(line))
+}
+
+/// Read a single delta-encoded number from the binary AND gate section.
+fn read_delta(reader: &mut impl BufRead) -> eyre::Result<u32> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8];
+        reader
+            .read_exact(&mut byte)
+            .wrap_err("Unexpected end of binary data")?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if value > u32::MAX as u64 {
+            return Err(eyre!("Delta-encoded number is too large"));
+        }
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as u32);
+        }
+        shift += 7;
+        if shift >= 32 {
+            return Err(eyre!("Delta-encoded number is too long"));
+        }
+    }
+}
+
+/// Write a single number using the delta encoding of the binary AND gate section.
+pub(crate) fn write_delta(writer: &mut impl std::io::Write, mut value: u32) -> std::io::Result<()> {
+    while value & !0x7f != 0 {
+        writer.write_all(&[(value & 0x7f) as u8 | 0x80])?;
+        value >>= 7;
     }
+    writer.write_all(&[value as u8])
 }
 
 /// An iterator over the records in an AIGER file.
 pub struct RecordsIter<R> {
-    lines: Lines<R>,
+    reader: R,
     header: Header,
     remaining_inputs: usize,
     remaining_latches: usize,
     remaining_outputs: usize,
+    remaining_bad: usize,
+    remaining_constraints: usize,
+    remaining_justice: usize,
+    remaining_fairness: usize,
     remaining_and_gates: usize,
+    /// Sizes of the justice properties, read all at once before their literals.
+    justice_sizes: Vec<usize>,
     comment: bool,
 }
 
 impl<R> RecordsIter<R> {
-    fn new(lines: Lines<R>, header: Header) -> RecordsIter<R> {
+    fn new(reader: R, header: Header) -> RecordsIter<R> {
         RecordsIter {
-            lines,
+            reader,
             remaining_inputs: header.i,
             remaining_latches: header.l,
             remaining_outputs: header.o,
+            remaining_bad: header.b,
+            remaining_constraints: header.c,
+            remaining_justice: header.j,
+            remaining_fairness: header.f,
             remaining_and_gates: header.a,
+            justice_sizes: Vec::new(),
             comment: false,
             header, // last to allow move
         }
     }
 
-    fn read_record(&mut self, line: &str) -> eyre::Result<Record> {
-        fn get_literals(line: &str) -> eyre::Result<Vec<Literal>> {
-            let mut literals = Vec::new();
-            for part in line.split(' ') {
-                let lit = part
-                    .parse::<u32>()
-                    .map_err(|_| eyre!("Invalid literal '{}', expected u32 number", part))?;
-                literals.push(Literal::new(lit));
-            }
-            Ok(literals)
-        }
+    /// The literal of the next implicitly defined variable in the binary format.
+    ///
+    /// Inputs, latches and AND gates are numbered consecutively, in that order.
+    fn implicit_literal(&self) -> Literal {
+        let defined = (self.header.i - self.remaining_inputs)
+            + (self.header.l - self.remaining_latches)
+            + (self.header.a - self.remaining_and_gates);
+        Literal::from_variable(defined as u32 + 1, false)
+    }
 
+    /// Whether all sections preceding the AND gates have been read.
+    fn is_and_gate_section(&self) -> bool {
+        self.remaining_inputs == 0
+            && self.remaining_latches == 0
+            && self.remaining_outputs == 0
+            && self.remaining_bad == 0
+            && self.remaining_constraints == 0
+            && self.remaining_justice == 0
+            && self.remaining_fairness == 0
+            && self.remaining_and_gates > 0
+    }
+
+    fn read_record(&mut self, line: &str) -> eyre::Result<Record> {
         if self.remaining_inputs > 0 {
             self.remaining_inputs -= 1;
             Record::parse_input(&get_literals(line)?)
                 .wrap_err_with(|| format!("Invalid input '{}'", line))
         } else if self.remaining_latches > 0 {
+            let mut literals = Vec::new();
+            if self.header.format == Format::Binary {
+                literals.push(self.implicit_literal());
+            }
+            literals.extend(get_literals(line)?);
             self.remaining_latches -= 1;
-            Record::parse_latch(&get_literals(line)?)
-                .wrap_err_with(|| format!("Invalid latch '{}'", line))
+            Record::parse_latch(&literals).wrap_err_with(|| format!("Invalid latch '{}'", line))
         } else if self.remaining_outputs > 0 {
             self.remaining_outputs -= 1;
             Record::parse_output(&get_literals(line)?)
                 .wrap_err_with(|| format!("Invalid output '{}'", line))
+        } else if self.remaining_bad > 0 {
+            self.remaining_bad -= 1;
+            Record::parse_bad(&get_literals(line)?)
+                .wrap_err_with(|| format!("Invalid bad state property '{}'", line))
+        } else if self.remaining_constraints > 0 {
+            self.remaining_constraints -= 1;
+            Record::parse_constraint(&get_literals(line)?)
+                .wrap_err_with(|| format!("Invalid invariant constraint '{}'", line))
+        } else if self.remaining_fairness > 0 {
+            self.remaining_fairness -= 1;
+            Record::parse_fairness(&get_literals(line)?)
+                .wrap_err_with(|| format!("Invalid fairness constraint '{}'", line))
         } else if self.remaining_and_gates > 0 {
             self.remaining_and_gates -= 1;
             Record::parse_and_gate(&get_literals(line)?)
@@ -369,23 +649,129 @@ impl<R> RecordsIter<R> {
     }
 }
 
+fn get_literals(line: &str) -> eyre::Result<Vec<Literal>> {
+    let mut literals = Vec::new();
+    for part in line.split(' ') {
+        let lit = part
+            .parse::<u32>()
+            .map_err(|_| eyre!("Invalid literal '{}', expected u32 number", part))?;
+        literals.push(Literal::new(lit));
+    }
+    Ok(literals)
+}
+
+impl<R: BufRead> RecordsIter<R> {
+    fn read_required_line(&mut self) -> eyre::Result<String> {
+        read_line(&mut self.reader)?.ok_or_else(|| eyre!("Unexpected end of input"))
+    }
+
+    /// Read the next justice property.
+    ///
+    /// The sizes of all justice properties precede their literals,
+    /// so the sizes are read on the first call.
+    fn read_justice(&mut self) -> eyre::Result<Record> {
+        if self.justice_sizes.is_empty() {
+            for _ in 0..self.header.j {
+                let line = self.read_required_line()?;
+                let size = line
+                    .parse::<usize>()
+                    .map_err(|_| eyre!("Invalid justice property size '{}'", line))?;
+                self.justice_sizes.push(size);
+            }
+        }
+
+        let size = self.justice_sizes[self.header.j - self.remaining_justice];
+        self.remaining_justice -= 1;
+        let mut literals = Vec::with_capacity(size);
+        for _ in 0..size {
+            let line = self.read_required_line()?;
+            let lit = get_literals(&line)?;
+            match lit[..] {
+                [lit] => literals.push(lit),
+                _ => return Err(eyre!("Invalid justice literal '{}'", line)),
+            }
+        }
+        Ok(Record::Justice { literals })
+    }
+
+    fn read_binary_and_gate(&mut self) -> eyre::Result<Record> {
+        let id = self.implicit_literal();
+        let delta0 = read_delta(&mut self.reader)?;
+        let delta1 = read_delta(&mut self.reader)?;
+        self.remaining_and_gates -= 1;
+
+        let left = id
+            .raw()
+            .checked_sub(delta0)
+            .ok_or_else(|| eyre!("Invalid delta {} for and gate {}", delta0, id.raw()))?;
+        let right = left
+            .checked_sub(delta1)
+            .ok_or_else(|| eyre!("Invalid delta {} for and gate {}", delta1, id.raw()))?;
+        if delta0 == 0 {
+            return Err(eyre!(
+                "And gate {} must be greater than its inputs",
+                id.raw()
+            ));
+        }
+
+        Ok(Record::AndGate {
+            id,
+            inputs: [Literal::new(left), Literal::new(right)],
+        })
+    }
+}
+
 impl<R: BufRead> Iterator for RecordsIter<R> {
     type Item = eyre::Result<Record>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.comment {
-            return None;
+            return match read_line(&mut self.reader) {
+                Ok(Some(text)) => Some(Ok(Record::Comment { text })),
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            };
+        }
+
+        if self.header.format == Format::Binary {
+            if self.remaining_inputs > 0 {
+                let id = self.implicit_literal();
+                self.remaining_inputs -= 1;
+                return Some(Ok(Record::Input { id }));
+            }
+            if self.is_and_gate_section() {
+                return Some(
+                    self.read_binary_and_gate()
+                        .wrap_err("Invalid binary and gate")
+                        .and_then(|r| r.validate(&self.header)),
+                );
+            }
+        }
+
+        if self.remaining_inputs == 0
+            && self.remaining_latches == 0
+            && self.remaining_outputs == 0
+            && self.remaining_bad == 0
+            && self.remaining_constraints == 0
+            && self.remaining_justice > 0
+        {
+            return Some(
+                self.read_justice()
+                    .wrap_err("Invalid justice property")
+                    .and_then(|r| r.validate(&self.header)),
+            );
         }
 
-        let line = match self.lines.next() {
-            Some(Ok(line)) => line,
-            Some(Err(e)) => return Some(Err(e.into())),
-            None => return None,
+        let line = match read_line(&mut self.reader) {
+            Ok(Some(line)) => line,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
         };
 
-        if line.starts_with('c') {
+        // Note: symbols for invariant constraints also start with 'c', e.g. 'c0 name'.
+        if line.starts_with('c') && !line[1..].starts_with(|c: char| c.is_ascii_digit()) {
             self.comment = true;
-            return None;
+            return self.next();
         }
 
         Some(
@@ -406,6 +792,7 @@ mod tests {
     fn test_parse_header() {
         let input = "aag 5 2 0 1 2";
         let header = input.parse::<Header>().unwrap();
+        assert_eq!(header.format, Format::Ascii);
         assert_eq!(header.m, 5);
         assert_eq!(header.i, 2);
         assert_eq!(header.l, 0);
@@ -413,6 +800,22 @@ mod tests {
         assert_eq!(header.a, 2);
     }
 
+    #[test]
+    fn test_parse_header_extended() {
+        let input = "aag 7 2 1 0 4 1 1 2 0";
+        let header = input.parse::<Header>().unwrap();
+        assert_eq!(header.a, 4);
+        assert_eq!(header.b, 1);
+        assert_eq!(header.c, 1);
+        assert_eq!(header.j, 2);
+        assert_eq!(header.f, 0);
+        assert_eq!(header.to_string(), "aag 7 2 1 0 4 1 1 2");
+
+        let header = "aag 1 1 0 1 0".parse::<Header>().unwrap();
+        assert_eq!(header.b, 0);
+        assert_eq!(header.to_string(), "aag 1 1 0 1 0");
+    }
+
     fn make_reader(input: &str) -> eyre::Result<Reader<&[u8]>> {
         Reader::new(input.as_bytes())
     }
@@ -538,4 +941,162 @@ mod tests {
         );
         assert_eq!(next(), None);
     }
+
+    #[test]
+    fn test_reader_binary() {
+        // Binary version of 'aag 5 2 1 1 2 / 2 / 4 / 6 9 / 11 / 8 6 3 / 10 9 4':
+        let mut input = b"aig 5 2 1 1 2\n9\n11\n".to_vec();
+        input.extend([2, 3, 1, 5]);
+        input.extend(b"i0 x\nc\ncomment\n");
+        let reader = Reader::new(input.as_slice()).unwrap();
+
+        let header = reader.header();
+        assert_eq!(header.format, Format::Binary);
+        assert_eq!(header.m, 5);
+
+        let records = reader.records().collect::<eyre::Result<Vec<_>>>().unwrap();
+        assert_eq!(
+            records,
+            vec![
+                Record::Input {
+                    id: Literal::new(2)
+                },
+                Record::Input {
+                    id: Literal::new(4)
+                },
+                Record::Latch {
+                    id: Literal::new(6),
+                    next: Literal::new(9),
+                    init: LatchInit::Zero,
+                },
+                Record::Output {
+                    id: Literal::new(11)
+                },
+                Record::AndGate {
+                    id: Literal::new(8),
+                    inputs: [Literal::new(6), Literal::new(3)]
+                },
+                Record::AndGate {
+                    id: Literal::new(10),
+                    inputs: [Literal::new(9), Literal::new(4)]
+                },
+                Record::Symbol {
+                    type_spec: SymbolType::Input,
+                    position: 0,
+                    symbol: "x".to_string()
+                },
+                Record::Comment {
+                    text: "comment".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delta_encoding() {
+        for value in [0, 1, 127, 128, 16383, 16384, u32::MAX] {
+            let mut buf = Vec::new();
+            write_delta(&mut buf, value).unwrap();
+            assert_eq!(read_delta(&mut buf.as_slice()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_reader_properties() {
+        let input = indoc! {"
+            aag 3 1 1 0 1 1 1 2 1
+            2
+            4 6
+            7
+            3
+            2
+            1
+            6
+            2
+            4
+            5
+            6 2 4
+            b0 bad
+            c0 constraint
+            j1 justice
+            c
+            comment
+        "};
+        let reader = make_reader(input).unwrap();
+        let records = reader.records().collect::<eyre::Result<Vec<_>>>().unwrap();
+        let lit = Literal::new;
+        assert_eq!(
+            records,
+            vec![
+                Record::Input { id: lit(2) },
+                Record::Latch {
+                    id: lit(4),
+                    next: lit(6),
+                    init: LatchInit::Zero,
+                },
+                Record::Bad { id: lit(7) },
+                Record::Constraint { id: lit(3) },
+                Record::Justice {
+                    literals: vec![lit(6), lit(2)]
+                },
+                Record::Justice {
+                    literals: vec![lit(4)]
+                },
+                Record::Fairness { id: lit(5) },
+                Record::AndGate {
+                    id: lit(6),
+                    inputs: [lit(2), lit(4)]
+                },
+                Record::Symbol {
+                    type_spec: SymbolType::Bad,
+                    position: 0,
+                    symbol: "bad".to_string()
+                },
+                Record::Symbol {
+                    type_spec: SymbolType::Constraint,
+                    position: 0,
+                    symbol: "constraint".to_string()
+                },
+                Record::Symbol {
+                    type_spec: SymbolType::Justice,
+                    position: 1,
+                    symbol: "justice".to_string()
+                },
+                Record::Comment {
+                    text: "comment".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reader_latch_reset() {
+        let input = indoc! {"
+            aag 3 0 3 0 0
+            2 3
+            4 5 1
+            6 7 6
+        "};
+        let reader = make_reader(input).unwrap();
+        let records = reader.records().collect::<eyre::Result<Vec<_>>>().unwrap();
+        let inits = records
+            .iter()
+            .map(|r| match r {
+                Record::Latch { init, .. } => *init,
+                _ => panic!("Expected latch, got {:?}", r),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inits,
+            vec![LatchInit::Zero, LatchInit::One, LatchInit::Nondet]
+        );
+
+        let input = indoc! {"
+            aag 2 0 2 0 0
+            2 3
+            4 5 2
+        "};
+        let reader = make_reader(input).unwrap();
+        assert!(reader.records().any(|r| r.is_err()));
+    }
 }
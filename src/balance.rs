@@ -0,0 +1,175 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::aig::Aig;
+use crate::builder::{image, AigBuilder};
+use crate::reference::Ref;
+
+impl Aig {
+    /// Balance the AIG to minimise its logic depth, preserving the function.
+    ///
+    /// AND gates are grouped into maximal supergates, expanding arguments which are
+    /// non-complemented AND gates with a single fanout. Each supergate is rebuilt as a tree
+    /// which always combines the two leaves with the lowest levels first, so that late
+    /// arriving leaves end up close to the root.
+    ///
+    /// Dangling gates are removed (see [`Aig::remove_dangling`]).
+    pub fn balance(&self) -> Aig {
+        let n = self.max_id() as usize + 1;
+
+        // Count the references of each node, and find the gates which are used only once,
+        // through a non-complemented edge of another gate: these are inside supergates.
+        let mut references = vec![0u32; n];
+        let mut internal = vec![false; n];
+        for &id in self.topological_order() {
+            for r in self.gate(id).args {
+                references[r.id() as usize] += 1;
+                internal[r.id() as usize] = !r.is_negated();
+            }
+        }
+        for r in self.root_refs() {
+            references[r.id() as usize] += 1;
+        }
+        let is_internal =
+            |r: Ref| !r.is_negated() && self.is_gate(r.id()) && references[r.id() as usize] == 1;
+
+        let mut builder = AigBuilder::new();
+        let mut map = vec![Ref::FALSE; n];
+        for &id in self.inputs() {
+            map[id as usize] = builder.input();
+        }
+        for &id in self.latches() {
+            map[id as usize] = builder.latch(self.latch(id).init);
+        }
+
+        // Levels of the nodes in the builder, indexed by id:
+        let mut levels = vec![0u32; builder.aig().max_id() as usize + 1];
+        let mut leaves = Vec::new();
+        let mut stack = Vec::new();
+        for &id in self.topological_order() {
+            if references[id as usize] == 1 && internal[id as usize] {
+                continue;
+            }
+
+            // Collect the leaves of the supergate:
+            leaves.clear();
+            stack.push(Ref::positive(id));
+            while let Some(r) = stack.pop() {
+                if r.id() == id || is_internal(r) {
+                    stack.extend(self.gate(r.id()).args);
+                } else {
+                    leaves.push(image(&map, r));
+                }
+            }
+
+            // Combine the leaves with the lowest levels first:
+            let mut queue = leaves
+                .iter()
+                .map(|&r| Reverse((levels[r.id() as usize], r.raw())))
+                .collect::<BinaryHeap<_>>();
+            let root = loop {
+                let Reverse((_, a)) = queue.pop().expect("Supergate must have leaves");
+                let Some(Reverse((_, b))) = queue.pop() else {
+                    break Ref::from_raw(a);
+                };
+                let r = builder.and(Ref::from_raw(a), Ref::from_raw(b));
+                let index = r.id() as usize;
+                if index >= levels.len() {
+                    levels.resize(index + 1, 0);
+                    let [x, y] = builder.aig().gate(r.id()).args;
+                    levels[index] = 1 + levels[x.id() as usize].max(levels[y.id() as usize]);
+                }
+                queue.push(Reverse((levels[index], r.raw())));
+            };
+            map[id as usize] = root;
+        }
+
+        for &id in self.latches() {
+            builder.set_latch_next(map[id as usize], image(&map, self.latch(id).next));
+        }
+        builder.import_properties(self, &map);
+        let aig = self.finish_rebuild(builder.build(), None);
+        log::debug!(
+            "Balancing changed depth from {} to {}",
+            self.depth(),
+            aig.depth()
+        );
+        aig
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cec::Pairing;
+    use crate::node::LatchInit;
+
+    #[test]
+    fn test_balance_chain() {
+        // ((((x0 & x1) & x2) & ...) & x7)
+        let mut builder = AigBuilder::new();
+        let x = builder.inputs(8);
+        let mut chain = x[0];
+        for &xi in &x[1..] {
+            chain = builder.and(chain, xi);
+        }
+        builder.output(chain);
+        let aig = builder.build();
+        assert_eq!(aig.depth(), 7);
+
+        let balanced = aig.balance();
+        assert_eq!(balanced.depth(), 3);
+        assert_eq!(balanced.and_gates().count(), 7);
+        let result = aig.check_equivalence(&balanced, Pairing::Position).unwrap();
+        assert!(result.is_equivalent());
+    }
+
+    #[test]
+    fn test_balance_boundaries() {
+        // Complemented edges and shared gates bound the supergates.
+        let mut builder = AigBuilder::new();
+        let x = builder.inputs(6);
+        let a = builder.and(x[0], x[1]);
+        let b = builder.and(a, x[2]);
+        let c = builder.and(b, x[3]);
+        let d = builder.and(-c, x[4]);
+        let e = builder.and(d, x[5]);
+        let f = builder.and(e, b);
+        builder.output(f);
+        builder.output(-d);
+        let aig = builder.build();
+
+        let balanced = aig.balance();
+        assert!(balanced.depth() <= aig.depth());
+        let result = aig.check_equivalence(&balanced, Pairing::Position).unwrap();
+        assert!(result.is_equivalent());
+    }
+
+    #[test]
+    fn test_balance_arrival() {
+        // A deep leaf is combined last.
+        let mut builder = AigBuilder::new();
+        let x = builder.inputs(8);
+        let deep = builder.xor_many(&x[..4]);
+        let l = builder.latch(LatchInit::Zero);
+        let t = builder.and(x[4], x[5]);
+        let u = builder.and(t, deep);
+        let v = builder.and(u, x[6]);
+        let w = builder.and(v, l);
+        builder.set_latch_next(l, -w);
+        builder.output(w);
+        let aig = builder.build();
+
+        let balanced = aig.balance();
+        // The other leaves are combined while the deep one is computed:
+        assert_eq!(balanced.depth(), aig.level(deep.id()) + 1);
+
+        let inputs = (0..6)
+            .map(|k| (0..8).map(|i| (k * 37 + i * 11) % 3 != 0).collect())
+            .collect::<Vec<Vec<bool>>>();
+        let expected = aig.simulate_sequential(None, &inputs).unwrap();
+        let actual = balanced.simulate_sequential(None, &inputs).unwrap();
+        assert_eq!(actual.outputs, expected.outputs);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::aig::Aig;
+use crate::builder::{image, AigBuilder};
+use crate::cnf::CnfEncoder;
+use crate::node::LatchInit;
+use crate::reference::Ref;
+use crate::sat::{SatSolver, SolveResult, Solver};
+use crate::witness::{Property, Witness};
+
+impl Aig {
+    /// Unroll the sequential AIG into a combinational one over the given number of frames.
+    ///
+    /// Latches start from their reset values. The inputs of the unrolled AIG are the
+    /// initial values of the uninitialised latches (in latch order), followed by the
+    /// inputs of each frame. For each frame `f` and each safety property `p`
+    /// (see [`Aig::safety_properties`]), output `f * P + p` is true iff `p` is violated
+    /// in frame `f` while all invariant constraints hold in frames `0..=f`.
+    pub fn unroll(&self, frames: usize) -> Aig {
+        let mut builder = AigBuilder::new();
+        let mut map = vec![Ref::FALSE; self.max_id() as usize + 1];
+        for &id in self.latches() {
+            map[id as usize] = match self.latch(id).init {
+                LatchInit::Zero => Ref::FALSE,
+                LatchInit::One => Ref::TRUE,
+                LatchInit::Nondet => builder.input(),
+            };
+        }
+
+        let mut valid = Ref::TRUE;
+        for _ in 0..frames {
+            for &id in self.inputs() {
+                map[id as usize] = builder.input();
+            }
+            builder.import(self, &mut map);
+
+            let constraints = self
+                .constraints()
+                .iter()
+                .map(|&c| image(&map, c))
+                .collect::<Vec<_>>();
+            let holds = builder.and_many(&constraints);
+            valid = builder.and(valid, holds);
+            for &p in self.safety_properties() {
+                let bad = builder.and(valid, image(&map, p));
+                builder.output(bad);
+            }
+
+            let next = self
+                .latches()
+                .iter()
+                .map(|&id| image(&map, self.latch(id).next))
+                .collect::<Vec<_>>();
+            for (&id, &r) in self.latches().iter().zip(&next) {
+                map[id as usize] = r;
+            }
+        }
+        builder.build()
+    }
+}
+
+/// Result of bounded model checking.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BmcResult {
+    /// A safety property is violated, the witness can be replayed with [`Aig::check_witness`].
+    Counterexample(Witness),
+    /// No property is violated in the given number of frames.
+    Bounded(usize),
+}
+
+/// Incremental time-frame expansion of a sequential AIG in a SAT solver:
+/// one [`CnfEncoder`] per frame, with latches bound to the next-state literals
+/// of the previous frame. Invariant constraints are asserted in every frame.
+pub(crate) struct Unrolling<'a> {
+    aig: &'a Aig,
+    /// Whether the first frame starts from the reset state or from an arbitrary one.
+    from_reset: bool,
+    frames: Vec<CnfEncoder<'a>>,
+}
+
+impl<'a> Unrolling<'a> {
+    pub(crate) fn new(aig: &'a Aig, from_reset: bool) -> Self {
+        Self {
+            aig,
+            from_reset,
+            frames: Vec::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Add the next frame, returning its index.
+    pub(crate) fn add_frame<S: SatSolver + ?Sized>(&mut self, solver: &mut S) -> usize {
+        let aig = self.aig;
+        let mut frame = CnfEncoder::new(aig);
+        match self.frames.last_mut() {
+            None => {
+                if self.from_reset {
+                    let zero = frame.encode(solver, Ref::FALSE);
+                    for &id in aig.latches() {
+                        match aig.latch(id).init {
+                            LatchInit::Zero => frame.bind(id, zero),
+                            LatchInit::One => frame.bind(id, -zero),
+                            LatchInit::Nondet => {}
+                        }
+                    }
+                }
+            }
+            Some(previous) => {
+                for &id in aig.latches() {
+                    let next = previous.encode(solver, aig.latch(id).next);
+                    frame.bind(id, next);
+                }
+            }
+        }
+        for &c in aig.constraints() {
+            let lit = frame.encode(solver, c);
+            solver.add_clause(&[lit]);
+        }
+        self.frames.push(frame);
+        self.frames.len() - 1
+    }
+
+    /// Literal of the reference in the given frame.
+    pub(crate) fn encode<S: SatSolver + ?Sized>(
+        &mut self,
+        solver: &mut S,
+        frame: usize,
+        r: Ref,
+    ) -> i32 {
+        self.frames[frame].encode(solver, r)
+    }
+
+    /// Literals of the latches in the given frame.
+    pub(crate) fn state<S: SatSolver + ?Sized>(
+        &mut self,
+        solver: &mut S,
+        frame: usize,
+    ) -> Vec<i32> {
+        let aig = self.aig;
+        aig.latches()
+            .iter()
+            .map(|&id| self.frames[frame].encode(solver, Ref::positive(id)))
+            .collect()
+    }
+
+    /// Build the witness for the property from the model of the last satisfiable call.
+    pub(crate) fn witness<S: SatSolver + ?Sized>(&self, solver: &S, property: Property) -> Witness {
+        let value = |frame: &CnfEncoder, id: u32| {
+            frame
+                .lit(Ref::positive(id))
+                .and_then(|lit| solver.value(lit))
+                .unwrap_or(false)
+        };
+        let init = self
+            .aig
+            .latches()
+            .iter()
+            .map(|&id| value(&self.frames[0], id))
+            .collect();
+        let inputs = self
+            .frames
+            .iter()
+            .map(|frame| {
+                self.aig
+                    .inputs()
+                    .iter()
+                    .map(|&id| value(frame, id))
+                    .collect()
+            })
+            .collect();
+        Witness::counterexample(property, init, inputs)
+    }
+}
+
+/// Check whether some of the literals can be true, under an activation literal
+/// which is retired afterwards. Returns the index of a true literal.
+pub(crate) fn solve_any<S: SatSolver + ?Sized>(solver: &mut S, lits: &[i32]) -> Option<usize> {
+    let activation = solver.new_var();
+    let mut clause = vec![-activation];
+    clause.extend(lits);
+    solver.add_clause(&clause);
+    let result = solver.solve_with(&[activation]);
+    solver.add_clause(&[-activation]);
+    match result {
+        SolveResult::Sat => Some(
+            lits.iter()
+                .position(|&lit| solver.value(lit) == Some(true))
+                .expect("Some literal must be true"),
+        ),
+        SolveResult::Unsat => None,
+    }
+}
+
+impl Aig {
+    /// Bounded model checking of the safety properties (see [`Aig::safety_properties`])
+    /// with the built-in SAT solver, searching up to `max_frames` frames.
+    pub fn bmc(&self, max_frames: usize) -> BmcResult {
+        self.bmc_with(max_frames, &mut Solver::new())
+    }
+
+    /// Bounded model checking with the given SAT solver.
+    ///
+    /// Frames are added incrementally: each depth reuses the encoding of the previous ones,
+    /// and the shortest counterexample is found first.
+    pub fn bmc_with<S: SatSolver + ?Sized>(&self, max_frames: usize, solver: &mut S) -> BmcResult {
+        let mut unrolling = Unrolling::new(self, true);
+        for _ in 0..max_frames {
+            let frame = unrolling.add_frame(solver);
+            let bad = self
+                .safety_properties()
+                .iter()
+                .map(|&p| unrolling.encode(solver, frame, p))
+                .collect::<Vec<_>>();
+            if let Some(index) = solve_any(solver, &bad) {
+                log::debug!("BMC found a counterexample in frame {}", frame);
+                return BmcResult::Counterexample(unrolling.witness(solver, Property::Bad(index)));
+            }
+            log::debug!("BMC: no counterexample in frame {}", frame);
+        }
+        BmcResult::Bounded(max_frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unroll() {
+        // Toggle flip-flop: output 0 is Q, output 1 is ~Q.
+        let aig = Aig::from_file("data/examples/toggle.aag").unwrap();
+        let unrolled = aig.unroll(3);
+        assert!(unrolled.latches().is_empty());
+        assert!(unrolled.inputs().is_empty());
+        assert_eq!(unrolled.outputs().len(), 6);
+
+        let trace = aig
+            .simulate_sequential(None, &[vec![], vec![], vec![]])
+            .unwrap();
+        let expected = trace.outputs.concat();
+        let actual = unrolled
+            .outputs()
+            .iter()
+            .map(|r| r.get_const().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_bmc_toggle_re() {
+        // Inputs of toggle-re are [enable, reset], output 'Q' serves as the bad property.
+        let mut aig = Aig::from_file("data/examples/toggle-re.aag").unwrap();
+        aig.add_bad(aig.outputs()[0]);
+        match aig.bmc(5) {
+            BmcResult::Counterexample(witness) => {
+                assert_eq!(witness.inputs.len(), 2);
+                assert_eq!(witness.properties, vec![Property::Bad(0)]);
+                assert_eq!(aig.check_witness(&witness).unwrap(), 1);
+            }
+            result => panic!("Expected a counterexample, got {:?}", result),
+        }
+
+        // Never enabled:
+        aig.add_constraint(-Ref::positive(aig.inputs()[0]));
+        assert_eq!(aig.bmc(5), BmcResult::Bounded(5));
+    }
+
+    #[test]
+    fn test_bmc_counter() {
+        // 3-bit counter, starting from zero, is bad when it reaches 5.
+        let mut builder = AigBuilder::new();
+        let bits = (0..3)
+            .map(|_| builder.latch(LatchInit::Zero))
+            .collect::<Vec<_>>();
+        let one = [Ref::TRUE, Ref::FALSE, Ref::FALSE];
+        let (next, _) = builder.add(&bits, &one, Ref::FALSE);
+        for (&bit, &next) in bits.iter().zip(&next) {
+            builder.set_latch_next(bit, next);
+        }
+        let five = builder.and_many(&[bits[0], -bits[1], bits[2]]);
+        builder.bad(five);
+        let aig = builder.build();
+
+        assert_eq!(aig.bmc(5), BmcResult::Bounded(5));
+        match aig.bmc(10) {
+            BmcResult::Counterexample(witness) => {
+                assert_eq!(witness.inputs.len(), 6);
+                assert_eq!(aig.check_witness(&witness).unwrap(), 5);
+            }
+            result => panic!("Expected a counterexample, got {:?}", result),
+        }
+
+        // The unrolled AIG violates the property in frame 5 only:
+        let unrolled = aig.unroll(7);
+        let violated = unrolled
+            .outputs()
+            .iter()
+            .map(|r| r.get_const().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            violated,
+            vec![false, false, false, false, false, true, false]
+        );
+    }
+}
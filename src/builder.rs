@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+
+use crate::aig::Aig;
+use crate::node::LatchInit;
+use crate::reference::Ref;
+
+/// Builder of AIGs with structural hashing.
+///
+/// The builder allocates fresh ids for new nodes and never creates two AND gates
+/// with the same (unordered) pair of arguments. Trivial gates are folded on the fly:
+/// `x & 0 = 0`, `x & 1 = x`, `x & x = x` and `x & ~x = 0`.
+///
+/// Word-level helpers take and return bits in little-endian order (LSB first).
+pub struct AigBuilder {
+    aig: Aig,
+    next_id: u32,
+    strash: HashMap<[Ref; 2], u32>,
+}
+
+impl AigBuilder {
+    pub fn new() -> Self {
+        Self {
+            aig: Aig::default(),
+            next_id: 1,
+            strash: HashMap::new(),
+        }
+    }
+
+    /// Continue building on top of an existing AIG.
+    ///
+    /// Existing AND gates are registered in the structural hash table,
+    /// but they are not deduplicated.
+    pub fn from_aig(aig: Aig) -> Self {
+        let mut strash = HashMap::new();
+        for gate in aig.and_gates() {
+            strash.entry(normalize(gate.args)).or_insert(gate.id);
+        }
+        Self {
+            next_id: aig.max_id() + 1,
+            aig,
+            strash,
+        }
+    }
+
+    pub fn aig(&self) -> &Aig {
+        &self.aig
+    }
+
+    pub fn build(self) -> Aig {
+        self.aig
+    }
+
+    fn fresh_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    pub fn input(&mut self) -> Ref {
+        let id = self.fresh_id();
+        self.aig.add_input(id);
+        Ref::positive(id)
+    }
+
+    pub fn inputs(&mut self, n: usize) -> Vec<Ref> {
+        (0..n).map(|_| self.input()).collect()
+    }
+
+    /// Create a latch with the given initial value.
+    ///
+    /// The next state is initially [`Ref::FALSE`], use [`AigBuilder::set_latch_next`] to set it.
+    pub fn latch(&mut self, init: LatchInit) -> Ref {
+        let id = self.fresh_id();
+        self.aig.add_latch(id, Ref::FALSE, init);
+        Ref::positive(id)
+    }
+
+    pub fn set_latch_next(&mut self, latch: Ref, next: Ref) {
+        assert!(!latch.is_negated(), "Latch reference must be positive");
+        self.aig.set_latch_next(latch.id(), next);
+    }
+
+    pub fn output(&mut self, output: Ref) {
+        self.aig.add_output(output);
+    }
+
+    pub fn bad(&mut self, bad: Ref) {
+        self.aig.add_bad(bad);
+    }
+
+    pub fn constraint(&mut self, constraint: Ref) {
+        self.aig.add_constraint(constraint);
+    }
+
+    /// Copy the AND gates of another AIG into the builder.
+    ///
+    /// `map` is indexed by the node ids of `aig` and must already contain the images
+    /// of its inputs and latches. The images of AND gates are filled in.
+    pub fn import(&mut self, aig: &Aig, map: &mut [Ref]) {
+        assert!(map.len() > aig.max_id() as usize);
+        map[0] = Ref::FALSE;
+        for &id in aig.topological_order() {
+            let [left, right] = aig.gate(id).args;
+            let left = image(map, left);
+            let right = image(map, right);
+            map[id as usize] = self.and(left, right);
+        }
+    }
+
+    /// Copy the outputs and properties of another AIG, mapped as in [`AigBuilder::import`].
+    pub(crate) fn import_properties(&mut self, aig: &Aig, map: &[Ref]) {
+        for &r in aig.outputs() {
+            self.aig.add_output(image(map, r));
+        }
+        for &r in aig.bad() {
+            self.aig.add_bad(image(map, r));
+        }
+        for &r in aig.constraints() {
+            self.aig.add_constraint(image(map, r));
+        }
+        for justice in aig.justice() {
+            self.aig
+                .add_justice(justice.iter().map(|&r| image(map, r)).collect());
+        }
+        for &r in aig.fairness() {
+            self.aig.add_fairness(image(map, r));
+        }
+    }
+}
+
+impl Default for AigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Image of the reference under the node mapping used by [`AigBuilder::import`].
+pub(crate) fn image(map: &[Ref], r: Ref) -> Ref {
+    Ref::from_raw(map[r.id() as usize].raw() ^ r.is_negated() as u32)
+}
+
+/// Order the arguments of an AND gate, larger literal first.
+pub(crate) const fn normalize([a, b]: [Ref; 2]) -> [Ref; 2] {
+    if a.raw() >= b.raw() {
+        [a, b]
+    } else {
+        [b, a]
+    }
+}
+
+// Basic gates
+impl AigBuilder {
+    pub fn and(&mut self, a: Ref, b: Ref) -> Ref {
+        // Constant folding and trivial cases:
+        if a.is_false() || b.is_false() {
+            return Ref::FALSE;
+        }
+        if a.is_true() {
+            return b;
+        }
+        if b.is_true() {
+            return a;
+        }
+        if a == b {
+            return a;
+        }
+        if a == -b {
+            return Ref::FALSE;
+        }
+
+        let args = normalize([a, b]);
+        if let Some(&id) = self.strash.get(&args) {
+            return Ref::positive(id);
+        }
+        let id = self.fresh_id();
+        self.aig.add_and_gate(id, args);
+        self.strash.insert(args, id);
+        Ref::positive(id)
+    }
+
+    pub fn or(&mut self, a: Ref, b: Ref) -> Ref {
+        -self.and(-a, -b)
+    }
+
+    pub fn nand(&mut self, a: Ref, b: Ref) -> Ref {
+        -self.and(a, b)
+    }
+
+    pub fn nor(&mut self, a: Ref, b: Ref) -> Ref {
+        self.and(-a, -b)
+    }
+
+    pub fn implies(&mut self, a: Ref, b: Ref) -> Ref {
+        self.or(-a, b)
+    }
+
+    pub fn xor(&mut self, a: Ref, b: Ref) -> Ref {
+        let x = self.and(a, -b);
+        let y = self.and(-a, b);
+        self.or(x, y)
+    }
+
+    pub fn xnor(&mut self, a: Ref, b: Ref) -> Ref {
+        -self.xor(a, b)
+    }
+
+    /// If-then-else: `cond ? then : other`.
+    pub fn mux(&mut self, cond: Ref, then: Ref, other: Ref) -> Ref {
+        if then == other {
+            return then;
+        }
+        let x = self.and(cond, then);
+        let y = self.and(-cond, other);
+        self.or(x, y)
+    }
+
+    /// Majority of three.
+    pub fn maj(&mut self, a: Ref, b: Ref, c: Ref) -> Ref {
+        let ab = self.and(a, b);
+        let a_or_b = self.or(a, b);
+        let c_ab = self.and(c, a_or_b);
+        self.or(ab, c_ab)
+    }
+
+    /// Conjunction of all arguments, built as a balanced tree.
+    /// Returns [`Ref::TRUE`] for an empty slice.
+    pub fn and_many(&mut self, args: &[Ref]) -> Ref {
+        match args {
+            [] => Ref::TRUE,
+            [a] => *a,
+            _ => {
+                let (left, right) = args.split_at(args.len() / 2);
+                let left = self.and_many(left);
+                let right = self.and_many(right);
+                self.and(left, right)
+            }
+        }
+    }
+
+    /// Disjunction of all arguments, built as a balanced tree.
+    /// Returns [`Ref::FALSE`] for an empty slice.
+    pub fn or_many(&mut self, args: &[Ref]) -> Ref {
+        let negated = args.iter().map(|&a| -a).collect::<Vec<_>>();
+        -self.and_many(&negated)
+    }
+
+    /// Parity of all arguments.
+    /// Returns [`Ref::FALSE`] for an empty slice.
+    pub fn xor_many(&mut self, args: &[Ref]) -> Ref {
+        match args {
+            [] => Ref::FALSE,
+            [a] => *a,
+            _ => {
+                let (left, right) = args.split_at(args.len() / 2);
+                let left = self.xor_many(left);
+                let right = self.xor_many(right);
+                self.xor(left, right)
+            }
+        }
+    }
+}
+
+// Word-level operations
+impl AigBuilder {
+    /// Full adder, returns `(sum, carry)`.
+    pub fn full_adder(&mut self, a: Ref, b: Ref, carry: Ref) -> (Ref, Ref) {
+        let ab = self.xor(a, b);
+        let sum = self.xor(ab, carry);
+        let carry = self.maj(a, b, carry);
+        (sum, carry)
+    }
+
+    /// Ripple-carry adder, returns the sum and the carry out.
+    pub fn add(&mut self, a: &[Ref], b: &[Ref], carry: Ref) -> (Vec<Ref>, Ref) {
+        assert_eq!(a.len(), b.len(), "Words must have the same width");
+        let mut carry = carry;
+        let mut sum = Vec::with_capacity(a.len());
+        for (&x, &y) in a.iter().zip(b) {
+            let (s, c) = self.full_adder(x, y, carry);
+            sum.push(s);
+            carry = c;
+        }
+        (sum, carry)
+    }
+
+    /// Subtraction `a - b` modulo `2^n`, returns the difference and the carry out,
+    /// which is set iff `a >= b`.
+    pub fn sub(&mut self, a: &[Ref], b: &[Ref]) -> (Vec<Ref>, Ref) {
+        let not_b = b.iter().map(|&x| -x).collect::<Vec<_>>();
+        self.add(a, &not_b, Ref::TRUE)
+    }
+
+    pub fn mux_word(&mut self, cond: Ref, then: &[Ref], other: &[Ref]) -> Vec<Ref> {
+        assert_eq!(then.len(), other.len(), "Words must have the same width");
+        then.iter()
+            .zip(other)
+            .map(|(&t, &e)| self.mux(cond, t, e))
+            .collect()
+    }
+
+    /// Equality of two words.
+    pub fn eq(&mut self, a: &[Ref], b: &[Ref]) -> Ref {
+        assert_eq!(a.len(), b.len(), "Words must have the same width");
+        let bits = a
+            .iter()
+            .zip(b)
+            .map(|(&x, &y)| self.xnor(x, y))
+            .collect::<Vec<_>>();
+        self.and_many(&bits)
+    }
+
+    /// Unsigned comparison `a < b`.
+    pub fn ult(&mut self, a: &[Ref], b: &[Ref]) -> Ref {
+        assert_eq!(a.len(), b.len(), "Words must have the same width");
+        // a < b iff there is no carry out of a + ~b + 1.
+        let mut carry = Ref::TRUE;
+        for (&x, &y) in a.iter().zip(b) {
+            carry = self.maj(x, -y, carry);
+        }
+        -carry
+    }
+
+    /// Unsigned comparison `a <= b`.
+    pub fn ule(&mut self, a: &[Ref], b: &[Ref]) -> Ref {
+        -self.ult(b, a)
+    }
+
+    /// Signed (two's complement) comparison `a < b`.
+    pub fn slt(&mut self, a: &[Ref], b: &[Ref]) -> Ref {
+        assert_eq!(a.len(), b.len(), "Words must have the same width");
+        assert!(!a.is_empty(), "Words must be non-empty");
+        // Flipping the sign bits reduces signed comparison to unsigned one.
+        let n = a.len() - 1;
+        let mut a = a.to_vec();
+        let mut b = b.to_vec();
+        a[n] = -a[n];
+        b[n] = -b[n];
+        self.ult(&a, &b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_ref(aig: &Aig, inputs: Vec<bool>, r: Ref) -> bool {
+        let values = aig.eval(inputs);
+        match r.get_const() {
+            Some(c) => c,
+            None => values[&r.id()] ^ r.is_negated(),
+        }
+    }
+
+    fn to_bits(x: u32, n: usize) -> Vec<bool> {
+        (0..n).map(|i| (x >> i) & 1 != 0).collect()
+    }
+
+    #[test]
+    fn test_strash() {
+        let mut builder = AigBuilder::new();
+        let a = builder.input();
+        let b = builder.input();
+        let x = builder.and(a, -b);
+        let y = builder.and(-b, a);
+        assert_eq!(x, y);
+        assert_eq!(builder.and(a, a), a);
+        assert_eq!(builder.and(a, -a), Ref::FALSE);
+        assert_eq!(builder.and(a, Ref::TRUE), a);
+        assert_eq!(builder.and(Ref::FALSE, b), Ref::FALSE);
+        assert_eq!(builder.or(a, Ref::TRUE), Ref::TRUE);
+        assert_eq!(builder.xor(a, a), Ref::FALSE);
+        assert_eq!(builder.xor(a, -a), Ref::TRUE);
+        assert_eq!(builder.aig().and_gates().count(), 1);
+    }
+
+    #[test]
+    fn test_gates() {
+        let mut builder = AigBuilder::new();
+        let xs = builder.inputs(3);
+        let xor = builder.xor(xs[0], xs[1]);
+        let mux = builder.mux(xs[0], xs[1], xs[2]);
+        let maj = builder.maj(xs[0], xs[1], xs[2]);
+        let or = builder.or_many(&xs);
+        let and = builder.and_many(&xs);
+        let parity = builder.xor_many(&xs);
+        let aig = builder.build();
+
+        for x in 0..8 {
+            let v = to_bits(x, 3);
+            let eval = |r| eval_ref(&aig, v.clone(), r);
+            assert_eq!(eval(xor), v[0] ^ v[1]);
+            assert_eq!(eval(mux), if v[0] { v[1] } else { v[2] });
+            assert_eq!(eval(maj), (v[0] as u8 + v[1] as u8 + v[2] as u8) >= 2);
+            assert_eq!(eval(or), v[0] || v[1] || v[2]);
+            assert_eq!(eval(and), v[0] && v[1] && v[2]);
+            assert_eq!(eval(parity), v[0] ^ v[1] ^ v[2]);
+        }
+    }
+
+    #[test]
+    fn test_word_operations() {
+        const N: usize = 3;
+        let mut builder = AigBuilder::new();
+        let a = builder.inputs(N);
+        let b = builder.inputs(N);
+        let (sum, carry) = builder.add(&a, &b, Ref::FALSE);
+        let (diff, _) = builder.sub(&a, &b);
+        let eq = builder.eq(&a, &b);
+        let ult = builder.ult(&a, &b);
+        let ule = builder.ule(&a, &b);
+        let slt = builder.slt(&a, &b);
+        let aig = builder.build();
+
+        let signed = |x: u32| ((x << (32 - N)) as i32) >> (32 - N);
+        for x in 0..(1 << N) {
+            for y in 0..(1 << N) {
+                let mut v = to_bits(x, N);
+                v.extend(to_bits(y, N));
+                let eval = |r| eval_ref(&aig, v.clone(), r);
+                let word = |bits: &[Ref]| {
+                    bits.iter()
+                        .enumerate()
+                        .map(|(i, &r)| (eval(r) as u32) << i)
+                        .sum::<u32>()
+                };
+                assert_eq!(word(&sum) + ((eval(carry) as u32) << N), x + y);
+                assert_eq!(word(&diff), x.wrapping_sub(y) % (1 << N));
+                assert_eq!(eval(eq), x == y);
+                assert_eq!(eval(ult), x < y);
+                assert_eq!(eval(ule), x <= y);
+                assert_eq!(eval(slt), signed(x) < signed(y));
+            }
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use eyre::eyre;
+
+use crate::aig::Aig;
+use crate::aiger::SymbolType;
+use crate::builder::{image, AigBuilder};
+use crate::reference::Ref;
+use crate::sat::{SatSolver, SolveResult, Solver};
+use crate::sim::SequentialSimulator;
+
+/// How inputs and outputs of two AIGs are paired.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Pairing {
+    /// The `i`-th input (output) of one AIG corresponds to the `i`-th input (output) of the other.
+    Position,
+    /// Inputs and outputs are paired by their symbol names.
+    Name,
+}
+
+/// Result of a combinational equivalence check.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CecResult {
+    Equivalent,
+    /// The AIGs differ on the given input vector, in the order of the inputs of the first AIG.
+    /// `output` is the index of a differing output of the first AIG.
+    NotEquivalent {
+        inputs: Vec<bool>,
+        output: usize,
+    },
+}
+
+impl CecResult {
+    pub fn is_equivalent(&self) -> bool {
+        matches!(self, CecResult::Equivalent)
+    }
+}
+
+/// For each element of `a`, find the position of the element of `b` with the same name.
+fn pair_by_name(
+    a: &Aig,
+    b: &Aig,
+    type_spec: SymbolType,
+    count_a: usize,
+    count_b: usize,
+) -> eyre::Result<Vec<usize>> {
+    let kind = match type_spec {
+        SymbolType::Input => "Input",
+        _ => "Output",
+    };
+    if count_a != count_b {
+        return Err(eyre!("{} counts differ: {} vs {}", kind, count_a, count_b));
+    }
+    (0..count_a)
+        .map(|i| {
+            let name = a
+                .symbols()
+                .name(type_spec, i)
+                .ok_or_else(|| eyre!("{} {} of the first AIG has no name", kind, i))?;
+            if a.symbols().positions(type_spec, name).nth(1).is_some() {
+                return Err(eyre!("{} '{}' is ambiguous in the first AIG", kind, name));
+            }
+            let mut positions = b.symbols().positions(type_spec, name);
+            match (positions.next(), positions.next()) {
+                (Some(j), None) if j < count_b => Ok(j),
+                (Some(_), Some(_)) => {
+                    Err(eyre!("{} '{}' is ambiguous in the second AIG", kind, name))
+                }
+                _ => Err(eyre!("{} '{}' is missing in the second AIG", kind, name)),
+            }
+        })
+        .collect()
+}
+
+/// Build a miter of two combinational AIGs.
+///
+/// The miter has the inputs of `a` (in the same order) and one output per output of `a`,
+/// which is the XOR of the paired outputs. The AIGs are equivalent iff all outputs
+/// of the miter are constant zero.
+pub fn miter(a: &Aig, b: &Aig, pairing: Pairing) -> eyre::Result<Aig> {
+    if !a.latches().is_empty() || !b.latches().is_empty() {
+        return Err(eyre!(
+            "Equivalence checking of sequential AIGs is not supported"
+        ));
+    }
+
+    let (inputs, outputs) = match pairing {
+        Pairing::Position => {
+            if a.inputs().len() != b.inputs().len() {
+                return Err(eyre!(
+                    "Input counts differ: {} vs {}",
+                    a.inputs().len(),
+                    b.inputs().len()
+                ));
+            }
+            if a.outputs().len() != b.outputs().len() {
+                return Err(eyre!(
+                    "Output counts differ: {} vs {}",
+                    a.outputs().len(),
+                    b.outputs().len()
+                ));
+            }
+            (
+                (0..a.inputs().len()).collect::<Vec<_>>(),
+                (0..a.outputs().len()).collect::<Vec<_>>(),
+            )
+        }
+        Pairing::Name => (
+            pair_by_name(a, b, SymbolType::Input, a.inputs().len(), b.inputs().len())?,
+            pair_by_name(
+                a,
+                b,
+                SymbolType::Output,
+                a.outputs().len(),
+                b.outputs().len(),
+            )?,
+        ),
+    };
+
+    let mut builder = AigBuilder::new();
+    let mut map_a = vec![Ref::FALSE; a.max_id() as usize + 1];
+    let mut map_b = vec![Ref::FALSE; b.max_id() as usize + 1];
+    for (&id, &j) in a.inputs().iter().zip(&inputs) {
+        let input = builder.input();
+        map_a[id as usize] = input;
+        map_b[b.inputs()[j] as usize] = input;
+    }
+    builder.import(a, &mut map_a);
+    builder.import(b, &mut map_b);
+
+    for (&output, &j) in a.outputs().iter().zip(&outputs) {
+        let left = image(&map_a, output);
+        let right = image(&map_b, b.outputs()[j]);
+        let diff = builder.xor(left, right);
+        builder.output(diff);
+    }
+    Ok(builder.build())
+}
+
+impl Aig {
+    /// Check the combinational equivalence of two AIGs using a miter and the built-in SAT solver.
+    ///
+    /// When the AIGs differ, the returned input vector (in the order of the inputs of `self`)
+    /// makes the paired outputs of [`Aig::eval`] differ.
+    pub fn check_equivalence(&self, other: &Aig, pairing: Pairing) -> eyre::Result<CecResult> {
+        self.check_equivalence_with(other, pairing, &mut Solver::new())
+    }
+
+    /// Check the combinational equivalence of two AIGs using the given SAT solver.
+    ///
+    /// The miter is added to the solver, the query itself is guarded by an assumption
+    /// and retired afterwards, so the solver can be reused.
+    pub fn check_equivalence_with<S: SatSolver + ?Sized>(
+        &self,
+        other: &Aig,
+        pairing: Pairing,
+        solver: &mut S,
+    ) -> eyre::Result<CecResult> {
+        let miter = miter(self, other, pairing)?;
+        let outputs = miter.outputs();
+        if outputs.iter().all(|r| r.is_false()) {
+            return Ok(CecResult::Equivalent);
+        }
+
+        let mapping = miter.encode_cnf(solver);
+        let lit = |r: Ref| match r.get_const() {
+            Some(_) => None,
+            None => {
+                let var = mapping[&r.id()];
+                Some(if r.is_negated() { -var } else { var })
+            }
+        };
+
+        // Some output must be true. Constant outputs are either skipped (false)
+        // or make the clause trivially satisfied (true).
+        let activation = solver.new_var();
+        let assumptions = if outputs.iter().any(|r| r.is_true()) {
+            Vec::new()
+        } else {
+            let mut clause = vec![-activation];
+            clause.extend(outputs.iter().filter_map(|&r| lit(r)));
+            solver.add_clause(&clause);
+            vec![activation]
+        };
+        let result = solver.solve_with(&assumptions);
+
+        let result = match result {
+            SolveResult::Unsat => Ok(CecResult::Equivalent),
+            SolveResult::Sat => {
+                let inputs = miter
+                    .inputs()
+                    .iter()
+                    .map(|id| solver.value(mapping[id]).unwrap_or(false))
+                    .collect::<Vec<_>>();
+                let mut sim = SequentialSimulator::new(&miter);
+                sim.eval(&inputs);
+                outputs
+                    .iter()
+                    .position(|&r| sim.value(r))
+                    .map(|output| CecResult::NotEquivalent { inputs, output })
+                    .ok_or_else(|| eyre!("Model of the solver does not distinguish any output"))
+            }
+        };
+        solver.add_clause(&[-activation]);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::sat::ClauseSink;
+
+    /// Value of the output under the result of [`Aig::eval`].
+    fn eval_output(aig: &Aig, inputs: &[bool], index: usize) -> bool {
+        let output = aig.outputs()[index];
+        match output.get_const() {
+            Some(c) => c,
+            None => aig.eval(inputs.to_vec())[&output.id()] ^ output.is_negated(),
+        }
+    }
+
+    #[test]
+    fn test_xorxormiter() {
+        // The miter of two XOR implementations is constant zero.
+        let aig = Aig::from_file("data/examples/xorxormiter.aag").unwrap();
+        let mut builder = AigBuilder::new();
+        builder.inputs(2);
+        builder.output(Ref::FALSE);
+        let zero = builder.build();
+
+        let result = aig.check_equivalence(&zero, Pairing::Position).unwrap();
+        assert_eq!(result, CecResult::Equivalent);
+    }
+
+    #[test]
+    fn test_check_equivalence() {
+        // a & (b | c) vs (a & b) | (a & c)
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let t = builder.or(b, c);
+        let f = builder.and(a, t);
+        builder.output(f);
+        let left = builder.build();
+
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let ac = builder.and(a, c);
+        let f = builder.or(ab, ac);
+        builder.output(f);
+        let right = builder.build();
+
+        let result = left.check_equivalence(&right, Pairing::Position).unwrap();
+        assert!(result.is_equivalent());
+
+        // a & (b ^ c) differs:
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let t = builder.xor(b, c);
+        let f = builder.and(a, t);
+        builder.output(f);
+        let wrong = builder.build();
+
+        match left.check_equivalence(&wrong, Pairing::Position).unwrap() {
+            CecResult::Equivalent => panic!("AIGs must not be equivalent"),
+            CecResult::NotEquivalent { inputs, output } => {
+                assert_eq!(inputs, vec![true, true, true]);
+                assert_ne!(
+                    eval_output(&left, &inputs, output),
+                    eval_output(&wrong, &inputs, output)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_pairing_by_name() {
+        // f = a & ~b, with inputs declared in different orders.
+        let mut builder = AigBuilder::new();
+        let [a, b] = builder.inputs(2).try_into().unwrap();
+        let f = builder.and(a, -b);
+        builder.output(f);
+        let mut left = builder.build();
+        left.set_symbol(SymbolType::Input, 0, "a".to_string());
+        left.set_symbol(SymbolType::Input, 1, "b".to_string());
+        left.set_symbol(SymbolType::Output, 0, "f".to_string());
+
+        let mut builder = AigBuilder::new();
+        let [b, a] = builder.inputs(2).try_into().unwrap();
+        let f = builder.and(a, -b);
+        builder.output(f);
+        let mut right = builder.build();
+        right.set_symbol(SymbolType::Input, 0, "b".to_string());
+        right.set_symbol(SymbolType::Input, 1, "a".to_string());
+        right.set_symbol(SymbolType::Output, 0, "f".to_string());
+
+        let result = left.check_equivalence(&right, Pairing::Name).unwrap();
+        assert!(result.is_equivalent());
+        let result = left.check_equivalence(&right, Pairing::Position).unwrap();
+        assert!(!result.is_equivalent());
+
+        right.set_symbol(SymbolType::Input, 1, "c".to_string());
+        assert!(left.check_equivalence(&right, Pairing::Name).is_err());
+
+        // Duplicate names can't be paired:
+        right.set_symbol(SymbolType::Input, 1, "a".to_string());
+        assert!(left.check_equivalence(&right, Pairing::Name).is_ok());
+        left.set_symbol(SymbolType::Input, 1, "a".to_string());
+        assert!(left.check_equivalence(&right, Pairing::Name).is_err());
+    }
+
+    /// Solver which does not report the values of the model.
+    struct NoModel(Solver);
+
+    impl ClauseSink for NoModel {
+        fn new_var(&mut self) -> i32 {
+            self.0.new_var()
+        }
+
+        fn add_clause(&mut self, lits: &[i32]) {
+            self.0.add_clause(lits)
+        }
+    }
+
+    impl SatSolver for NoModel {
+        fn num_vars(&self) -> usize {
+            self.0.num_vars()
+        }
+
+        fn solve_with(&mut self, assumptions: &[i32]) -> SolveResult {
+            self.0.solve_with(assumptions)
+        }
+
+        fn value(&self, _lit: i32) -> Option<bool> {
+            None
+        }
+
+        fn failed_assumptions(&self) -> Vec<i32> {
+            self.0.failed_assumptions()
+        }
+    }
+
+    #[test]
+    fn test_partial_model() {
+        // x vs 0: the inputs defaulted to false do not distinguish the outputs.
+        let mut builder = AigBuilder::new();
+        let x = builder.input();
+        builder.output(x);
+        let left = builder.build();
+
+        let mut builder = AigBuilder::new();
+        builder.input();
+        builder.output(Ref::FALSE);
+        let right = builder.build();
+
+        let mut solver = NoModel(Solver::new());
+        let result = left.check_equivalence_with(&right, Pairing::Position, &mut solver);
+        assert!(result.is_err());
+    }
+}
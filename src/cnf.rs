@@ -1,25 +1,47 @@
 use std::collections::HashMap;
 
 use crate::aig::Aig;
-use crate::node::Node;
 use crate::reference::Ref;
+use crate::sat::{ClauseSink, SatSolver};
 
 impl Aig {
     pub fn to_cnf(&self) -> (Vec<Vec<i32>>, HashMap<u32, u32>) {
+        let mut clauses = Vec::new();
+        let mapping = self.encode(1, &mut |clause| clauses.push(clause.to_vec()));
+        let mapping = mapping
+            .into_iter()
+            .map(|(id, var)| (id, var as u32))
+            .collect();
+        (clauses, mapping)
+    }
+
+    /// Encode the AIG into the solver, using fresh variables for inputs and AND gates.
+    ///
+    /// Returns the mapping from node ids to solver variables.
+    pub fn encode_cnf<S: SatSolver + ?Sized>(&self, solver: &mut S) -> HashMap<u32, i32> {
+        let first = solver.num_vars() as i32 + 1;
+        let mapping = self.encode(first, &mut |clause| solver.add_clause(clause));
+        // Variables that do not appear in any clause must exist as well:
+        while solver.num_vars() < first as usize - 1 + mapping.len() {
+            solver.new_var();
+        }
+        mapping
+    }
+
+    /// Tseitin encoding with variables numbered from `first`: inputs, then AND gates in topological order.
+    fn encode(&self, first: i32, add_clause: &mut dyn FnMut(&[i32])) -> HashMap<u32, i32> {
         if !self.latches().is_empty() {
             panic!("Can't convert AIG with latches to CNF");
         }
 
         let mut mapping = HashMap::new(); // {id: var}
-        let mut clauses = Vec::new();
 
         for (i, &id) in self.inputs().iter().enumerate() {
-            mapping.insert(id, i as u32 + 1);
+            mapping.insert(id, first + i as i32);
         }
 
-        fn ref2lit(r: Ref, mapping: &HashMap<u32, u32>) -> i32 {
-            let var = mapping[&r.id()];
-            let lit = var as i32;
+        fn ref2lit(r: Ref, mapping: &HashMap<u32, i32>) -> i32 {
+            let lit = mapping[&r.id()];
             if r.is_negated() {
                 -lit
             } else {
@@ -27,67 +49,387 @@ impl Aig {
             }
         }
 
-        for (i, layer) in self.layers_input().enumerate().skip(1) {
-            for id in layer {
-                match self.node(id) {
-                    Node::Zero => {
-                        panic!("Unexpected zero on level {}", i);
+        for &id in self.topological_order() {
+            let gate = self.gate(id);
+            let x = first + mapping.len() as i32;
+            mapping.insert(id, x);
+            let [left, right] = gate.args;
+            match (left.get_const(), right.get_const()) {
+                (Some(l), Some(r)) => {
+                    // Both LHS and RHS are constants
+                    if l && r {
+                        add_clause(&[x]);
+                    } else {
+                        add_clause(&[-x]);
                     }
-                    Node::Input(input) => {
-                        panic!("Unexpected input on level {}: {:?}", i, input);
+                }
+                (Some(l), None) => {
+                    // LHS is constant
+                    let rhs = ref2lit(right, &mapping);
+                    if l {
+                        add_clause(&[x, -rhs]);
+                        add_clause(&[-x, rhs]);
+                    } else {
+                        add_clause(&[-x]);
                     }
-                    Node::Latch(latch) => {
-                        panic!("Unexpected latch on level {}: {:?}", i, latch);
+                }
+                (None, Some(r)) => {
+                    // RHS is constant
+                    let lhs = ref2lit(left, &mapping);
+                    if r {
+                        add_clause(&[x, -lhs]);
+                        add_clause(&[-x, lhs]);
+                    } else {
+                        add_clause(&[-x]);
                     }
-                    Node::AndGate(gate) => {
-                        let x = mapping.len() as u32 + 1;
-                        mapping.insert(id, x);
-                        let x = x as i32;
-                        let [left, right] = gate.args;
-                        match (left.get_const(), right.get_const()) {
-                            (Some(l), Some(r)) => {
-                                // Both LHS and RHS are constants
-                                if l && r {
-                                    clauses.push(vec![x]);
-                                } else {
-                                    clauses.push(vec![-x]);
-                                }
-                            }
-                            (Some(l), None) => {
-                                // LHS is constant
-                                let rhs = ref2lit(right, &mapping);
-                                if l {
-                                    clauses.push(vec![x, -rhs]);
-                                    clauses.push(vec![-x, rhs]);
-                                } else {
-                                    clauses.push(vec![-x]);
-                                }
-                            }
-                            (None, Some(r)) => {
-                                // RHS is constant
-                                let lhs = ref2lit(left, &mapping);
-                                if r {
-                                    clauses.push(vec![x, -lhs]);
-                                    clauses.push(vec![-x, lhs]);
-                                } else {
-                                    clauses.push(vec![-x]);
-                                }
-                            }
-                            (None, None) => {
-                                // Normal AND
-                                let lhs = ref2lit(left, &mapping);
-                                let rhs = ref2lit(right, &mapping);
-                                clauses.push(vec![x, -lhs, -rhs]);
-                                clauses.push(vec![-x, lhs]);
-                                clauses.push(vec![-x, rhs]);
-                            }
+                }
+                (None, None) => {
+                    // Normal AND
+                    let lhs = ref2lit(left, &mapping);
+                    let rhs = ref2lit(right, &mapping);
+                    add_clause(&[x, -lhs, -rhs]);
+                    add_clause(&[-x, lhs]);
+                    add_clause(&[-x, rhs]);
+                }
+            }
+        }
+
+        mapping
+    }
+}
+
+/// CNF formula over variables `1..=num_vars`.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Cnf {
+    pub num_vars: usize,
+    pub clauses: Vec<Vec<i32>>,
+    /// Comment lines, without the leading `c `.
+    pub comments: Vec<String>,
+}
+
+impl Cnf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ClauseSink for Cnf {
+    fn new_var(&mut self) -> i32 {
+        self.num_vars += 1;
+        self.num_vars as i32
+    }
+    fn add_clause(&mut self, lits: &[i32]) {
+        self.clauses.push(lits.to_vec());
+    }
+}
+
+/// Options of the [`CnfEncoder`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct CnfOptions {
+    /// Plaisted–Greenbaum encoding: emit only the implications required by the
+    /// polarities in which nodes are used, see [`CnfEncoder::encode_with`].
+    pub polarity: bool,
+    /// Encode multi-input AND trees, XORs and MUXes with compact clause sets.
+    /// Only gates with a single fanout are merged into their parents.
+    pub compact: bool,
+}
+
+/// Polarity in which a literal is used.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Polarity {
+    /// The literal only implies the value of the reference: it can be asserted,
+    /// assumed true, or occur positively in clauses.
+    Positive,
+    /// The value of the reference only implies the literal.
+    Negative,
+    Both,
+}
+
+const POSITIVE: u8 = 1;
+const NEGATIVE: u8 = 2;
+const BOTH: u8 = POSITIVE | NEGATIVE;
+
+impl Polarity {
+    const fn mask(self) -> u8 {
+        match self {
+            Polarity::Positive => POSITIVE,
+            Polarity::Negative => NEGATIVE,
+            Polarity::Both => BOTH,
+        }
+    }
+}
+
+/// Polarity mask of the node under a (possibly negated) reference.
+const fn flip(mask: u8, negated: bool) -> u8 {
+    if negated {
+        ((mask & POSITIVE) << 1) | ((mask & NEGATIVE) >> 1)
+    } else {
+        mask
+    }
+}
+
+/// Function of a node, as seen by the encoder.
+enum Function {
+    And(Vec<Ref>),
+    /// `a ^ b`
+    Xor(Ref, Ref),
+    /// `c ? t : e`
+    Mux(Ref, Ref, Ref),
+}
+
+/// Incremental Tseitin encoder of an AIG into a SAT solver.
+///
+/// Only the transitive fan-in of the requested references is encoded, and the
+/// `id -> literal` mapping is kept across calls, so nothing is encoded twice.
+/// Inputs and latches are leaves: they get fresh variables on first use,
+/// unless they were bound to existing literals with [`CnfEncoder::bind`].
+/// Using one encoder per timeframe, with latches bound to the next-state
+/// literals of the previous frame, unrolls a sequential AIG.
+#[derive(Clone)]
+pub struct CnfEncoder<'a> {
+    aig: &'a Aig,
+    options: CnfOptions,
+    /// Literals of the nodes, indexed by id.
+    lits: Vec<Option<i32>>,
+    /// Polarities in which the nodes are encoded, indexed by id.
+    encoded: Vec<u8>,
+}
+
+impl<'a> CnfEncoder<'a> {
+    pub fn new(aig: &'a Aig) -> Self {
+        Self::with_options(aig, CnfOptions::default())
+    }
+
+    pub fn with_options(aig: &'a Aig, options: CnfOptions) -> Self {
+        let n = aig.max_id() as usize + 1;
+        Self {
+            aig,
+            options,
+            lits: vec![None; n],
+            encoded: vec![0; n],
+        }
+    }
+
+    pub fn aig(&self) -> &'a Aig {
+        self.aig
+    }
+
+    pub fn options(&self) -> CnfOptions {
+        self.options
+    }
+
+    /// Use the literal for the node with the given id, which must be an input, a latch,
+    /// or the constant node `0` (whose literal is then false).
+    pub fn bind(&mut self, id: u32, lit: i32) {
+        assert!(
+            id == 0 || self.aig.is_input(id) || self.aig.is_latch(id),
+            "Only inputs, latches and the constant can be bound, got {}",
+            id
+        );
+        assert!(
+            self.lits[id as usize].is_none(),
+            "Node {} is already encoded",
+            id
+        );
+        self.lits[id as usize] = Some(lit);
+        self.encoded[id as usize] = BOTH;
+    }
+
+    /// Literal of the reference, if it has one.
+    pub fn lit(&self, r: Ref) -> Option<i32> {
+        let lit = self.lits[r.id() as usize]?;
+        Some(if r.is_negated() { -lit } else { lit })
+    }
+
+    /// The number of encoded nodes, including bound ones.
+    pub fn num_encoded(&self) -> usize {
+        self.encoded.iter().filter(|&&mask| mask != 0).count()
+    }
+
+    /// Encode the cone of the reference, returning its literal, which can be used in both polarities.
+    pub fn encode<S: ClauseSink + ?Sized>(&mut self, sink: &mut S, r: Ref) -> i32 {
+        self.encode_with(sink, r, Polarity::Both)
+    }
+
+    /// Encode the cones of several references at once.
+    pub fn encode_many<S: ClauseSink + ?Sized>(&mut self, sink: &mut S, refs: &[Ref]) -> Vec<i32> {
+        refs.iter().map(|&r| self.encode(sink, r)).collect()
+    }
+
+    /// Encode the cone of the reference for use in the given polarity.
+    ///
+    /// The polarity only matters with [`CnfOptions::polarity`], otherwise both are encoded.
+    /// Requesting another polarity of the same reference later adds the missing clauses.
+    pub fn encode_with<S: ClauseSink + ?Sized>(
+        &mut self,
+        sink: &mut S,
+        r: Ref,
+        polarity: Polarity,
+    ) -> i32 {
+        let mask = if self.options.polarity {
+            flip(polarity.mask(), r.is_negated())
+        } else {
+            BOTH
+        };
+        let lit = self.lit_of(sink, r);
+
+        let mut stack = vec![(r.id(), mask)];
+        while let Some((id, mask)) = stack.pop() {
+            let new = mask & !self.encoded[id as usize];
+            if new == 0 {
+                continue;
+            }
+            self.encoded[id as usize] |= new;
+            let x = self.var_of(sink, id);
+
+            match self.function(id) {
+                Function::And(args) => {
+                    let lits = args
+                        .iter()
+                        .map(|&a| self.lit_of(sink, a))
+                        .collect::<Vec<_>>();
+                    if new & POSITIVE != 0 {
+                        for &l in &lits {
+                            sink.add_clause(&[-x, l]);
                         }
                     }
+                    if new & NEGATIVE != 0 {
+                        let mut clause = vec![x];
+                        clause.extend(lits.iter().map(|&l| -l));
+                        sink.add_clause(&clause);
+                    }
+                    for &a in &args {
+                        stack.push((a.id(), flip(new, a.is_negated())));
+                    }
+                }
+                Function::Xor(a, b) => {
+                    let la = self.lit_of(sink, a);
+                    let lb = self.lit_of(sink, b);
+                    if new & POSITIVE != 0 {
+                        sink.add_clause(&[-x, la, lb]);
+                        sink.add_clause(&[-x, -la, -lb]);
+                    }
+                    if new & NEGATIVE != 0 {
+                        sink.add_clause(&[x, -la, lb]);
+                        sink.add_clause(&[x, la, -lb]);
+                    }
+                    stack.push((a.id(), BOTH));
+                    stack.push((b.id(), BOTH));
+                }
+                Function::Mux(c, t, e) => {
+                    let lc = self.lit_of(sink, c);
+                    let lt = self.lit_of(sink, t);
+                    let le = self.lit_of(sink, e);
+                    if new & POSITIVE != 0 {
+                        sink.add_clause(&[-x, -lc, lt]);
+                        sink.add_clause(&[-x, lc, le]);
+                    }
+                    if new & NEGATIVE != 0 {
+                        sink.add_clause(&[x, -lc, -lt]);
+                        sink.add_clause(&[x, lc, -le]);
+                    }
+                    stack.push((c.id(), BOTH));
+                    stack.push((t.id(), flip(new, t.is_negated())));
+                    stack.push((e.id(), flip(new, e.is_negated())));
                 }
             }
         }
 
-        (clauses, mapping)
+        lit
+    }
+
+    /// Variable of the node, allocating a fresh one if needed.
+    /// Leaves and the constant are fully encoded on allocation.
+    fn var_of<S: ClauseSink + ?Sized>(&mut self, sink: &mut S, id: u32) -> i32 {
+        if let Some(lit) = self.lits[id as usize] {
+            return lit;
+        }
+        let var = sink.new_var();
+        let lit = if id == 0 {
+            sink.add_clause(&[var]);
+            -var
+        } else {
+            var
+        };
+        self.lits[id as usize] = Some(lit);
+        if !self.aig.is_gate(id) {
+            self.encoded[id as usize] = BOTH;
+        }
+        lit
+    }
+
+    fn lit_of<S: ClauseSink + ?Sized>(&mut self, sink: &mut S, r: Ref) -> i32 {
+        let lit = self.var_of(sink, r.id());
+        if r.is_negated() {
+            -lit
+        } else {
+            lit
+        }
+    }
+
+    /// Whether the gate can be merged into its only fanout.
+    fn is_mergeable(&self, r: Ref) -> bool {
+        let id = r.id();
+        self.aig.is_gate(id) && self.lits[id as usize].is_none() && self.aig.fanouts(id).len() == 1
+    }
+
+    fn function(&self, id: u32) -> Function {
+        let [left, right] = self.aig.gate(id).args;
+        if !self.options.compact {
+            return Function::And(vec![left, right]);
+        }
+
+        // x = ~(a0 & a1) & ~(b0 & b1) is a XOR or a MUX when the arguments are complementary.
+        if left.is_negated()
+            && right.is_negated()
+            && self.is_mergeable(left)
+            && self.is_mergeable(right)
+        {
+            let [a0, a1] = self.aig.gate(left.id()).args;
+            let [b0, b1] = self.aig.gate(right.id()).args;
+            if (b0 == -a0 && b1 == -a1) || (b0 == -a1 && b1 == -a0) {
+                return Function::Xor(a0, a1);
+            }
+            for (c, t) in [(a0, a1), (a1, a0)] {
+                for (nc, e) in [(b0, b1), (b1, b0)] {
+                    if nc == -c {
+                        return Function::Mux(c, -t, -e);
+                    }
+                }
+            }
+        }
+
+        // Multi-input AND over positive single-fanout gates:
+        let mut args = Vec::new();
+        let mut stack = vec![right, left];
+        while let Some(r) = stack.pop() {
+            if !r.is_negated() && self.is_mergeable(r) {
+                let [a, b] = self.aig.gate(r.id()).args;
+                stack.push(b);
+                stack.push(a);
+            } else {
+                args.push(r);
+            }
+        }
+        Function::And(args)
+    }
+}
+
+impl Aig {
+    /// Encode the cones of all outputs with the given options.
+    ///
+    /// With [`CnfOptions::polarity`], outputs are encoded in [`Polarity::Positive`]:
+    /// their literals may be asserted or used positively in clauses, but not negated.
+    /// Returns the formula and the literals of the outputs.
+    pub fn to_cnf_with(&self, options: CnfOptions) -> (Cnf, Vec<i32>) {
+        let mut cnf = Cnf::new();
+        let mut encoder = CnfEncoder::with_options(self, options);
+        let outputs = self
+            .outputs()
+            .iter()
+            .map(|&r| encoder.encode_with(&mut cnf, r, Polarity::Positive))
+            .collect();
+        (cnf, outputs)
     }
 }
 
@@ -95,7 +437,11 @@ impl Aig {
 mod tests {
     use super::*;
 
+    use crate::builder::AigBuilder;
     use crate::reference::Ref;
+    use crate::sat::Solver;
+    use crate::sim::SplitMix64;
+    use crate::test_utils::RandomAig;
 
     #[test]
     fn test_to_cnf() {
@@ -124,4 +470,167 @@ mod tests {
         }
         assert_eq!(clauses.len(), 7);
     }
+
+    #[test]
+    fn test_encode_cnf() {
+        // Majority of three inputs: (a & b) | (a & c) | (b & c)
+        let aig = {
+            let mut builder = AigBuilder::new();
+            let [a, b, c] = builder.inputs(3).try_into().unwrap();
+            let f = builder.maj(a, b, c);
+            builder.output(f);
+            builder.build()
+        };
+        let output = aig.outputs()[0];
+
+        let mut solver = Solver::new();
+        solver.new_var(); // unrelated variable, shifts the encoding
+        let mapping = aig.encode_cnf(&mut solver);
+        assert!(mapping.values().all(|&var| var > 1));
+        let lit = |r: Ref| {
+            let var = mapping[&r.id()];
+            if r.is_negated() {
+                -var
+            } else {
+                var
+            }
+        };
+
+        let inputs = aig
+            .inputs()
+            .iter()
+            .map(|id| mapping[id])
+            .collect::<Vec<_>>();
+        assert!(solver
+            .solve_with(&[lit(output), -inputs[0], -inputs[1]])
+            .is_unsat());
+        assert!(solver.solve_with(&[lit(output), -inputs[0]]).is_sat());
+        assert_eq!(solver.value(inputs[1]), Some(true));
+        assert_eq!(solver.value(inputs[2]), Some(true));
+    }
+
+    #[test]
+    fn test_encoder_cone() {
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let bc = builder.or(b, c);
+        builder.output(ab);
+        builder.output(bc);
+        let aig = builder.build();
+
+        let mut solver = Solver::new();
+        let mut encoder = CnfEncoder::new(&aig);
+        let x = encoder.encode(&mut solver, ab);
+        // Only a, b and the gate:
+        assert_eq!(encoder.num_encoded(), 3);
+        assert_eq!(solver.num_vars(), 3);
+        assert_eq!(encoder.lit(c), None);
+
+        let y = encoder.encode(&mut solver, -bc);
+        assert_eq!(encoder.num_encoded(), 5);
+        assert_eq!(encoder.encode(&mut solver, ab), x);
+
+        // a & b and ~(b | c) are contradictory:
+        assert!(solver.solve_with(&[x, y]).is_unsat());
+        assert!(solver.solve_with(&[x, -y]).is_sat());
+
+        assert_eq!(
+            encoder.encode(&mut solver, Ref::TRUE),
+            -encoder.encode(&mut solver, Ref::FALSE)
+        );
+        assert!(solver
+            .solve_with(&[encoder.lit(Ref::FALSE).unwrap()])
+            .is_unsat());
+    }
+
+    #[test]
+    fn test_encoder_timeframes() {
+        // Toggle flip-flop: two frames, the latch of frame 1 is the next state of frame 0.
+        let aig = Aig::from_file("data/examples/toggle.aag").unwrap();
+        let latch = aig.latches()[0];
+        let next = aig.latch(latch).next;
+
+        let mut solver = Solver::new();
+        let mut frame0 = CnfEncoder::new(&aig);
+        let q0 = frame0.encode(&mut solver, Ref::positive(latch));
+        let next0 = frame0.encode(&mut solver, next);
+        let mut frame1 = CnfEncoder::new(&aig);
+        frame1.bind(latch, next0);
+        let q1 = frame1.encode(&mut solver, Ref::positive(latch));
+        assert_eq!(q1, next0);
+
+        // The latch toggles: it can't hold the same value in both frames.
+        assert!(solver.solve_with(&[q0, q1]).is_unsat());
+        assert!(solver.solve_with(&[-q0, -q1]).is_unsat());
+        assert!(solver.solve_with(&[-q0, q1]).is_sat());
+    }
+
+    /// Random circuit over 5 inputs built from ANDs, XORs and MUXes.
+    #[test]
+    fn test_encoder_options() {
+        for seed in 0..20 {
+            let aig = RandomAig {
+                inputs: 5,
+                gates: 20,
+                mixed: true,
+                outputs: 4,
+                ..RandomAig::default()
+            }
+            .generate(&mut SplitMix64::new(seed));
+            let sim = aig.simulate::<1>(&[
+                [0xaaaaaaaa],
+                [0xcccccccc],
+                [0xf0f0f0f0],
+                [0xff00ff00],
+                [0xffff0000],
+            ]);
+            for polarity in [false, true] {
+                for compact in [false, true] {
+                    let options = CnfOptions { polarity, compact };
+                    let mut solver = Solver::new();
+                    let mut encoder = CnfEncoder::with_options(&aig, options);
+                    for &output in aig.outputs() {
+                        for r in [output, -output] {
+                            let lit = encoder.encode_with(&mut solver, r, Polarity::Positive);
+                            let expected = (0..32).any(|k| sim.bit(r, k));
+                            assert_eq!(solver.solve_with(&[lit]).is_sat(), expected);
+                            if expected {
+                                let k = aig
+                                    .inputs()
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, &id)| {
+                                        let value = encoder
+                                            .lit(Ref::positive(id))
+                                            .and_then(|l| solver.value(l))
+                                            .unwrap_or(false);
+                                        (value as usize) << i
+                                    })
+                                    .sum::<usize>();
+                                assert!(sim.bit(r, k), "Model does not satisfy {:?}", options);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cnf_size() {
+        let aig = Aig::from_file("data/arithmetic/multiplier.aag").unwrap();
+        let (plain, _) = aig.to_cnf_with(CnfOptions::default());
+        let (polarity, _) = aig.to_cnf_with(CnfOptions {
+            polarity: true,
+            compact: false,
+        });
+        let (compact, _) = aig.to_cnf_with(CnfOptions {
+            polarity: true,
+            compact: true,
+        });
+        assert!(polarity.clauses.len() < plain.clauses.len());
+        assert!(compact.clauses.len() < polarity.clauses.len());
+        assert!(compact.num_vars < plain.num_vars);
+    }
 }
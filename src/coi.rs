@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+
+use crate::aig::Aig;
+use crate::aiger::SymbolType;
+use crate::builder::image;
+use crate::reference::Ref;
+
+/// Root of a cone of influence: an output or a property of an AIG, by index.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Root {
+    Output(usize),
+    Bad(usize),
+    Justice(usize),
+}
+
+impl Aig {
+    /// Extract the cone of influence of the selected outputs and properties.
+    ///
+    /// The result contains only the nodes in the transitive fan-in of the roots,
+    /// following latches through their next-state functions. Invariant constraints
+    /// (and their cones) are always kept, since they restrict the behaviour of the
+    /// properties; fairness constraints are kept when some justice property is selected.
+    ///
+    /// Nodes are renumbered densely: inputs, then latches (both in their original
+    /// relative order, keeping their names), then AND gates in topological order.
+    /// Outputs and properties of each kind appear in the order of `roots`.
+    /// Comments are kept.
+    pub fn cone_of_influence(&self, roots: &[Root]) -> Aig {
+        let mut result = Aig::default();
+        for comment in self.comments() {
+            result.add_comment(comment.clone());
+        }
+        let mut kept = Vec::new();
+        for &root in roots {
+            match root {
+                Root::Output(i) => {
+                    result.add_output(self.outputs()[i]);
+                    kept.push((SymbolType::Output, i));
+                }
+                Root::Bad(i) => {
+                    result.add_bad(self.bad()[i]);
+                    kept.push((SymbolType::Bad, i));
+                }
+                Root::Justice(i) => {
+                    result.add_justice(self.justice()[i].clone());
+                    kept.push((SymbolType::Justice, i));
+                }
+            }
+        }
+        for (i, &c) in self.constraints().iter().enumerate() {
+            result.add_constraint(c);
+            kept.push((SymbolType::Constraint, i));
+        }
+        if !result.justice().is_empty() {
+            for (i, &f) in self.fairness().iter().enumerate() {
+                result.add_fairness(f);
+                kept.push((SymbolType::Fairness, i));
+            }
+        }
+        self.extract(result, &kept, false)
+    }
+
+    /// Split the AIG into one AIG per output, each being the cone of influence
+    /// of the output (see [`Aig::cone_of_influence`]).
+    pub fn split_outputs(&self) -> Vec<Aig> {
+        (0..self.outputs().len())
+            .map(|i| self.cone_of_influence(&[Root::Output(i)]))
+            .collect()
+    }
+
+    /// Remove the AND gates which do not influence any output, property or constraint.
+    ///
+    /// All inputs and latches are kept, so the result has the same interface.
+    /// Nodes are renumbered densely, as in [`Aig::cone_of_influence`], so the result
+    /// is in canonical order (see [`Aig::is_canonical`]).
+    pub fn remove_dangling(&self) -> Aig {
+        let (roots, kept) = self.roots();
+        self.extract(roots, &kept, true)
+    }
+
+    /// References of the outputs, properties and latch next-states: the roots whose
+    /// cones are kept by [`Aig::remove_dangling`].
+    pub(crate) fn root_refs(&self) -> impl Iterator<Item = Ref> + use<'_> {
+        self.outputs()
+            .iter()
+            .chain(self.bad())
+            .chain(self.constraints())
+            .chain(self.justice().iter().flatten())
+            .chain(self.fairness())
+            .copied()
+            .chain(self.latches().iter().map(|&id| self.latch(id).next))
+    }
+
+    /// Finish `aig`, rebuilt from `self` by a transformation pass with the same inputs,
+    /// outputs and properties, and the latches selected by `kept_latches` (all of them
+    /// if `None`): copy the symbols and comments of `self`, and remove the dangling gates.
+    pub(crate) fn finish_rebuild(&self, mut aig: Aig, kept_latches: Option<&[bool]>) -> Aig {
+        for (type_spec, position, name) in self.symbols().iter() {
+            let position = match (type_spec, kept_latches) {
+                (SymbolType::Latch, Some(kept)) => {
+                    // Positions of the remaining latches shift:
+                    if !kept[position] {
+                        continue;
+                    }
+                    kept[..position].iter().filter(|&&k| k).count()
+                }
+                _ => position,
+            };
+            aig.set_symbol(type_spec, position, name.to_string());
+        }
+        for comment in self.comments() {
+            aig.add_comment(comment.clone());
+        }
+        aig.remove_dangling()
+    }
+
+    /// Copy the cone of the roots already present in `roots` (in terms of the node ids
+    /// of `self`) into a compact AIG.
+    /// `kept` lists the original positions of the roots, to carry over their names.
+    fn extract(&self, roots: Aig, kept: &[(SymbolType, usize)], keep_interface: bool) -> Aig {
+        let mut visited = vec![false; self.max_id() as usize + 1];
+        let mut stack = Vec::new();
+        let mut visit = |r: Ref, stack: &mut Vec<u32>| {
+            let id = r.id();
+            if id != 0 && !visited[id as usize] {
+                visited[id as usize] = true;
+                stack.push(id);
+            }
+        };
+
+        for r in roots.root_refs() {
+            visit(r, &mut stack);
+        }
+        if keep_interface {
+            for &id in self.inputs().iter().chain(self.latches()) {
+                visit(Ref::positive(id), &mut stack);
+            }
+        }
+        while let Some(id) = stack.pop() {
+            if self.is_gate(id) {
+                for r in self.gate(id).args {
+                    visit(r, &mut stack);
+                }
+            } else if self.is_latch(id) {
+                visit(self.latch(id).next, &mut stack);
+            }
+        }
+
+        self.compact(roots, kept, &visited).0
+    }
+
+    /// All outputs, properties and comments, to be copied by [`Aig::compact`],
+    /// along with their positions.
+    pub(crate) fn roots(&self) -> (Aig, Vec<(SymbolType, usize)>) {
+        let mut roots = Aig::default();
+        let mut kept = Vec::new();
+        for (i, &r) in self.outputs().iter().enumerate() {
+            roots.add_output(r);
+            kept.push((SymbolType::Output, i));
+        }
+        for (i, &r) in self.bad().iter().enumerate() {
+            roots.add_bad(r);
+            kept.push((SymbolType::Bad, i));
+        }
+        for (i, &r) in self.constraints().iter().enumerate() {
+            roots.add_constraint(r);
+            kept.push((SymbolType::Constraint, i));
+        }
+        for (i, justice) in self.justice().iter().enumerate() {
+            roots.add_justice(justice.clone());
+            kept.push((SymbolType::Justice, i));
+        }
+        for (i, &r) in self.fairness().iter().enumerate() {
+            roots.add_fairness(r);
+            kept.push((SymbolType::Fairness, i));
+        }
+        for comment in self.comments() {
+            roots.add_comment(comment.clone());
+        }
+        (roots, kept)
+    }
+
+    /// Copy the nodes selected by `mask` (indexed by id) and the roots (outputs and
+    /// properties of `roots`, in terms of the node ids of `self`) into a new AIG, numbered
+    /// densely as in [`Aig::cone_of_influence`]. The mask must be closed under fan-in,
+    /// through latches.
+    ///
+    /// `kept` lists the original positions of the roots, to carry over their names.
+    /// Returns the new AIG and the mapping from the old ids to the new references.
+    pub(crate) fn compact(
+        &self,
+        roots: Aig,
+        kept: &[(SymbolType, usize)],
+        mask: &[bool],
+    ) -> (Aig, Vec<Ref>) {
+        let mut result = Aig::default();
+        let mut map = vec![Ref::FALSE; self.max_id() as usize + 1];
+        let mut next_id = 0;
+        let mut fresh = |old: u32, map: &mut [Ref]| {
+            next_id += 1;
+            map[old as usize] = Ref::positive(next_id);
+            next_id
+        };
+
+        for (position, &id) in self.inputs().iter().enumerate() {
+            if mask[id as usize] {
+                let new = fresh(id, &mut map);
+                result.add_input(new);
+                if let Some(name) = self.symbols().name(SymbolType::Input, position) {
+                    let index = result.inputs().len() - 1;
+                    result.set_symbol(SymbolType::Input, index, name.to_string());
+                }
+            }
+        }
+        let mut latches = Vec::new();
+        for (position, &id) in self.latches().iter().enumerate() {
+            if mask[id as usize] {
+                let new = fresh(id, &mut map);
+                result.add_latch(new, Ref::FALSE, self.latch(id).init);
+                latches.push((id, new));
+                if let Some(name) = self.symbols().name(SymbolType::Latch, position) {
+                    let index = result.latches().len() - 1;
+                    result.set_symbol(SymbolType::Latch, index, name.to_string());
+                }
+            }
+        }
+        for &id in self.topological_order() {
+            if mask[id as usize] {
+                let new = fresh(id, &mut map);
+                let [left, right] = self.gate(id).args;
+                result.add_and_gate(new, [image(&map, left), image(&map, right)]);
+            }
+        }
+        for (id, new) in latches {
+            result.set_latch_next(new, image(&map, self.latch(id).next));
+        }
+
+        for &r in roots.outputs() {
+            result.add_output(image(&map, r));
+        }
+        for &r in roots.bad() {
+            result.add_bad(image(&map, r));
+        }
+        for &r in roots.constraints() {
+            result.add_constraint(image(&map, r));
+        }
+        for justice in roots.justice() {
+            result.add_justice(justice.iter().map(|&r| image(&map, r)).collect());
+        }
+        for &r in roots.fairness() {
+            result.add_fairness(image(&map, r));
+        }
+        for comment in roots.comments() {
+            result.add_comment(comment.clone());
+        }
+
+        // Names of the roots, renumbered within each kind in the order of `kept`:
+        let mut counts = HashMap::new();
+        for &(type_spec, position) in kept {
+            let count = counts.entry(type_spec).or_insert(0);
+            if let Some(name) = self.symbols().name(type_spec, position) {
+                result.set_symbol(type_spec, *count, name.to_string());
+            }
+            *count += 1;
+        }
+        (result, map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builder::AigBuilder;
+    use crate::node::LatchInit;
+
+    #[test]
+    fn test_remove_dangling() {
+        // Gate 7 (g3 & 0) is not used by the output.
+        let mut aig = Aig::default();
+        aig.add_input(1);
+        aig.add_input(2);
+        aig.add_input(3);
+        aig.add_and_gate(4, [Ref::positive(1), Ref::positive(2)]);
+        aig.add_and_gate(5, [Ref::negative(4), Ref::positive(3)]);
+        aig.add_and_gate(6, [Ref::positive(1), Ref::negative(5)]);
+        aig.add_and_gate(7, [Ref::positive(6), Ref::FALSE]);
+        aig.add_output(Ref::positive(6));
+        aig.set_symbol(SymbolType::Output, 0, "g3".to_string());
+
+        let clean = aig.remove_dangling();
+        assert_eq!(clean.inputs(), &[1, 2, 3]);
+        assert_eq!(clean.and_gates().count(), 3);
+        assert_eq!(clean.outputs(), &[Ref::positive(6)]);
+        assert_eq!(clean.output_name(0), Some("g3"));
+        for i in 0..8u32 {
+            let inputs = (0..3).map(|k| i >> k & 1 != 0).collect::<Vec<_>>();
+            assert_eq!(aig.eval(inputs.clone())[&6], clean.eval(inputs)[&6]);
+        }
+    }
+
+    #[test]
+    fn test_cone_of_influence() {
+        // Two independent toggles, each with its own enable input.
+        let mut builder = AigBuilder::new();
+        let [a, b] = builder.inputs(2).try_into().unwrap();
+        let p = builder.latch(LatchInit::Zero);
+        let q = builder.latch(LatchInit::One);
+        let np = builder.xor(p, a);
+        let nq = builder.xor(q, b);
+        builder.set_latch_next(p, np);
+        builder.set_latch_next(q, nq);
+        builder.output(p);
+        builder.output(-q);
+        builder.bad(q);
+        let mut aig = builder.build();
+        aig.set_symbol(SymbolType::Input, 1, "b".to_string());
+        aig.set_symbol(SymbolType::Latch, 1, "q".to_string());
+        aig.set_symbol(SymbolType::Output, 1, "nq".to_string());
+        aig.set_symbol(SymbolType::Bad, 0, "q_high".to_string());
+        aig.add_comment("two toggles".to_string());
+
+        let cone = aig.cone_of_influence(&[Root::Output(1), Root::Bad(0)]);
+        assert_eq!(cone.comments(), &["two toggles".to_string()]);
+        assert_eq!(cone.inputs().len(), 1);
+        assert_eq!(cone.latches().len(), 1);
+        assert_eq!(cone.and_gates().count(), 3);
+        assert_eq!(cone.max_id(), 5);
+        assert_eq!(cone.input_name(cone.inputs()[0]), Some("b"));
+        assert_eq!(cone.latch_name(cone.latches()[0]), Some("q"));
+        assert_eq!(cone.output_name(0), Some("nq"));
+        assert_eq!(cone.symbols().name(SymbolType::Bad, 0), Some("q_high"));
+        let latch = cone.latch(cone.latches()[0]);
+        assert_eq!(latch.init, LatchInit::One);
+
+        // Same behaviour as the original output:
+        let inputs = [false, true, true, false];
+        let original = aig
+            .simulate_sequential(
+                None,
+                &inputs.iter().map(|&x| vec![false, x]).collect::<Vec<_>>(),
+            )
+            .unwrap();
+        let extracted = cone
+            .simulate_sequential(None, &inputs.iter().map(|&x| vec![x]).collect::<Vec<_>>())
+            .unwrap();
+        for (a, b) in original.outputs.iter().zip(&extracted.outputs) {
+            assert_eq!(a[1], b[0]);
+        }
+
+        let parts = aig.split_outputs();
+        assert_eq!(parts.len(), 2);
+        for part in &parts {
+            assert_eq!(part.inputs().len(), 1);
+            assert_eq!(part.latches().len(), 1);
+            assert_eq!(part.outputs().len(), 1);
+            assert!(part.bad().is_empty());
+        }
+    }
+}
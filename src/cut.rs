@@ -0,0 +1,231 @@
+use crate::aig::Aig;
+use crate::reference::Ref;
+
+/// The maximum number of leaves in a cut, limited by the size of the truth table.
+pub const MAX_CUT_SIZE: usize = 6;
+
+/// Truth tables of the cut variables, over [`MAX_CUT_SIZE`] variables.
+pub const VAR_TRUTH: [u64; MAX_CUT_SIZE] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+/// Cut of a node: a set of nodes (leaves) such that every path from an input
+/// or latch to the node passes through a leaf.
+///
+/// The truth table gives the function of the node in terms of the leaves:
+/// leaf `i` is variable `i`, with the truth table [`VAR_TRUTH`]`[i]`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Cut {
+    /// Leaf ids, sorted in increasing order.
+    pub leaves: Vec<u32>,
+    pub truth: u64,
+}
+
+impl Cut {
+    /// The trivial cut of a node, consisting of the node itself.
+    pub fn trivial(id: u32) -> Self {
+        Self {
+            leaves: vec![id],
+            truth: VAR_TRUTH[0],
+        }
+    }
+
+    /// The cut of the constant node, without leaves.
+    pub fn constant() -> Self {
+        Self {
+            leaves: Vec::new(),
+            truth: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Whether the cut consists of the given node only.
+    pub fn is_trivial(&self, id: u32) -> bool {
+        self.leaves == [id]
+    }
+
+    /// Truth table of the cut function over the (sorted) superset of its leaves.
+    fn expand(&self, leaves: &[u32]) -> u64 {
+        if self.leaves == leaves {
+            return self.truth;
+        }
+        let positions = self
+            .leaves
+            .iter()
+            .map(|leaf| leaves.iter().position(|x| x == leaf).unwrap())
+            .collect::<Vec<_>>();
+        let mut truth = 0;
+        for m in 0..64 {
+            let mut index = 0;
+            for (i, &p) in positions.iter().enumerate() {
+                index |= ((m >> p) & 1) << i;
+            }
+            truth |= ((self.truth >> index) & 1) << m;
+        }
+        truth
+    }
+
+    /// Cut of an AND gate with the (possibly complemented) arguments having the given cuts,
+    /// or `None` if it would have more than `k` leaves.
+    pub fn merge(a: &Cut, a_negated: bool, b: &Cut, b_negated: bool, k: usize) -> Option<Cut> {
+        let mut leaves = Vec::with_capacity(k);
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            let next = match (a.leaves.get(i), b.leaves.get(j)) {
+                (Some(&x), Some(&y)) if x == y => {
+                    i += 1;
+                    j += 1;
+                    x
+                }
+                (Some(&x), Some(&y)) if x < y => {
+                    i += 1;
+                    x
+                }
+                (Some(&x), None) => {
+                    i += 1;
+                    x
+                }
+                (_, Some(&y)) => {
+                    j += 1;
+                    y
+                }
+                (None, None) => unreachable!(),
+            };
+            if leaves.len() == k {
+                return None;
+            }
+            leaves.push(next);
+        }
+        let mask = |negated: bool| if negated { !0 } else { 0 };
+        let truth = (a.expand(&leaves) ^ mask(a_negated)) & (b.expand(&leaves) ^ mask(b_negated));
+        Some(Cut { leaves, truth })
+    }
+
+    /// Whether the leaves of the cut are a subset of the leaves of the other one.
+    pub(crate) fn dominates(&self, other: &Cut) -> bool {
+        self.leaves.iter().all(|leaf| other.leaves.contains(leaf))
+    }
+}
+
+/// Cuts of an AND gate, from the cuts of its arguments: at most `limit` merged cuts
+/// with up to `k` leaves (smallest first, without dominated cuts), followed by the trivial cut.
+pub(crate) fn node_cuts(
+    id: u32,
+    [a, b]: [Ref; 2],
+    a_cuts: &[Cut],
+    b_cuts: &[Cut],
+    k: usize,
+    limit: usize,
+) -> Vec<Cut> {
+    let mut cuts = Vec::new();
+    for x in a_cuts {
+        for y in b_cuts {
+            if let Some(cut) = Cut::merge(x, a.is_negated(), y, b.is_negated(), k) {
+                cuts.push(cut);
+            }
+        }
+    }
+    cuts.sort_by(|x, y| (x.len(), &x.leaves).cmp(&(y.len(), &y.leaves)));
+    cuts.dedup_by(|x, y| x.leaves == y.leaves);
+    let mut result: Vec<Cut> = Vec::new();
+    for cut in cuts {
+        if result.len() == limit {
+            break;
+        }
+        if !result.iter().any(|other| other.dominates(&cut)) {
+            result.push(cut);
+        }
+    }
+    result.push(Cut::trivial(id));
+    result
+}
+
+impl Aig {
+    /// Enumerate `k`-feasible cuts of all nodes, keeping at most `limit` non-trivial cuts
+    /// per node (the smallest ones, without dominated cuts). Returns the cuts indexed by id.
+    ///
+    /// Inputs and latches only have their trivial cut, the constant has the empty cut.
+    /// Each AND gate has its trivial cut last.
+    pub fn enumerate_cuts(&self, k: usize, limit: usize) -> Vec<Vec<Cut>> {
+        assert!(
+            (1..=MAX_CUT_SIZE).contains(&k),
+            "Cut size must be between 1 and {}",
+            MAX_CUT_SIZE
+        );
+        let mut cuts = vec![Vec::new(); self.max_id() as usize + 1];
+        cuts[0] = vec![Cut::constant()];
+        for &id in self.inputs().iter().chain(self.latches()) {
+            cuts[id as usize] = vec![Cut::trivial(id)];
+        }
+        for &id in self.topological_order() {
+            let args = self.gate(id).args;
+            let [a, b] = args.map(|r| &cuts[r.id() as usize]);
+            cuts[id as usize] = node_cuts(id, args, a, b, k, limit);
+        }
+        cuts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builder::AigBuilder;
+
+    #[test]
+    fn test_merge() {
+        let a = Cut::trivial(3);
+        let b = Cut::trivial(5);
+        let ab = Cut::merge(&a, false, &b, true, 4).unwrap();
+        assert_eq!(ab.leaves, vec![3, 5]);
+        assert_eq!(ab.truth, VAR_TRUTH[0] & !VAR_TRUTH[1]);
+
+        let c = Cut::trivial(4);
+        let abc = Cut::merge(&ab, true, &c, false, 4).unwrap();
+        assert_eq!(abc.leaves, vec![3, 4, 5]);
+        assert_eq!(abc.truth, !(VAR_TRUTH[0] & !VAR_TRUTH[2]) & VAR_TRUTH[1]);
+        assert!(Cut::merge(&ab, false, &c, false, 2).is_none());
+    }
+
+    #[test]
+    fn test_enumerate_cuts() {
+        // Cut functions agree with the simulation of the AIG.
+        let mut builder = AigBuilder::new();
+        let x = builder.inputs(5);
+        let t = builder.xor(x[0], x[1]);
+        let u = builder.mux(x[2], t, x[3]);
+        let v = builder.maj(u, x[4], t);
+        builder.output(v);
+        let aig = builder.build();
+
+        let simulation = aig.simulate_random::<1>(42);
+        for k in [2, 4, 6] {
+            let cuts = aig.enumerate_cuts(k, 8);
+            for &id in aig.topological_order() {
+                let cuts = &cuts[id as usize];
+                assert!(cuts.last().unwrap().is_trivial(id));
+                for cut in cuts {
+                    assert!(cut.len() <= k);
+                    for pattern in 0..64 {
+                        let index = cut.leaves.iter().enumerate().fold(0, |index, (i, &leaf)| {
+                            index | (simulation.bit(Ref::positive(leaf), pattern) as usize) << i
+                        });
+                        let expected = simulation.bit(Ref::positive(id), pattern);
+                        assert_eq!((cut.truth >> index) & 1 != 0, expected);
+                    }
+                }
+            }
+        }
+    }
+}
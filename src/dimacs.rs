@@ -0,0 +1,241 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use eyre::{eyre, WrapErr};
+
+use crate::aig::Aig;
+use crate::cnf::{Cnf, CnfEncoder, CnfOptions, Polarity};
+use crate::reference::Ref;
+use crate::sat::ClauseSink;
+
+impl Cnf {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        log::debug!("Reading CNF from {}", path.display());
+        let f = File::open(path).wrap_err_with(|| format!("Failed to open {}", path.display()))?;
+        Self::parse(BufReader::new(f))
+    }
+
+    /// Parse a formula in the DIMACS format.
+    ///
+    /// Clauses may span several lines and are terminated by `0`.
+    /// Comment lines (`c ...`) are kept in [`Cnf::comments`].
+    pub fn parse(r: impl BufRead) -> eyre::Result<Self> {
+        let mut cnf = Cnf::new();
+        let mut header: Option<(usize, usize)> = None;
+        let mut clause = Vec::new();
+
+        for (index, line) in r.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('c') {
+                if comment.is_empty() || comment.starts_with(' ') {
+                    cnf.comments.push(comment.trim_start().to_string());
+                    continue;
+                }
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('%') {
+                // End marker used by SATLIB benchmarks.
+                break;
+            }
+            if line.starts_with('p') {
+                if header.is_some() {
+                    return Err(eyre!("Duplicate header on line {}", index + 1));
+                }
+                let tokens = line.split_whitespace().collect::<Vec<_>>();
+                let [_, "cnf", vars, clauses] = tokens[..] else {
+                    return Err(eyre!(
+                        "Invalid header '{}', expected 'p cnf <vars> <clauses>'",
+                        line
+                    ));
+                };
+                let vars = vars.parse().wrap_err("Invalid number of variables")?;
+                let clauses = clauses.parse().wrap_err("Invalid number of clauses")?;
+                cnf.num_vars = vars;
+                header = Some((vars, clauses));
+                continue;
+            }
+
+            let (num_vars, _) =
+                header.ok_or_else(|| eyre!("Clause before the header on line {}", index + 1))?;
+            for token in line.split_whitespace() {
+                let lit = token.parse::<i32>().wrap_err_with(|| {
+                    format!("Invalid literal '{}' on line {}", token, index + 1)
+                })?;
+                if lit == 0 {
+                    cnf.clauses.push(std::mem::take(&mut clause));
+                } else if lit.unsigned_abs() as usize > num_vars {
+                    return Err(eyre!(
+                        "Literal {} on line {} exceeds the number of variables {}",
+                        lit,
+                        index + 1,
+                        num_vars
+                    ));
+                } else {
+                    clause.push(lit);
+                }
+            }
+        }
+
+        let (_, num_clauses) = header.ok_or_else(|| eyre!("Missing header"))?;
+        if !clause.is_empty() {
+            return Err(eyre!("Last clause is not terminated by 0"));
+        }
+        if cnf.clauses.len() != num_clauses {
+            return Err(eyre!(
+                "Expected {} clauses, got {}",
+                num_clauses,
+                cnf.clauses.len()
+            ));
+        }
+        Ok(cnf)
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> eyre::Result<()> {
+        let path = path.as_ref();
+        log::debug!("Writing CNF to {}", path.display());
+        let f =
+            File::create(path).wrap_err_with(|| format!("Failed to create {}", path.display()))?;
+        let mut f = BufWriter::new(f);
+        write!(f, "{}", self)?;
+        f.flush()?;
+        Ok(())
+    }
+}
+
+impl FromStr for Cnf {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        Self::parse(s.as_bytes())
+    }
+}
+
+impl Display for Cnf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for comment in &self.comments {
+            if comment.is_empty() {
+                writeln!(f, "c")?;
+            } else {
+                writeln!(f, "c {}", comment)?;
+            }
+        }
+        writeln!(f, "p cnf {} {}", self.num_vars, self.clauses.len())?;
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(f, "{} ", lit)?;
+            }
+            writeln!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+impl Aig {
+    /// Encode the cones of all outputs into a formula ready to be written as DIMACS.
+    ///
+    /// The outputs with the given indices are asserted by unit clauses.
+    /// The comments record the mapping and the asserted outputs:
+    ///
+    /// ```text
+    /// c map <id> <lit>       literal of the AIG node
+    /// c output <index> <lit> literal of the output
+    /// c assert <index>       the output is asserted
+    /// ```
+    ///
+    /// With [`CnfOptions::polarity`], outputs are encoded in [`Polarity::Positive`].
+    pub fn to_dimacs(&self, options: CnfOptions, asserted: &[usize]) -> eyre::Result<Cnf> {
+        if let Some(&index) = asserted.iter().find(|&&i| i >= self.outputs().len()) {
+            return Err(eyre!("Output {} does not exist", index));
+        }
+
+        let mut cnf = Cnf::new();
+        let mut encoder = CnfEncoder::with_options(self, options);
+        let outputs = self
+            .outputs()
+            .iter()
+            .map(|&r| encoder.encode_with(&mut cnf, r, Polarity::Positive))
+            .collect::<Vec<_>>();
+        for &index in asserted {
+            cnf.add_clause(&[outputs[index]]);
+        }
+
+        for id in 0..=self.max_id() {
+            if let Some(lit) = encoder.lit(Ref::from_raw(id << 1)) {
+                cnf.comments.push(format!("map {} {}", id, lit));
+            }
+        }
+        for (index, lit) in outputs.iter().enumerate() {
+            cnf.comments.push(format!("output {} {}", index, lit));
+        }
+        for index in asserted {
+            cnf.comments.push(format!("assert {}", index));
+        }
+        Ok(cnf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::sat::{SatSolver, Solver};
+
+    #[test]
+    fn test_dimacs_round_trip() {
+        let input = indoc! {"
+            c example
+            p cnf 3 2
+            1 -3 0
+            2 3
+            -1 0
+        "};
+        let cnf = input.parse::<Cnf>().unwrap();
+        assert_eq!(cnf.num_vars, 3);
+        assert_eq!(cnf.clauses, vec![vec![1, -3], vec![2, 3, -1]]);
+        assert_eq!(cnf.comments, vec!["example"]);
+
+        let output = cnf.to_string();
+        assert_eq!(output, "c example\np cnf 3 2\n1 -3 0\n2 3 -1 0\n");
+        assert_eq!(output.parse::<Cnf>().unwrap(), cnf);
+
+        assert!("p cnf 2 1\n1 3 0\n".parse::<Cnf>().is_err());
+        assert!("p cnf 2 2\n1 2 0\n".parse::<Cnf>().is_err());
+        assert!("1 2 0\n".parse::<Cnf>().is_err());
+    }
+
+    #[test]
+    fn test_to_dimacs() {
+        // Miter of two XOR implementations, its output is unsatisfiable.
+        let aig = Aig::from_file("data/examples/xorxormiter.aag").unwrap();
+        let cnf = aig.to_dimacs(CnfOptions::default(), &[0]).unwrap();
+        assert!(cnf.comments.contains(&"assert 0".to_string()));
+        let output = cnf
+            .comments
+            .iter()
+            .find_map(|c| c.strip_prefix("output 0 "))
+            .unwrap();
+        let output = output.parse::<i32>().unwrap();
+        assert!(cnf.clauses.contains(&vec![output]));
+        assert!(cnf
+            .comments
+            .contains(&format!("map {} {}", aig.outputs()[0].id(), output.abs())));
+
+        let cnf = cnf.to_string().parse::<Cnf>().unwrap();
+        let mut solver = Solver::new();
+        for clause in &cnf.clauses {
+            solver.add_clause(clause);
+        }
+        assert!(solver.solve().is_unsat());
+
+        assert!(aig.to_dimacs(CnfOptions::default(), &[1]).is_err());
+    }
+}
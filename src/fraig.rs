@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+
+use crate::aig::Aig;
+use crate::builder::{image, AigBuilder};
+use crate::cnf::CnfEncoder;
+use crate::reference::Ref;
+use crate::sat::{SatSolver, SolveResult, Solver};
+use crate::sim::SplitMix64;
+
+/// Statistics of [`Aig::fraig`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FraigStats {
+    /// Nodes merged into a functionally equivalent (possibly complemented) node.
+    pub merged: usize,
+    /// SAT calls made to prove or refute candidate equivalences.
+    pub sat_calls: usize,
+    /// Candidate equivalences refuted by a counterexample.
+    pub refuted: usize,
+    /// AND gates removed in total.
+    pub removed_gates: usize,
+}
+
+/// Number of random simulation words (64 patterns each) for the initial signatures.
+const INITIAL_WORDS: usize = 4;
+
+/// Candidate equivalence classes, given by simulation signatures.
+///
+/// Signatures are normalised so that the first pattern is zero, so a node and
+/// its complement fall into the same class. Each class holds representatives,
+/// which are pairwise distinct: counterexamples are collected and simulated
+/// 64 at a time, so a class may contain several representatives until the
+/// counterexamples separating them are simulated.
+struct Classes {
+    /// Simulation words of each node, indexed by id.
+    signatures: Vec<Vec<u64>>,
+    /// Representatives, in the order they were added.
+    representatives: Vec<u32>,
+    /// Normalised signature to representatives.
+    table: HashMap<Vec<u64>, Vec<u32>>,
+    /// Counterexamples (values of the inputs and latches) which are not yet simulated.
+    pending: Vec<Vec<bool>>,
+}
+
+impl Classes {
+    fn phase(&self, id: u32) -> bool {
+        self.signatures[id as usize][0] & 1 != 0
+    }
+
+    fn key(&self, id: u32) -> Vec<u64> {
+        let mask = if self.phase(id) { !0 } else { 0 };
+        self.signatures[id as usize]
+            .iter()
+            .map(|&w| w ^ mask)
+            .collect()
+    }
+
+    /// Candidate representatives of the node, as references with the relative phase.
+    fn candidates(&self, id: u32) -> Vec<Ref> {
+        let Some(class) = self.table.get(&self.key(id)) else {
+            return Vec::new();
+        };
+        class
+            .iter()
+            .map(|&repr| {
+                let negated = self.phase(id) != self.phase(repr);
+                Ref::from_raw(repr << 1 | negated as u32)
+            })
+            .collect()
+    }
+
+    fn add_representative(&mut self, id: u32) {
+        self.table.entry(self.key(id)).or_default().push(id);
+        self.representatives.push(id);
+    }
+
+    /// Record a counterexample refuting a candidate equivalence.
+    /// Once 64 counterexamples are collected, they are simulated to split the classes.
+    fn add_counterexample(&mut self, aig: &Aig, model: Vec<bool>, rng: &mut SplitMix64) {
+        self.pending.push(model);
+        if self.pending.len() == 64 {
+            self.refine(aig, rng);
+        }
+    }
+
+    /// Append one simulation word with the pending counterexamples to all signatures
+    /// and split the classes. Unused patterns of the word are random.
+    fn refine(&mut self, aig: &Aig, rng: &mut SplitMix64) {
+        let num_leaves = aig.inputs().len() + aig.latches().len();
+        let mut words = (0..num_leaves)
+            .map(|_| [rng.next_u64()])
+            .collect::<Vec<_>>();
+        for (bit, model) in self.pending.drain(..).enumerate() {
+            for (word, value) in words.iter_mut().zip(model) {
+                word[0] = word[0] & !(1 << bit) | (value as u64) << bit;
+            }
+        }
+        let (inputs, state) = words.split_at(aig.inputs().len());
+        let simulation = aig.simulate_with_state(inputs, state);
+        for (signature, words) in self.signatures.iter_mut().zip(simulation.values()) {
+            signature.push(words[0]);
+        }
+
+        self.table.clear();
+        for &id in &self.representatives {
+            self.table.entry(self.key(id)).or_default().push(id);
+        }
+    }
+}
+
+impl Aig {
+    /// SAT sweeping: merge functionally equivalent (or complementary) nodes,
+    /// using the built-in SAT solver.
+    pub fn fraig(&self) -> (Aig, FraigStats) {
+        self.fraig_with(&mut Solver::new())
+    }
+
+    /// SAT sweeping with the given SAT solver.
+    ///
+    /// Random simulation splits the nodes into candidate equivalence classes.
+    /// Nodes are visited in topological order, and each one is checked with SAT against
+    /// the representative of its class: proven equivalences are merged (and added to the
+    /// solver to speed up later checks), while counterexamples are collected and simulated
+    /// in batches of 64 to refine the classes.
+    ///
+    /// Latches are treated as free variables, so only combinational equivalences are
+    /// found, which makes the result valid for sequential AIGs as well. Dangling gates
+    /// are removed (see [`Aig::remove_dangling`]).
+    pub fn fraig_with<S: SatSolver + ?Sized>(&self, solver: &mut S) -> (Aig, FraigStats) {
+        let mut rng = SplitMix64::new(0x5eed);
+        let mut stats = FraigStats::default();
+
+        let leaves = self
+            .inputs()
+            .iter()
+            .chain(self.latches())
+            .copied()
+            .collect::<Vec<_>>();
+        let mut signatures = vec![Vec::new(); self.max_id() as usize + 1];
+        for _ in 0..INITIAL_WORDS {
+            let simulation = self.simulate_random::<1>(rng.next_u64());
+            for (signature, words) in signatures.iter_mut().zip(simulation.values()) {
+                signature.push(words[0]);
+            }
+        }
+        let mut classes = Classes {
+            signatures,
+            representatives: Vec::new(),
+            table: HashMap::new(),
+            pending: Vec::new(),
+        };
+
+        let mut builder = AigBuilder::new();
+        let mut map = vec![Ref::FALSE; self.max_id() as usize + 1];
+        for &id in self.inputs() {
+            map[id as usize] = builder.input();
+        }
+        for &id in self.latches() {
+            map[id as usize] = builder.latch(self.latch(id).init);
+        }
+        classes.add_representative(0);
+        for &id in &leaves {
+            classes.add_representative(id);
+        }
+
+        let mut encoder = CnfEncoder::new(self);
+        for &id in self.topological_order() {
+            let node = Ref::positive(id);
+            let mut merged = false;
+            for candidate in classes.candidates(id) {
+                // Check `node == candidate` in both directions:
+                let a = encoder.encode(solver, node);
+                let b = encoder.encode(solver, candidate);
+                stats.sat_calls += 1;
+                let mut result = solver.solve_with(&[a, -b]);
+                if result == SolveResult::Unsat {
+                    stats.sat_calls += 1;
+                    result = solver.solve_with(&[-a, b]);
+                }
+
+                match result {
+                    SolveResult::Unsat => {
+                        solver.add_clause(&[-a, b]);
+                        solver.add_clause(&[a, -b]);
+                        map[id as usize] = image(&map, candidate);
+                        stats.merged += 1;
+                        merged = true;
+                        break;
+                    }
+                    SolveResult::Sat => {
+                        stats.refuted += 1;
+                        let model = leaves
+                            .iter()
+                            .map(|&leaf| {
+                                encoder
+                                    .lit(Ref::positive(leaf))
+                                    .and_then(|lit| solver.value(lit))
+                                    .unwrap_or(false)
+                            })
+                            .collect::<Vec<_>>();
+                        classes.add_counterexample(self, model, &mut rng);
+                    }
+                }
+            }
+            if !merged {
+                let [left, right] = self.gate(id).args;
+                map[id as usize] = builder.and(image(&map, left), image(&map, right));
+                classes.add_representative(id);
+            }
+        }
+
+        for &id in self.latches() {
+            builder.set_latch_next(map[id as usize], image(&map, self.latch(id).next));
+        }
+        builder.import_properties(self, &map);
+        let aig = self.finish_rebuild(builder.build(), None);
+
+        stats.removed_gates = self.and_gates().count() - aig.and_gates().count();
+        log::debug!(
+            "Fraig merged {} nodes with {} SAT calls ({} refuted), removing {} gates",
+            stats.merged,
+            stats.sat_calls,
+            stats.refuted,
+            stats.removed_gates
+        );
+        (aig, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cec::Pairing;
+    use crate::node::LatchInit;
+
+    #[test]
+    fn test_fraig_xorxormiter() {
+        // The miter of two XOR implementations collapses to a constant.
+        let aig = Aig::from_file("data/examples/xorxormiter.aag").unwrap();
+        let (fraiged, stats) = aig.fraig();
+        assert_eq!(fraiged.outputs(), &[Ref::FALSE]);
+        assert_eq!(fraiged.and_gates().count(), 0);
+        assert_eq!(stats.removed_gates, aig.and_gates().count());
+        assert!(stats.merged > 0);
+    }
+
+    #[test]
+    fn test_fraig_adders() {
+        // Two different implementations of a 4-bit adder, sharing the inputs.
+        let mut builder = AigBuilder::new();
+        let a = builder.inputs(4);
+        let b = builder.inputs(4);
+        let (ripple, _) = builder.add(&a, &b, Ref::FALSE);
+        // Sum bits as XOR of inputs and carries, with carries built as majorities of ORs.
+        let mut carry = Ref::FALSE;
+        let mut sum = Vec::new();
+        for i in 0..4 {
+            let t = builder.xor(a[i], b[i]);
+            let s = builder.xnor(t, -carry);
+            sum.push(s);
+            let ab = builder.and(a[i], b[i]);
+            let or = builder.or(a[i], b[i]);
+            let c = builder.and(or, carry);
+            carry = builder.or(ab, c);
+        }
+        for (&x, &y) in ripple.iter().zip(&sum) {
+            builder.output(x);
+            builder.output(y);
+        }
+        let aig = builder.build();
+
+        let (fraiged, stats) = aig.fraig();
+        assert!(stats.merged > 0);
+        assert!(fraiged.and_gates().count() < aig.and_gates().count());
+        for pair in fraiged.outputs().chunks(2) {
+            assert_eq!(pair[0], pair[1]);
+        }
+        let result = aig.check_equivalence(&fraiged, Pairing::Position).unwrap();
+        assert!(result.is_equivalent());
+    }
+
+    #[test]
+    fn test_fraig_complemented() {
+        // x & ~y and ~(~x | y) are equal, while ~x | y is their complement.
+        let mut builder = AigBuilder::new();
+        let [x, y] = builder.inputs(2).try_into().unwrap();
+        let l = builder.latch(LatchInit::One);
+        let p = builder.and(x, -y);
+        let q = builder.xor(x, y);
+        let r = builder.and(q, x); // = x & ~y
+        let s = builder.or(-x, y); // = ~(x & ~y), already strashed
+        let t = builder.and(r, l);
+        builder.set_latch_next(l, t);
+        builder.output(p);
+        builder.output(r);
+        builder.output(-s);
+        let aig = builder.build();
+
+        let (fraiged, stats) = aig.fraig();
+        assert_eq!(stats.merged, 1);
+        assert_eq!(fraiged.outputs()[0], fraiged.outputs()[1]);
+        assert_eq!(fraiged.outputs()[0], fraiged.outputs()[2]);
+        assert_eq!(fraiged.latches().len(), 1);
+
+        let inputs = [[true, false], [true, true], [true, false], [false, false]]
+            .iter()
+            .map(|v| v.to_vec())
+            .collect::<Vec<_>>();
+        let expected = aig.simulate_sequential(None, &inputs).unwrap();
+        let actual = fraiged.simulate_sequential(None, &inputs).unwrap();
+        assert_eq!(actual.outputs, expected.outputs);
+        assert_eq!(actual.states, expected.states);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::aig::Aig;
+use crate::bmc::{solve_any, Unrolling};
+use crate::sat::{SatSolver, Solver};
+use crate::witness::{Property, Witness};
+
+/// Options of the k-induction engine.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct InductionOptions {
+    /// The maximum induction depth to try.
+    pub max_depth: usize,
+    /// Require the states in the inductive step to be pairwise distinct.
+    /// This makes k-induction complete for large enough depths, at the cost
+    /// of a quadratic number of constraints.
+    pub simple_path: bool,
+}
+
+impl Default for InductionOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 20,
+            simple_path: true,
+        }
+    }
+}
+
+/// Result of k-induction.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum InductionResult {
+    /// All safety properties hold; they are `depth`-inductive:
+    /// no path of `depth` good states is followed by a bad state.
+    Proved { depth: usize },
+    /// A safety property is violated, the witness can be replayed with [`Aig::check_witness`].
+    Counterexample(Witness),
+    /// Neither proved nor refuted up to the maximum depth.
+    Unknown { depth: usize },
+}
+
+impl Aig {
+    /// Prove the safety properties (see [`Aig::safety_properties`]) by k-induction
+    /// with the built-in SAT solver.
+    pub fn k_induction(&self, options: InductionOptions) -> InductionResult {
+        self.k_induction_with::<Solver>(options)
+    }
+
+    /// Prove the safety properties by k-induction, using two instances of the SAT solver:
+    /// one for the base case (unrolling from reset) and one for the inductive step
+    /// (unrolling from an arbitrary state).
+    pub fn k_induction_with<S: SatSolver + Default>(
+        &self,
+        options: InductionOptions,
+    ) -> InductionResult {
+        let properties = self.safety_properties();
+        let mut base_solver = S::default();
+        let mut step_solver = S::default();
+        let mut base = Unrolling::new(self, true);
+        let mut step = Unrolling::new(self, false);
+
+        for depth in 0..=options.max_depth {
+            // Base case: no violation in frame `depth` from reset.
+            let frame = base.add_frame(&mut base_solver);
+            let bad = properties
+                .iter()
+                .map(|&p| base.encode(&mut base_solver, frame, p))
+                .collect::<Vec<_>>();
+            if let Some(index) = solve_any(&mut base_solver, &bad) {
+                log::debug!("k-induction: counterexample in frame {}", frame);
+                return InductionResult::Counterexample(
+                    base.witness(&base_solver, Property::Bad(index)),
+                );
+            }
+            // The properties hold in this frame for the following base cases:
+            for lit in bad {
+                base_solver.add_clause(&[-lit]);
+            }
+
+            // Inductive step: `depth` good states followed by a bad one.
+            let frame = step.add_frame(&mut step_solver);
+            debug_assert_eq!(step.len(), depth + 1);
+            if options.simple_path {
+                let state = step.state(&mut step_solver, frame);
+                for other in 0..frame {
+                    let other = step.state(&mut step_solver, other);
+                    add_distinct(&mut step_solver, &state, &other);
+                }
+            }
+            let bad = properties
+                .iter()
+                .map(|&p| step.encode(&mut step_solver, frame, p))
+                .collect::<Vec<_>>();
+            if solve_any(&mut step_solver, &bad).is_none() {
+                log::debug!("k-induction: proved at depth {}", depth);
+                return InductionResult::Proved { depth };
+            }
+            for lit in bad {
+                step_solver.add_clause(&[-lit]);
+            }
+            log::debug!("k-induction: depth {} is not inductive", depth);
+        }
+
+        InductionResult::Unknown {
+            depth: options.max_depth,
+        }
+    }
+}
+
+/// Require the two states to differ in some latch.
+fn add_distinct<S: SatSolver + ?Sized>(solver: &mut S, a: &[i32], b: &[i32]) {
+    let mut clause = Vec::with_capacity(a.len());
+    for (&x, &y) in a.iter().zip(b) {
+        // d -> (x ^ y)
+        let d = solver.new_var();
+        solver.add_clause(&[-d, x, y]);
+        solver.add_clause(&[-d, -x, -y]);
+        clause.push(d);
+    }
+    solver.add_clause(&clause);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builder::AigBuilder;
+    use crate::node::LatchInit;
+    use crate::reference::Ref;
+
+    /// 2-bit counter, enabled by a latch that stays at zero, with a stall input.
+    /// It is bad when the counter reaches 3, which is unreachable.
+    fn stalled_counter() -> Aig {
+        let mut builder = AigBuilder::new();
+        let stall = builder.input();
+        let enable = builder.latch(LatchInit::Zero);
+        builder.set_latch_next(enable, enable);
+        let bits = vec![
+            builder.latch(LatchInit::Zero),
+            builder.latch(LatchInit::Zero),
+        ];
+        let (increment, _) = builder.add(&bits, &[Ref::TRUE, Ref::FALSE], Ref::FALSE);
+        let count = builder.and(enable, -stall);
+        let next = builder.mux_word(count, &increment, &bits);
+        for (&bit, &next) in bits.iter().zip(&next) {
+            builder.set_latch_next(bit, next);
+        }
+        let bad = builder.and(bits[0], bits[1]);
+        builder.bad(bad);
+        builder.build()
+    }
+
+    #[test]
+    fn test_simple_path() {
+        let aig = stalled_counter();
+        let options = InductionOptions {
+            max_depth: 6,
+            simple_path: false,
+        };
+        // Stalling in an unreachable state yields arbitrarily long paths to the bad state.
+        assert_eq!(
+            aig.k_induction(options),
+            InductionResult::Unknown { depth: 6 }
+        );
+
+        let options = InductionOptions {
+            max_depth: 6,
+            simple_path: true,
+        };
+        // The longest simple path is 0 -> 1 -> 2 -> 3:
+        assert_eq!(
+            aig.k_induction(options),
+            InductionResult::Proved { depth: 4 }
+        );
+    }
+
+    #[test]
+    fn test_toggle_re() {
+        // Inputs of toggle-re are [enable, reset]:
+        let mut aig = Aig::from_file("data/examples/toggle-re.aag").unwrap();
+        let q = aig.outputs()[0];
+        let not_q = aig.outputs()[1];
+
+        // Q and ~Q are never both true:
+        let mut both = aig.clone();
+        let id = both.max_id() + 1;
+        both.add_and_gate(id, [q, not_q]);
+        both.add_bad(Ref::positive(id));
+        assert_eq!(
+            both.k_induction(InductionOptions::default()),
+            InductionResult::Proved { depth: 0 }
+        );
+
+        // Q is never true while a latch stuck at zero is set. The toggle may stay
+        // disabled in such an unreachable state for arbitrarily long before Q is set,
+        // so only the simple-path constraint makes the property inductive.
+        let mut stuck = aig.clone();
+        let latch = stuck.max_id() + 1;
+        stuck.add_latch(latch, Ref::positive(latch), LatchInit::Zero);
+        stuck.add_and_gate(latch + 1, [Ref::positive(latch), q]);
+        stuck.add_bad(Ref::positive(latch + 1));
+        let options = InductionOptions {
+            max_depth: 6,
+            simple_path: false,
+        };
+        assert_eq!(
+            stuck.k_induction(options),
+            InductionResult::Unknown { depth: 6 }
+        );
+        let options = InductionOptions {
+            max_depth: 6,
+            simple_path: true,
+        };
+        assert_eq!(
+            stuck.k_induction(options),
+            InductionResult::Proved { depth: 2 }
+        );
+
+        // Q is reachable:
+        aig.add_bad(q);
+        match aig.k_induction(InductionOptions::default()) {
+            InductionResult::Counterexample(witness) => {
+                assert_eq!(aig.check_witness(&witness).unwrap(), 1);
+            }
+            result => panic!("Expected a counterexample, got {:?}", result),
+        }
+
+        // ...but not when the toggle is never enabled.
+        // Q may hold in an arbitrary state, but it is never set again.
+        aig.add_constraint(-Ref::positive(aig.inputs()[0]));
+        assert_eq!(
+            aig.k_induction(InductionOptions::default()),
+            InductionResult::Proved { depth: 1 }
+        );
+    }
+}
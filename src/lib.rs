@@ -1,12 +1,37 @@
 pub mod aig;
 pub mod aiger;
+pub mod balance;
+pub mod bmc;
+pub mod builder;
+pub mod cec;
 pub mod cnf;
+pub mod coi;
+pub mod cut;
+pub mod dimacs;
+pub mod fraig;
+pub mod induction;
+pub mod lut;
+pub mod mapping;
+mod network;
 pub mod node;
+pub mod npn;
 pub mod parse;
+pub mod pdr;
+pub mod refactor;
 pub mod reference;
+pub mod renumber;
+pub mod resub;
+pub mod rewrite;
+pub mod sat;
+pub mod sim;
+pub mod simplify;
+pub mod symbol;
+mod window;
+pub mod witness;
 pub mod write;
 
-pub(crate) mod toposort;
-
 #[cfg(feature = "python")]
 mod python;
+
+#[cfg(test)]
+mod test_utils;
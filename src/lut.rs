@@ -0,0 +1,653 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use eyre::WrapErr;
+
+use crate::aiger::SymbolType;
+use crate::node::{AigLatch, LatchInit};
+use crate::reference::Ref;
+use crate::symbol::SymbolTable;
+
+/// Look-up table: a node computing an arbitrary function of its fanins.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Lut {
+    /// Id of the AIG node implemented by the LUT.
+    pub id: u32,
+    /// Ids of the fanins: inputs, latches or other LUTs.
+    pub fanins: Vec<u32>,
+    /// Truth table over the fanins: bit `m` is the value of the LUT when each fanin `i`
+    /// has the value of bit `i` of `m`. Only the lower `2^fanins.len()` bits are used.
+    pub truth: u64,
+}
+
+impl Lut {
+    /// Value of the LUT for the given values of the fanins.
+    pub fn eval(&self, values: impl IntoIterator<Item = bool>) -> bool {
+        let index = values
+            .into_iter()
+            .enumerate()
+            .fold(0, |index, (i, value)| index | (value as usize) << i);
+        (self.truth >> index) & 1 != 0
+    }
+}
+
+/// Network of LUTs, obtained by mapping an AIG (see [`Aig::map_luts`][crate::aig::Aig::map_luts]).
+///
+/// Nodes keep the ids of the AIG: inputs and latches, and LUTs for the mapped AND gates.
+/// Outputs, properties and latch next-states reference them, possibly complemented.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct LutNetwork {
+    pub inputs: Vec<u32>,
+    pub latches: Vec<AigLatch>,
+    /// LUTs in topological order.
+    pub luts: Vec<Lut>,
+    pub outputs: Vec<Ref>,
+    pub bad: Vec<Ref>,
+    pub constraints: Vec<Ref>,
+    pub justice: Vec<Vec<Ref>>,
+    pub fairness: Vec<Ref>,
+    /// Names of the inputs, latches, outputs and properties.
+    pub symbols: SymbolTable,
+}
+
+impl LutNetwork {
+    pub fn num_luts(&self) -> usize {
+        self.luts.len()
+    }
+
+    /// Return the maximum number of LUTs on a path from an input or latch.
+    pub fn depth(&self) -> u32 {
+        let mut levels = HashMap::new();
+        let mut depth = 0;
+        for lut in &self.luts {
+            let level = 1 + lut
+                .fanins
+                .iter()
+                .map(|id| levels.get(id).copied().unwrap_or(0))
+                .max()
+                .unwrap_or(0);
+            levels.insert(lut.id, level);
+            depth = depth.max(level);
+        }
+        depth
+    }
+
+    /// Evaluate the combinational network, as [`Aig::eval`][crate::aig::Aig::eval].
+    pub fn eval(&self, input_values: Vec<bool>) -> BTreeMap<u32, bool> {
+        assert_eq!(input_values.len(), self.inputs.len());
+
+        let mut values = BTreeMap::new();
+        for (&id, value) in self.inputs.iter().zip(input_values) {
+            values.insert(id, value);
+        }
+        for lut in &self.luts {
+            let value = lut.eval(lut.fanins.iter().map(|id| match values.get(id) {
+                Some(&value) => value,
+                None => panic!("Value of node {} is not available", id),
+            }));
+            values.insert(lut.id, value);
+        }
+        values
+    }
+
+    /// Write the network to a file.
+    ///
+    /// The JSON format is used for files with the `.json` extension,
+    /// the BLIF format is used otherwise.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> eyre::Result<()> {
+        let path = path.as_ref();
+        log::debug!("Writing LUT network to {}", path.display());
+        let f =
+            File::create(path).wrap_err_with(|| format!("Failed to create {}", path.display()))?;
+        let mut f = BufWriter::new(f);
+        if path.extension().is_some_and(|ext| ext == "json") {
+            self.write_json(&mut f)?;
+        } else {
+            self.write_blif(&mut f)?;
+        }
+        f.flush()?;
+        Ok(())
+    }
+
+    /// Write the network in the BLIF format, with a `.names` table per LUT.
+    ///
+    /// The properties are written as additional outputs, after the outputs: bad states,
+    /// invariant constraints, justice and fairness properties, in this order.
+    /// Signals are named after the symbols of the inputs, latches, outputs and properties,
+    /// or `i<index>`, `l<index>`, `o<index>`, `b<index>`, `c<index>`, `j<index>` and
+    /// `f<index>` without symbols. Justice properties have a signal per literal, suffixed
+    /// with `_<index>`. LUTs are named `n<id>`.
+    ///
+    /// Whitespace, `#`, `\` and `=` in symbols are replaced with `_`. Names are made
+    /// distinct with a suffix `_<k>`, added to the generated names before the symbols.
+    pub fn write_blif(&self, writer: &mut impl Write) -> eyre::Result<()> {
+        let mut names = BlifNames::new(self);
+        let roots = std::mem::take(&mut names.roots);
+
+        writeln!(writer, ".model top")?;
+        write!(writer, ".inputs")?;
+        for id in &self.inputs {
+            write!(writer, " {}", names.nodes[id])?;
+        }
+        writeln!(writer)?;
+        write!(writer, ".outputs")?;
+        for (name, _) in &roots {
+            write!(writer, " {}", name)?;
+        }
+        writeln!(writer)?;
+
+        let mut buffers = Vec::new();
+        for latch in &self.latches {
+            let name = names.nodes[&latch.id].clone();
+            let next = if latch.next.id() != 0 && !latch.next.is_negated() {
+                names.nodes[&latch.next.id()].clone()
+            } else {
+                let next = names.unique(&format!("{}_next", name));
+                buffers.push((next.clone(), latch.next));
+                next
+            };
+            let init = match latch.init {
+                LatchInit::Zero => 0,
+                LatchInit::One => 1,
+                LatchInit::Nondet => 2,
+            };
+            writeln!(writer, ".latch {} {} {}", next, name, init)?;
+        }
+
+        for lut in &self.luts {
+            write!(writer, ".names")?;
+            for id in &lut.fanins {
+                write!(writer, " {}", names.nodes[id])?;
+            }
+            writeln!(writer, " {}", names.nodes[&lut.id])?;
+            // Rows of the on-set, or of the off-set if it is smaller (and not empty):
+            let size = 1 << lut.fanins.len();
+            let ones = (0..size).filter(|m| (lut.truth >> m) & 1 != 0).count();
+            let value = 2 * ones <= size || ones == size;
+            for m in (0..size).filter(|m| ((lut.truth >> m) & 1 != 0) == value) {
+                for i in 0..lut.fanins.len() {
+                    write!(writer, "{}", (m >> i) & 1)?;
+                }
+                if lut.fanins.is_empty() {
+                    writeln!(writer, "{}", value as u8)?;
+                } else {
+                    writeln!(writer, " {}", value as u8)?;
+                }
+            }
+        }
+
+        buffers.extend(roots);
+        for (name, r) in buffers {
+            if r.is_false() {
+                writeln!(writer, ".names {}", name)?;
+            } else if r.is_true() {
+                writeln!(writer, ".names {}\n1", name)?;
+            } else {
+                writeln!(writer, ".names {} {}", names.nodes[&r.id()], name)?;
+                writeln!(writer, "{} 1", !r.is_negated() as u8)?;
+            }
+        }
+        writeln!(writer, ".end")?;
+        Ok(())
+    }
+
+    /// Write the network as a JSON netlist:
+    ///
+    /// ```text
+    /// {
+    ///   "inputs": [{"id": 1, "name": "a"}, ...],
+    ///   "latches": [{"id": 3, "next": 8, "init": 0}, ...],
+    ///   "luts": [{"id": 4, "fanins": [1, 2], "truth": "8"}, ...],
+    ///   "outputs": [{"literal": 9}, ...],
+    ///   "bad": [{"literal": 8}, ...],
+    ///   "constraints": [{"literal": 2}, ...],
+    ///   "justice": [{"literals": [8, 3]}, ...],
+    ///   "fairness": [{"literal": 6}, ...],
+    ///   "num_luts": 1,
+    ///   "depth": 1
+    /// }
+    /// ```
+    ///
+    /// Outputs, properties and latch next-states are AIGER literals (`2 * id`, plus one if
+    /// complemented). Names are only present for symbols. The initial value of uninitialised
+    /// latches is `null`. Truth tables are hexadecimal, as in [`Lut::truth`], most
+    /// significant digit first.
+    pub fn write_json(&self, writer: &mut impl Write) -> eyre::Result<()> {
+        let name = |type_spec: SymbolType, position: usize| {
+            self.symbols
+                .name(type_spec, position)
+                .map_or_else(String::new, |name| {
+                    format!(", \"name\": {}", json_string(name))
+                })
+        };
+        let literals = |type_spec: SymbolType, refs: &[Ref]| {
+            refs.iter()
+                .enumerate()
+                .map(|(i, r)| format!("{{\"literal\": {}{}}}", r.raw(), name(type_spec, i)))
+                .collect::<Vec<_>>()
+        };
+
+        writeln!(writer, "{{")?;
+        let inputs = (self.inputs.iter().enumerate())
+            .map(|(i, id)| format!("{{\"id\": {}{}}}", id, name(SymbolType::Input, i)))
+            .collect::<Vec<_>>();
+        write_array(writer, "inputs", &inputs)?;
+
+        let latches = (self.latches.iter().enumerate())
+            .map(|(i, latch)| {
+                let init = match latch.init.value() {
+                    Some(value) => (value as u8).to_string(),
+                    None => "null".to_string(),
+                };
+                format!(
+                    "{{\"id\": {}{}, \"next\": {}, \"init\": {}}}",
+                    latch.id,
+                    name(SymbolType::Latch, i),
+                    latch.next.raw(),
+                    init
+                )
+            })
+            .collect::<Vec<_>>();
+        write_array(writer, "latches", &latches)?;
+
+        let luts = (self.luts.iter())
+            .map(|lut| {
+                let fanins = lut
+                    .fanins
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>();
+                let size = 1u32 << lut.fanins.len();
+                let mask = if size == 64 { !0 } else { (1 << size) - 1 };
+                format!(
+                    "{{\"id\": {}, \"fanins\": [{}], \"truth\": \"{:0width$x}\"}}",
+                    lut.id,
+                    fanins.join(", "),
+                    lut.truth & mask,
+                    width = size.div_ceil(4) as usize
+                )
+            })
+            .collect::<Vec<_>>();
+        write_array(writer, "luts", &luts)?;
+
+        write_array(
+            writer,
+            "outputs",
+            &literals(SymbolType::Output, &self.outputs),
+        )?;
+        write_array(writer, "bad", &literals(SymbolType::Bad, &self.bad))?;
+        let constraints = literals(SymbolType::Constraint, &self.constraints);
+        write_array(writer, "constraints", &constraints)?;
+        let justice = (self.justice.iter().enumerate())
+            .map(|(i, refs)| {
+                let refs = refs.iter().map(|r| r.raw().to_string()).collect::<Vec<_>>();
+                format!(
+                    "{{\"literals\": [{}]{}}}",
+                    refs.join(", "),
+                    name(SymbolType::Justice, i)
+                )
+            })
+            .collect::<Vec<_>>();
+        write_array(writer, "justice", &justice)?;
+        write_array(
+            writer,
+            "fairness",
+            &literals(SymbolType::Fairness, &self.fairness),
+        )?;
+
+        writeln!(writer, "  \"num_luts\": {},", self.num_luts())?;
+        writeln!(writer, "  \"depth\": {}", self.depth())?;
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+}
+
+/// Distinct signal names of a network in BLIF.
+struct BlifNames {
+    /// Names of the inputs, latches and LUTs, by id.
+    nodes: HashMap<u32, String>,
+    /// Names of the outputs and properties, with the literals driving them.
+    roots: Vec<(String, Ref)>,
+    used: HashSet<String>,
+}
+
+impl BlifNames {
+    fn new(network: &LutNetwork) -> Self {
+        let symbol = |type_spec: SymbolType, position: usize| {
+            network.symbols.name(type_spec, position).map(String::from)
+        };
+        let names = |type_spec: SymbolType, position: usize| {
+            let generated = format!("{}{}", type_spec.prefix(), position);
+            (symbol(type_spec, position), generated)
+        };
+
+        // Symbol (if any) and generated name of each signal: the nodes, then the roots.
+        let mut ids = Vec::new();
+        let mut signals = Vec::new();
+        for (i, &id) in network.inputs.iter().enumerate() {
+            ids.push(id);
+            signals.push(names(SymbolType::Input, i));
+        }
+        for (i, latch) in network.latches.iter().enumerate() {
+            ids.push(latch.id);
+            signals.push(names(SymbolType::Latch, i));
+        }
+        for lut in &network.luts {
+            ids.push(lut.id);
+            signals.push((None, format!("n{}", lut.id)));
+        }
+        let mut refs = Vec::new();
+        for (type_spec, roots) in [
+            (SymbolType::Output, &network.outputs),
+            (SymbolType::Bad, &network.bad),
+            (SymbolType::Constraint, &network.constraints),
+        ] {
+            for (i, &r) in roots.iter().enumerate() {
+                refs.push(r);
+                signals.push(names(type_spec, i));
+            }
+        }
+        for (i, roots) in network.justice.iter().enumerate() {
+            let (symbol, generated) = names(SymbolType::Justice, i);
+            for (j, &r) in roots.iter().enumerate() {
+                refs.push(r);
+                let symbol = symbol.as_ref().map(|symbol| format!("{}_{}", symbol, j));
+                signals.push((symbol, format!("{}_{}", generated, j)));
+            }
+        }
+        for (i, &r) in network.fairness.iter().enumerate() {
+            refs.push(r);
+            signals.push(names(SymbolType::Fairness, i));
+        }
+
+        // The symbols are reserved first, so that they are only renamed when they collide
+        // with each other:
+        let mut result = Self {
+            nodes: HashMap::new(),
+            roots: Vec::new(),
+            used: HashSet::new(),
+        };
+        let mut unique = vec![String::new(); signals.len()];
+        for (i, (symbol, _)) in signals.iter().enumerate() {
+            if let Some(symbol) = symbol {
+                unique[i] = result.unique(symbol);
+            }
+        }
+        for (i, (symbol, generated)) in signals.iter().enumerate() {
+            if symbol.is_none() {
+                unique[i] = result.unique(generated);
+            }
+        }
+        let roots = unique.split_off(ids.len());
+        result.nodes = ids.into_iter().zip(unique).collect();
+        result.roots = roots.into_iter().zip(refs).collect();
+        result
+    }
+
+    /// Reserve a name derived from `name`: with the characters BLIF does not allow replaced
+    /// with `_`, and a suffix `_<k>` if it is already used.
+    fn unique(&mut self, name: &str) -> String {
+        let name = name
+            .chars()
+            .map(|c| match c {
+                '#' | '\\' | '=' => '_',
+                c if c.is_whitespace() || c.is_control() => '_',
+                c => c,
+            })
+            .collect::<String>();
+        let name = if name.is_empty() {
+            "_".to_string()
+        } else {
+            name
+        };
+        let mut unique = name.clone();
+        let mut k = 1;
+        while !self.used.insert(unique.clone()) {
+            unique = format!("{}_{}", name, k);
+            k += 1;
+        }
+        unique
+    }
+}
+
+/// Write the items as a JSON array field of the top-level object, one item per line.
+fn write_array(writer: &mut impl Write, key: &str, items: &[String]) -> eyre::Result<()> {
+    if items.is_empty() {
+        writeln!(writer, "  \"{}\": [],", key)?;
+        return Ok(());
+    }
+    writeln!(writer, "  \"{}\": [", key)?;
+    for (i, item) in items.iter().enumerate() {
+        let comma = if i + 1 < items.len() { "," } else { "" };
+        writeln!(writer, "    {}{}", item, comma)?;
+    }
+    writeln!(writer, "  ],")?;
+    Ok(())
+}
+
+/// Quote and escape the string for JSON.
+fn json_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    /// o0 = a ^ b, o1 = ~(a ^ b) & l, with the latch l' = ~o1.
+    fn example() -> LutNetwork {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(SymbolType::Input, 0, "a".to_string());
+        symbols.insert(SymbolType::Output, 1, "y".to_string());
+        LutNetwork {
+            inputs: vec![1, 2],
+            latches: vec![AigLatch {
+                id: 3,
+                next: -Ref::positive(6),
+                init: LatchInit::Nondet,
+            }],
+            luts: vec![
+                Lut {
+                    id: 5,
+                    fanins: vec![1, 2],
+                    truth: 0x6,
+                },
+                Lut {
+                    id: 6,
+                    fanins: vec![5, 3],
+                    truth: 0x4,
+                },
+            ],
+            outputs: vec![Ref::positive(5), Ref::positive(6)],
+            symbols,
+            ..LutNetwork::default()
+        }
+    }
+
+    #[test]
+    fn test_lut_network() {
+        let network = example();
+        assert_eq!(network.num_luts(), 2);
+        assert_eq!(network.depth(), 2);
+        assert!(network.luts[0].eval([true, false]));
+        assert!(!network.luts[0].eval([true, true]));
+        assert!(network.luts[1].eval([false, true]));
+        assert_eq!(json_string("a \"b\"\n"), r#""a \"b\"\n""#);
+    }
+
+    #[test]
+    fn test_write_blif() {
+        let mut buf = Vec::new();
+        example().write_blif(&mut buf).unwrap();
+        let expected = indoc! {r#"
+            .model top
+            .inputs a i1
+            .outputs o0 y
+            .latch l0_next l0 2
+            .names a i1 n5
+            10 1
+            01 1
+            .names n5 l0 n6
+            01 1
+            .names n6 l0_next
+            0 1
+            .names n5 o0
+            1 1
+            .names n6 y
+            1 1
+            .end
+        "#};
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_json() {
+        let mut buf = Vec::new();
+        example().write_json(&mut buf).unwrap();
+        let expected = indoc! {r#"
+            {
+              "inputs": [
+                {"id": 1, "name": "a"},
+                {"id": 2}
+              ],
+              "latches": [
+                {"id": 3, "next": 13, "init": null}
+              ],
+              "luts": [
+                {"id": 5, "fanins": [1, 2], "truth": "6"},
+                {"id": 6, "fanins": [5, 3], "truth": "4"}
+              ],
+              "outputs": [
+                {"literal": 10},
+                {"literal": 12, "name": "y"}
+              ],
+              "bad": [],
+              "constraints": [],
+              "justice": [],
+              "fairness": [],
+              "num_luts": 2,
+              "depth": 2
+            }
+        "#};
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_properties() {
+        let mut network = example();
+        network.bad = vec![-Ref::positive(6)];
+        network.justice = vec![vec![Ref::positive(5), -Ref::positive(3)]];
+        network.fairness = vec![Ref::positive(2)];
+        network
+            .symbols
+            .insert(SymbolType::Justice, 0, "live".to_string());
+
+        let mut buf = Vec::new();
+        network.write_blif(&mut buf).unwrap();
+        let blif = String::from_utf8(buf).unwrap();
+        assert!(blif.contains(".outputs o0 y b0 live_0 live_1 f0\n"));
+        let expected = indoc! {"
+            .names n6 b0
+            0 1
+            .names n5 live_0
+            1 1
+            .names l0 live_1
+            0 1
+            .names i1 f0
+            1 1
+            .end
+        "};
+        assert!(blif.ends_with(expected));
+
+        let mut buf = Vec::new();
+        network.write_json(&mut buf).unwrap();
+        let json = String::from_utf8(buf).unwrap();
+        let expected = indoc! {r#"
+            {
+              "inputs": [
+                {"id": 1, "name": "a"},
+                {"id": 2}
+              ],
+              "latches": [
+                {"id": 3, "next": 13, "init": null}
+              ],
+              "luts": [
+                {"id": 5, "fanins": [1, 2], "truth": "6"},
+                {"id": 6, "fanins": [5, 3], "truth": "4"}
+              ],
+              "outputs": [
+                {"literal": 10},
+                {"literal": 12, "name": "y"}
+              ],
+              "bad": [
+                {"literal": 13}
+              ],
+              "constraints": [],
+              "justice": [
+                {"literals": [10, 7], "name": "live"}
+              ],
+              "fairness": [
+                {"literal": 4}
+              ],
+              "num_luts": 2,
+              "depth": 2
+            }
+        "#};
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn test_write_blif_names() {
+        // Symbols colliding with generated names and with each other, and with spaces.
+        let mut network = example();
+        network.bad = vec![-Ref::positive(6)];
+        let mut symbols = SymbolTable::new();
+        symbols.insert(SymbolType::Input, 0, "n5".to_string());
+        symbols.insert(SymbolType::Latch, 0, "a b".to_string());
+        symbols.insert(SymbolType::Output, 0, "i1".to_string());
+        symbols.insert(SymbolType::Output, 1, "a_b".to_string());
+        symbols.insert(SymbolType::Bad, 0, "a_b_next".to_string());
+        network.symbols = symbols;
+
+        let mut buf = Vec::new();
+        network.write_blif(&mut buf).unwrap();
+        let expected = indoc! {r#"
+            .model top
+            .inputs n5 i1_1
+            .outputs i1 a_b_1 a_b_next
+            .latch a_b_next_1 a_b 2
+            .names n5 i1_1 n5_1
+            10 1
+            01 1
+            .names n5_1 a_b n6
+            01 1
+            .names n6 a_b_next_1
+            0 1
+            .names n5_1 i1
+            1 1
+            .names n6 a_b_1
+            1 1
+            .names n6 a_b_next
+            0 1
+            .end
+        "#};
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    }
+}
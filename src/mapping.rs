@@ -0,0 +1,507 @@
+use std::cmp::Ordering;
+
+use crate::aig::Aig;
+use crate::cut::{Cut, MAX_CUT_SIZE};
+use crate::lut::{Lut, LutNetwork};
+
+/// Options of [`Aig::map_luts`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct MappingOptions {
+    /// Number of LUT inputs, between 2 and [`MAX_CUT_SIZE`].
+    pub k: usize,
+    /// Maximum number of priority cuts stored per node.
+    pub max_cuts: usize,
+    /// Rounds of area recovery using area flow.
+    pub area_flow_rounds: usize,
+    /// Rounds of area recovery using exact local area.
+    pub exact_area_rounds: usize,
+}
+
+impl Default for MappingOptions {
+    fn default() -> Self {
+        Self {
+            k: 6,
+            max_cuts: 8,
+            area_flow_rounds: 1,
+            exact_area_rounds: 2,
+        }
+    }
+}
+
+/// Cost function used to select the cuts in a mapping pass.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Mode {
+    /// Depth, then area flow.
+    Depth,
+    /// Area flow, then depth, within the required times.
+    AreaFlow,
+    /// Number of LUTs added to the current mapping, within the required times.
+    ExactArea,
+}
+
+/// Cut with its costs.
+struct Candidate {
+    cut: Cut,
+    depth: u32,
+    flow: f64,
+    area: usize,
+}
+
+impl Candidate {
+    fn cmp(&self, other: &Self, mode: Mode) -> Ordering {
+        let size = self.cut.len().cmp(&other.cut.len());
+        match mode {
+            Mode::Depth => (self.depth.cmp(&other.depth))
+                .then(self.flow.total_cmp(&other.flow))
+                .then(size),
+            Mode::AreaFlow => (self.flow.total_cmp(&other.flow))
+                .then(self.depth.cmp(&other.depth))
+                .then(size),
+            Mode::ExactArea => (self.area.cmp(&other.area))
+                .then(self.flow.total_cmp(&other.flow))
+                .then(self.depth.cmp(&other.depth))
+                .then(size),
+        }
+    }
+}
+
+/// Priority-cut LUT mapper, with the state indexed by node id.
+struct Mapper<'a> {
+    aig: &'a Aig,
+    options: MappingOptions,
+    /// Priority cuts, followed by the trivial cut for the AND gates.
+    cuts: Vec<Vec<Cut>>,
+    /// Selected cuts of the AND gates.
+    best: Vec<Option<Cut>>,
+    /// Depth of the selected cuts: number of LUTs on the longest path from an input.
+    arrival: Vec<u32>,
+    /// Area flow of the selected cuts: their area, shared between their fanouts.
+    flow: Vec<f64>,
+    /// Estimated number of fanouts in the mapping: the fanouts in the AIG for the first
+    /// pass, then the references in the previous mapping.
+    estimated_refs: Vec<f64>,
+    /// Number of references in the current mapping (zero for unmapped gates).
+    refs: Vec<u32>,
+    /// Maximum depth of the gates which keeps the depth of the mapping.
+    required: Vec<u32>,
+}
+
+impl<'a> Mapper<'a> {
+    fn new(aig: &'a Aig, options: MappingOptions) -> Self {
+        let n = aig.max_id() as usize + 1;
+        let mut cuts = vec![Vec::new(); n];
+        cuts[0] = vec![Cut::constant()];
+        for &id in aig.inputs().iter().chain(aig.latches()) {
+            cuts[id as usize] = vec![Cut::trivial(id)];
+        }
+        let mut estimated_refs = (0..n as u32)
+            .map(|id| aig.fanouts(id).len() as f64)
+            .collect::<Vec<_>>();
+        for r in aig.root_refs() {
+            estimated_refs[r.id() as usize] += 1.0;
+        }
+        Self {
+            aig,
+            options,
+            cuts,
+            best: vec![None; n],
+            arrival: vec![0; n],
+            flow: vec![0.0; n],
+            estimated_refs,
+            refs: vec![0; n],
+            required: vec![u32::MAX; n],
+        }
+    }
+
+    /// Reference the nodes in the mapping, recursively through the selected cuts of the
+    /// newly referenced gates. Returns the number of these gates.
+    fn reference(&mut self, nodes: &[u32]) -> usize {
+        let mut added = 0;
+        let mut stack = nodes.to_vec();
+        while let Some(id) = stack.pop() {
+            if let Some(cut) = &self.best[id as usize] {
+                self.refs[id as usize] += 1;
+                if self.refs[id as usize] == 1 {
+                    added += 1;
+                    stack.extend(&cut.leaves);
+                }
+            }
+        }
+        added
+    }
+
+    /// Undo [`Mapper::reference`], returning the number of gates which are no longer referenced.
+    fn dereference(&mut self, nodes: &[u32]) -> usize {
+        let mut removed = 0;
+        let mut stack = nodes.to_vec();
+        while let Some(id) = stack.pop() {
+            if let Some(cut) = &self.best[id as usize] {
+                self.refs[id as usize] -= 1;
+                if self.refs[id as usize] == 0 {
+                    removed += 1;
+                    stack.extend(&cut.leaves);
+                }
+            }
+        }
+        removed
+    }
+
+    fn evaluate(&mut self, cut: Cut, mode: Mode) -> Candidate {
+        let mut depth = 0;
+        let mut flow = 1.0;
+        for &leaf in &cut.leaves {
+            let leaf = leaf as usize;
+            depth = depth.max(self.arrival[leaf]);
+            flow += self.flow[leaf] / self.estimated_refs[leaf].max(1.0);
+        }
+        let area = if mode == Mode::ExactArea {
+            let area = self.reference(&cut.leaves);
+            self.dereference(&cut.leaves);
+            area + 1
+        } else {
+            0
+        };
+        Candidate {
+            cut,
+            depth: depth + 1,
+            flow,
+            area,
+        }
+    }
+
+    /// Select the cut of the gate, and update its priority cuts.
+    fn map_node(&mut self, id: u32, mode: Mode) {
+        let index = id as usize;
+        let args = self.aig.gate(id).args;
+        let [a, b] = args.map(|r| &self.cuts[r.id() as usize]);
+        let k = self.options.k;
+        let mut cuts = Vec::new();
+        for x in a {
+            for y in b {
+                if let Some(cut) = Cut::merge(x, args[0].is_negated(), y, args[1].is_negated(), k) {
+                    cuts.push(cut);
+                }
+            }
+        }
+        // The previous selection remains feasible:
+        cuts.extend(self.best[index].clone());
+        cuts.sort_by(|x, y| (x.len(), &x.leaves).cmp(&(y.len(), &y.leaves)));
+        cuts.dedup_by(|x, y| x.leaves == y.leaves);
+        let mut kept: Vec<Cut> = Vec::new();
+        for cut in cuts {
+            if !kept.iter().any(|other| other.dominates(&cut)) {
+                kept.push(cut);
+            }
+        }
+
+        let mapped = self.refs[index] > 0;
+        if mode == Mode::ExactArea && mapped {
+            let leaves = self.best_leaves(id);
+            self.dereference(&leaves);
+        }
+        let mut candidates = kept
+            .into_iter()
+            .map(|cut| self.evaluate(cut, mode))
+            .collect::<Vec<_>>();
+        let required = self.required[index];
+        candidates.sort_by(|x, y| {
+            (x.depth > required)
+                .cmp(&(y.depth > required))
+                .then(x.cmp(y, mode))
+        });
+        assert!(candidates[0].depth <= required || mode == Mode::Depth);
+
+        let best = &candidates[0];
+        self.arrival[index] = best.depth;
+        self.flow[index] = best.flow;
+        self.best[index] = Some(best.cut.clone());
+        if mode == Mode::ExactArea && mapped {
+            let leaves = self.best_leaves(id);
+            self.reference(&leaves);
+        }
+        let mut cuts = candidates
+            .into_iter()
+            .take(self.options.max_cuts)
+            .map(|candidate| candidate.cut)
+            .collect::<Vec<_>>();
+        cuts.push(Cut::trivial(id));
+        self.cuts[index] = cuts;
+    }
+
+    fn best_leaves(&self, id: u32) -> Vec<u32> {
+        self.best[id as usize].as_ref().unwrap().leaves.clone()
+    }
+
+    fn pass(&mut self, mode: Mode) {
+        for &id in self.aig.topological_order() {
+            self.map_node(id, mode);
+        }
+    }
+
+    /// Depth of the mapping: maximum arrival time of the roots.
+    fn depth(&self) -> u32 {
+        self.aig
+            .root_refs()
+            .map(|r| self.arrival[r.id() as usize])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Recompute the references and the required times of the current mapping.
+    fn update(&mut self, depth: u32) {
+        self.refs.fill(0);
+        let roots = self.aig.root_refs().map(|r| r.id()).collect::<Vec<_>>();
+        self.reference(&roots);
+        for (estimate, &refs) in self.estimated_refs.iter_mut().zip(&self.refs) {
+            *estimate = refs.max(1) as f64;
+        }
+
+        self.required.fill(u32::MAX);
+        for &id in &roots {
+            self.required[id as usize] = depth;
+        }
+        for &id in self.aig.topological_order().iter().rev() {
+            if self.refs[id as usize] == 0 {
+                continue;
+            }
+            let required = self.required[id as usize].saturating_sub(1);
+            for &leaf in &self.best[id as usize].as_ref().unwrap().leaves {
+                let r = &mut self.required[leaf as usize];
+                *r = (*r).min(required);
+            }
+        }
+    }
+
+    fn network(&self) -> LutNetwork {
+        let luts = self
+            .aig
+            .topological_order()
+            .iter()
+            .filter(|&&id| self.refs[id as usize] > 0)
+            .map(|&id| {
+                let cut = self.best[id as usize].as_ref().unwrap();
+                let size = 1 << cut.len();
+                let mask = if size == 64 { !0 } else { (1 << size) - 1 };
+                Lut {
+                    id,
+                    fanins: cut.leaves.clone(),
+                    truth: cut.truth & mask,
+                }
+            })
+            .collect();
+        LutNetwork {
+            inputs: self.aig.inputs().to_vec(),
+            latches: self
+                .aig
+                .latches()
+                .iter()
+                .map(|&id| self.aig.latch(id))
+                .collect(),
+            luts,
+            outputs: self.aig.outputs().to_vec(),
+            bad: self.aig.bad().to_vec(),
+            constraints: self.aig.constraints().to_vec(),
+            justice: self.aig.justice().to_vec(),
+            fairness: self.aig.fairness().to_vec(),
+            symbols: self.aig.symbols().clone(),
+        }
+    }
+}
+
+impl Aig {
+    /// Map the AIG into `k`-input LUTs (see [`MappingOptions`]).
+    ///
+    /// Each AND gate keeps a few priority cuts, merged from the priority cuts of its
+    /// fanins. The first pass selects the cuts minimising the depth. The following passes
+    /// recover area without increasing the depth: by area flow (the area of a cut, with
+    /// the area of its leaves shared between their fanouts), then by exact local area
+    /// (the number of LUTs the cut adds to the current mapping).
+    ///
+    /// The LUTs implement the cones of the outputs, the properties and the latch
+    /// next-states.
+    pub fn map_luts(&self, options: MappingOptions) -> LutNetwork {
+        assert!(
+            (2..=MAX_CUT_SIZE).contains(&options.k),
+            "LUT size must be between 2 and {}",
+            MAX_CUT_SIZE
+        );
+        assert!(
+            options.max_cuts > 0,
+            "At least one cut per node is required"
+        );
+
+        let mut mapper = Mapper::new(self, options);
+        mapper.pass(Mode::Depth);
+        let depth = mapper.depth();
+        mapper.update(depth);
+        for _ in 0..options.area_flow_rounds {
+            mapper.pass(Mode::AreaFlow);
+            mapper.update(depth);
+        }
+        for _ in 0..options.exact_area_rounds {
+            mapper.pass(Mode::ExactArea);
+            mapper.update(depth);
+        }
+        let network = mapper.network();
+
+        log::debug!(
+            "Mapped {} AND gates into {} {}-LUTs with depth {}",
+            self.and_gates().count(),
+            network.num_luts(),
+            options.k,
+            network.depth()
+        );
+        network
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builder::AigBuilder;
+    use crate::node::LatchInit;
+    use crate::reference::Ref;
+    use crate::sim::SplitMix64;
+    use crate::test_utils::{eval_outputs, RandomAig};
+
+    /// Values of the outputs of a mapping, with [`LutNetwork::eval`].
+    fn eval_network(network: &LutNetwork, inputs: &[bool]) -> Vec<bool> {
+        let values = network.eval(inputs.to_vec());
+        network
+            .outputs
+            .iter()
+            .map(|r| values.get(&r.id()).copied().unwrap_or(false) ^ r.is_negated())
+            .collect()
+    }
+
+    #[test]
+    fn test_map_and_tree() {
+        // A balanced tree of 7 AND gates: with 3-LUTs, its structure forces depth 3.
+        let mut builder = AigBuilder::new();
+        let x = builder.inputs(8);
+        let o = builder.and_many(&x);
+        builder.output(-o);
+        let aig = builder.build();
+
+        for (k, luts, depth) in [(2, 7, 3), (3, 5, 3), (4, 3, 2), (6, 2, 2)] {
+            let options = MappingOptions {
+                k,
+                ..MappingOptions::default()
+            };
+            let network = aig.map_luts(options);
+            assert_eq!(network.num_luts(), luts, "k = {}", k);
+            assert_eq!(network.depth(), depth, "k = {}", k);
+            assert_eq!(network.outputs, aig.outputs());
+            let inputs = vec![true; 8];
+            assert_eq!(eval_network(&network, &inputs), eval_outputs(&aig, &inputs));
+        }
+    }
+
+    #[test]
+    fn test_map_sequential() {
+        // A 4-bit counter with enable.
+        let mut builder = AigBuilder::new();
+        let enable = builder.input();
+        let bits = (0..4)
+            .map(|_| builder.latch(LatchInit::Zero))
+            .collect::<Vec<_>>();
+        let mut carry = enable;
+        for &bit in &bits {
+            let next = builder.xor(bit, carry);
+            builder.set_latch_next(bit, next);
+            carry = builder.and(bit, carry);
+        }
+        builder.output(carry);
+        let aig = builder.build();
+
+        let network = aig.map_luts(MappingOptions::default());
+        assert_eq!(network.latches.len(), 4);
+        // Each next-state and the carry out depend on at most 5 nodes.
+        assert_eq!(network.num_luts(), 5);
+        assert_eq!(network.depth(), 1);
+        for lut in &network.luts {
+            assert!(lut.fanins.len() <= 6);
+        }
+    }
+
+    #[test]
+    fn test_map_properties() {
+        // Properties outside of the cones of the outputs are mapped too.
+        let mut builder = AigBuilder::new();
+        let [a, b, c, d] = builder.inputs(4).try_into().unwrap();
+        let ab = builder.and(a, b);
+        builder.output(ab);
+        let cd = builder.xor(c, d);
+        builder.bad(cd);
+        let ac = builder.or(a, c);
+        builder.constraint(ac);
+        let bd = builder.and(b, d);
+        let abcd = builder.and(ab, -cd);
+        let mut aig = builder.build();
+        aig.add_justice(vec![bd, -a]);
+        aig.add_fairness(abcd);
+
+        let network = aig.map_luts(MappingOptions::default());
+        assert_eq!(network.bad, aig.bad());
+        assert_eq!(network.constraints, aig.constraints());
+        assert_eq!(network.justice, aig.justice());
+        assert_eq!(network.fairness, aig.fairness());
+        for m in 0..16 {
+            let inputs = (0..4).map(|i| (m >> i) & 1 != 0).collect::<Vec<_>>();
+            let expected = aig.eval(inputs.clone());
+            let actual = network.eval(inputs);
+            let value = |values: &std::collections::BTreeMap<u32, bool>, r: Ref| {
+                values.get(&r.id()).copied().unwrap_or(false) ^ r.is_negated()
+            };
+            for r in [cd, ac, bd, -a, abcd] {
+                assert_eq!(value(&actual, r), value(&expected, r));
+            }
+        }
+    }
+
+    #[test]
+    fn test_map_random() {
+        let mut rng = SplitMix64::new(17);
+        for k in [3, 4, 6] {
+            let (mut area, mut recovered) = (0, 0);
+            for _ in 0..20 {
+                let aig = RandomAig {
+                    inputs: 10,
+                    gates: 100,
+                    window: 20,
+                    outputs: 6,
+                    stride: 5,
+                    ..RandomAig::default()
+                }
+                .generate(&mut rng);
+
+                let options = MappingOptions {
+                    k,
+                    area_flow_rounds: 0,
+                    exact_area_rounds: 0,
+                    ..MappingOptions::default()
+                };
+                let fast = aig.map_luts(options);
+                let network = aig.map_luts(MappingOptions {
+                    k,
+                    ..MappingOptions::default()
+                });
+                area += fast.num_luts();
+                recovered += network.num_luts();
+                // Area recovery keeps the depth:
+                assert!(network.depth() <= fast.depth());
+                for lut in &network.luts {
+                    assert!(lut.fanins.len() <= k);
+                }
+                for _ in 0..32 {
+                    let inputs = (0..10)
+                        .map(|_| rng.next_u64().is_multiple_of(2))
+                        .collect::<Vec<_>>();
+                    assert_eq!(eval_network(&network, &inputs), eval_outputs(&aig, &inputs));
+                }
+            }
+            assert!(recovered < area, "k = {}: {} >= {}", k, recovered, area);
+        }
+    }
+}
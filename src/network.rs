@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+
+use crate::aig::Aig;
+use crate::builder::{image, normalize, AigBuilder};
+use crate::reference::Ref;
+use crate::rewrite::RewriteStats;
+
+/// Fold trivial AND gates: `x & 0 = 0`, `x & 1 = x`, `x & x = x` and `x & ~x = 0`.
+pub(crate) fn fold(a: Ref, b: Ref) -> Option<Ref> {
+    if a.is_false() || b.is_false() || a == -b {
+        Some(Ref::FALSE)
+    } else if a.is_true() || a == b {
+        Some(b)
+    } else if b.is_true() {
+        Some(a)
+    } else {
+        None
+    }
+}
+
+/// Mutable structurally hashed AIG with reference counts, used by the local
+/// optimisation passes ([`Aig::rewrite`], [`Aig::refactor`] and [`Aig::resub`]).
+///
+/// Nodes keep their original ids, new gates are appended. A replaced node is redirected
+/// to its replacement, its fanouts are rewired (merging gates which become trivial or
+/// structurally identical), and the nodes it no longer needs are deleted.
+///
+/// Candidate replacements are given as small AIGs: `gates` over the `inputs`, where
+/// node 0 is the constant, nodes `1..inputs.len()` are the inputs, followed by the gates.
+pub(crate) struct Network {
+    /// Arguments of the AND gates, `None` for the constant, inputs and latches.
+    fanins: Vec<Option<[Ref; 2]>>,
+    /// Gates using each node (possibly stale).
+    fanouts: Vec<Vec<u32>>,
+    /// Number of references from gates and from the outputs, properties and latches.
+    refs: Vec<u32>,
+    /// Number of references from the outputs, properties and latches.
+    external: Vec<u32>,
+    /// Levels, kept larger than the levels of the fanins (but not necessarily minimal).
+    levels: Vec<u32>,
+    dead: Vec<bool>,
+    replaced: Vec<Option<Ref>>,
+    strash: HashMap<[Ref; 2], u32>,
+}
+
+impl Network {
+    pub(crate) fn new(aig: &Aig) -> Self {
+        let n = aig.max_id() as usize + 1;
+        let mut network = Network {
+            fanins: vec![None; n],
+            fanouts: vec![Vec::new(); n],
+            refs: vec![0; n],
+            external: vec![0; n],
+            levels: vec![0; n],
+            dead: vec![false; n],
+            replaced: vec![None; n],
+            strash: HashMap::new(),
+        };
+        for &id in aig.topological_order() {
+            let args = aig.gate(id).args;
+            network.fanins[id as usize] = Some(args);
+            network.levels[id as usize] = aig.level(id);
+            for r in args {
+                network.refs[r.id() as usize] += 1;
+                network.fanouts[r.id() as usize].push(id);
+            }
+            network.strash.entry(normalize(args)).or_insert(id);
+        }
+        for r in aig.root_refs() {
+            network.refs[r.id() as usize] += 1;
+            network.external[r.id() as usize] += 1;
+        }
+        network
+    }
+
+    /// Number of node ids, including the deleted nodes.
+    pub(crate) fn len(&self) -> usize {
+        self.fanins.len()
+    }
+
+    pub(crate) fn fanins(&self, id: u32) -> Option<[Ref; 2]> {
+        self.fanins[id as usize]
+    }
+
+    pub(crate) fn is_gate(&self, id: u32) -> bool {
+        self.fanins[id as usize].is_some()
+    }
+
+    pub(crate) fn is_dead(&self, id: u32) -> bool {
+        self.dead[id as usize]
+    }
+
+    pub(crate) fn refs(&self, id: u32) -> u32 {
+        self.refs[id as usize]
+    }
+
+    pub(crate) fn level(&self, id: u32) -> u32 {
+        self.levels[id as usize]
+    }
+
+    /// Whether the node is used by an output, a property or a latch.
+    pub(crate) fn is_external(&self, id: u32) -> bool {
+        self.external[id as usize] > 0
+    }
+
+    /// Live gates using the node, in increasing order.
+    pub(crate) fn fanouts(&self, id: u32) -> Vec<u32> {
+        let mut fanouts = self.fanouts[id as usize]
+            .iter()
+            .copied()
+            .filter(|&f| {
+                !self.dead[f as usize]
+                    && self.fanins[f as usize].is_some_and(|args| args.iter().any(|r| r.id() == id))
+            })
+            .collect::<Vec<_>>();
+        fanouts.sort_unstable();
+        fanouts.dedup();
+        fanouts
+    }
+
+    /// Final replacement of the reference.
+    pub(crate) fn resolve(&self, mut r: Ref) -> Ref {
+        while let Some(next) = self.replaced[r.id() as usize] {
+            r = Ref::from_raw(next.raw() ^ r.is_negated() as u32);
+        }
+        r
+    }
+
+    /// Existing gate or trivial result for the AND of the arguments.
+    pub(crate) fn lookup(&self, a: Ref, b: Ref) -> Option<Ref> {
+        fold(a, b).or_else(|| Some(Ref::positive(*self.strash.get(&normalize([a, b]))?)))
+    }
+
+    /// AND of the arguments, creating a new gate if needed.
+    pub(crate) fn and(&mut self, a: Ref, b: Ref) -> Ref {
+        if let Some(r) = self.lookup(a, b) {
+            return r;
+        }
+        let id = self.fanins.len() as u32;
+        let args = normalize([a, b]);
+        self.fanins.push(Some(args));
+        self.fanouts.push(Vec::new());
+        self.refs.push(0);
+        self.external.push(0);
+        self.levels
+            .push(1 + self.level(a.id()).max(self.level(b.id())));
+        self.dead.push(false);
+        self.replaced.push(None);
+        for r in args {
+            self.refs[r.id() as usize] += 1;
+            self.fanouts[r.id() as usize].push(id);
+        }
+        self.strash.insert(args, id);
+        Ref::positive(id)
+    }
+
+    /// Dereference the fanins of the root recursively, stopping at the leaves.
+    /// Returns the size of the maximum fanout-free cone of the root, including the root:
+    /// the gates which now have no references.
+    pub(crate) fn deref(&mut self, root: u32, leaves: &[u32]) -> usize {
+        let mut size = 0;
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            size += 1;
+            for r in self.fanins[id as usize].unwrap() {
+                let x = r.id();
+                self.refs[x as usize] -= 1;
+                if self.refs[x as usize] == 0 && self.is_gate(x) && !leaves.contains(&x) {
+                    stack.push(x);
+                }
+            }
+        }
+        size
+    }
+
+    /// Undo [`Network::deref`].
+    pub(crate) fn reref(&mut self, root: u32, leaves: &[u32]) {
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            for r in self.fanins[id as usize].unwrap() {
+                let x = r.id();
+                if self.refs[x as usize] == 0 && self.is_gate(x) && !leaves.contains(&x) {
+                    stack.push(x);
+                }
+                self.refs[x as usize] += 1;
+            }
+        }
+    }
+
+    /// Number of gates the replacement would add to the network, while the cone of the root
+    /// is dereferenced, or `None` if it is not less than the limit or would reuse the root.
+    /// Existing gates in the dereferenced cone count as added, since they would otherwise be removed.
+    pub(crate) fn count_added(
+        &self,
+        gates: &[[Ref; 2]],
+        inputs: &[Ref],
+        root: u32,
+        limit: usize,
+    ) -> Option<usize> {
+        let mut nodes = inputs.iter().map(|&r| Some(r)).collect::<Vec<_>>();
+        let node = |nodes: &[Option<Ref>], r: Ref| {
+            nodes[r.id() as usize].map(|x| Ref::from_raw(x.raw() ^ r.is_negated() as u32))
+        };
+        let mut added = 0;
+        for &[a, b] in gates {
+            let r = match (node(&nodes, a), node(&nodes, b)) {
+                (Some(a), Some(b)) => self.lookup(a, b),
+                _ => None,
+            };
+            match r {
+                Some(r) if r.id() == root => return None,
+                Some(r) if !self.is_gate(r.id()) || self.refs[r.id() as usize] > 0 => {}
+                _ => {
+                    added += 1;
+                    if added >= limit {
+                        return None;
+                    }
+                }
+            }
+            nodes.push(r);
+        }
+        Some(added)
+    }
+
+    /// Replace the root with the replacement, which must be equivalent
+    /// (possibly up to don't-cares).
+    pub(crate) fn substitute(
+        &mut self,
+        root: u32,
+        gates: &[[Ref; 2]],
+        output: Ref,
+        inputs: &[Ref],
+    ) {
+        // Keep the root out of the structural hash table, so that it is not reused:
+        let args = self.fanins[root as usize].unwrap();
+        if self.strash.get(&args) == Some(&root) {
+            self.strash.remove(&args);
+        }
+        let mut nodes = inputs.to_vec();
+        for &[a, b] in gates {
+            let r = self.and(image(&nodes, a), image(&nodes, b));
+            nodes.push(r);
+        }
+        self.replace(root, image(&nodes, output));
+    }
+
+    /// Delete the unreferenced gate, and its fanins which become unreferenced.
+    fn delete(&mut self, id: u32) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            self.dead[id as usize] = true;
+            let args = self.fanins[id as usize].unwrap();
+            if self.strash.get(&args) == Some(&id) {
+                self.strash.remove(&args);
+            }
+            for r in args {
+                let x = r.id();
+                self.refs[x as usize] -= 1;
+                if self.refs[x as usize] == 0 && self.is_gate(x) && !self.dead[x as usize] {
+                    stack.push(x);
+                }
+            }
+        }
+    }
+
+    /// Raise the level of the gate above its fanins, and propagate to the fanouts.
+    fn update_level(&mut self, id: u32) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let args = self.fanins[id as usize].unwrap();
+            let level = 1 + self.level(args[0].id()).max(self.level(args[1].id()));
+            if level > self.levels[id as usize] {
+                self.levels[id as usize] = level;
+                stack.extend(self.fanouts(id));
+            }
+        }
+    }
+
+    /// Replace the gate with the (equivalent) reference everywhere, and delete it.
+    fn replace(&mut self, old: u32, new: Ref) {
+        let mut queue = vec![(old, new)];
+        while let Some((old, new)) = queue.pop() {
+            if self.dead[old as usize] {
+                continue;
+            }
+            let new = self.resolve(new);
+            if self.dead[new.id() as usize] {
+                // The gate it merges with was deleted meanwhile: keep it instead.
+                let args = self.fanins[old as usize].unwrap();
+                self.strash.insert(args, old);
+                continue;
+            }
+            debug_assert_ne!(old, new.id());
+            self.replaced[old as usize] = Some(new);
+            let external = std::mem::take(&mut self.external[old as usize]);
+            self.external[new.id() as usize] += external;
+            self.refs[new.id() as usize] += external;
+            self.refs[old as usize] -= external;
+
+            // Rewire the fanouts, which may become trivial or duplicate other gates:
+            for f in std::mem::take(&mut self.fanouts[old as usize]) {
+                let Some(args) = self.fanins[f as usize] else {
+                    continue;
+                };
+                if self.dead[f as usize] || args.iter().all(|r| r.id() != old) {
+                    continue;
+                }
+                if self.strash.get(&args) == Some(&f) {
+                    self.strash.remove(&args);
+                }
+                let args = normalize(args.map(|r| {
+                    if r.id() == old {
+                        self.refs[old as usize] -= 1;
+                        self.refs[new.id() as usize] += 1;
+                        Ref::from_raw(new.raw() ^ r.is_negated() as u32)
+                    } else {
+                        r
+                    }
+                }));
+                self.fanins[f as usize] = Some(args);
+                self.fanouts[new.id() as usize].push(f);
+                self.update_level(f);
+                match self.lookup(args[0], args[1]) {
+                    Some(r) if r.id() != f => queue.push((f, r)),
+                    _ => {
+                        self.strash.insert(args, f);
+                    }
+                }
+            }
+            debug_assert_eq!(self.refs[old as usize], 0);
+            self.delete(old);
+        }
+    }
+
+    /// Copy the cone of the reference into the builder, using and filling in the map.
+    fn build(&self, builder: &mut AigBuilder, map: &mut [Option<Ref>], r: Ref) -> Ref {
+        let mut stack = vec![(r.id(), false)];
+        while let Some((id, ready)) = stack.pop() {
+            if map[id as usize].is_some() {
+                continue;
+            }
+            let args = self.fanins[id as usize].expect("Unmapped node must be a gate");
+            if ready {
+                let [a, b] = args.map(|r| {
+                    let x = map[r.id() as usize].unwrap();
+                    Ref::from_raw(x.raw() ^ r.is_negated() as u32)
+                });
+                map[id as usize] = Some(builder.and(a, b));
+            } else {
+                stack.push((id, true));
+                for r in args {
+                    stack.push((r.id(), false));
+                }
+            }
+        }
+        let x = map[r.id() as usize].unwrap();
+        Ref::from_raw(x.raw() ^ r.is_negated() as u32)
+    }
+
+    /// Rebuild the AIG from the network, which was created from it
+    /// (see [`Aig::finish_rebuild`]).
+    pub(crate) fn rebuild(&self, aig: &Aig) -> Aig {
+        let mut builder = AigBuilder::new();
+        let mut network_map = vec![None; self.len()];
+        network_map[0] = Some(Ref::FALSE);
+        let mut map = vec![Ref::FALSE; aig.max_id() as usize + 1];
+        for &id in aig.inputs() {
+            map[id as usize] = builder.input();
+            network_map[id as usize] = Some(map[id as usize]);
+        }
+        for &id in aig.latches() {
+            map[id as usize] = builder.latch(aig.latch(id).init);
+            network_map[id as usize] = Some(map[id as usize]);
+        }
+        for r in aig.root_refs() {
+            let id = r.id();
+            let resolved = self.resolve(Ref::from_raw(id << 1));
+            map[id as usize] = self.build(&mut builder, &mut network_map, resolved);
+        }
+
+        for &id in aig.latches() {
+            builder.set_latch_next(map[id as usize], image(&map, aig.latch(id).next));
+        }
+        builder.import_properties(aig, &map);
+        aig.finish_rebuild(builder.build(), None)
+    }
+
+    /// Rebuild the AIG at the end of a pass which replaced `replaced` cuts or nodes,
+    /// and log the statistics.
+    pub(crate) fn finish(&self, aig: &Aig, pass: &str, replaced: usize) -> (Aig, RewriteStats) {
+        let result = self.rebuild(aig);
+        let stats = RewriteStats {
+            gates_before: aig.and_gates().count(),
+            gates_after: result.and_gates().count(),
+            replaced,
+        };
+        log::debug!(
+            "{} replaced {} cuts or nodes, from {} to {} gates",
+            pass,
+            stats.replaced,
+            stats.gates_before,
+            stats.gates_after
+        );
+        (result, stats)
+    }
+}
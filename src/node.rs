@@ -17,6 +17,36 @@ pub struct AigInput {
 pub struct AigLatch {
     pub id: u32,
     pub next: Ref,
+    pub init: LatchInit,
+}
+
+/// Initial (reset) value of a latch.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum LatchInit {
+    #[default]
+    Zero,
+    One,
+    /// Uninitialised latch, its initial value is non-deterministic.
+    Nondet,
+}
+
+impl LatchInit {
+    pub const fn from_bool(value: bool) -> Self {
+        if value {
+            LatchInit::One
+        } else {
+            LatchInit::Zero
+        }
+    }
+
+    /// Return the initial value, or `None` if it is non-deterministic.
+    pub const fn value(self) -> Option<bool> {
+        match self {
+            LatchInit::Zero => Some(false),
+            LatchInit::One => Some(true),
+            LatchInit::Nondet => None,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -31,8 +61,8 @@ impl Node {
     pub const fn input(id: u32) -> Self {
         Node::Input(AigInput { id })
     }
-    pub const fn latch(id: u32, next: Ref) -> Self {
-        Node::Latch(AigLatch { id, next })
+    pub const fn latch(id: u32, next: Ref, init: LatchInit) -> Self {
+        Node::Latch(AigLatch { id, next, init })
     }
     pub const fn and_gate(id: u32, args: [Ref; 2]) -> Self {
         Node::AndGate(AigAndGate { id, args })
@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::builder::normalize;
+use crate::network::fold;
+use crate::reference::Ref;
+
+/// Truth tables of the variables of 4-input functions.
+const VAR_TRUTH: [u16; 4] = [0xAAAA, 0xCCCC, 0xF0F0, 0xFF00];
+
+/// NPN transformation of a 4-input function: permutation and negation of the inputs,
+/// and negation of the output.
+///
+/// Applied to `f`, it gives `g(x) = output ^ f(y)`, where `y[i] = x[perm[i]] ^ negations[i]`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Transform {
+    pub perm: [u8; 4],
+    /// Bit `i` is set if input `i` is negated.
+    pub negations: u8,
+    pub output: bool,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        perm: [0, 1, 2, 3],
+        negations: 0,
+        output: false,
+    };
+
+    /// Apply the transformation to the truth table.
+    pub fn apply(&self, truth: u16) -> u16 {
+        let mut result = 0;
+        for m in 0..16 {
+            let mut index = 0;
+            for i in 0..4 {
+                let bit = ((m >> self.perm[i]) & 1) ^ ((self.negations >> i) & 1) as usize;
+                index |= bit << i;
+            }
+            result |= ((truth >> index) & 1) << m;
+        }
+        if self.output {
+            !result
+        } else {
+            result
+        }
+    }
+
+    /// All 768 NPN transformations.
+    fn all() -> impl Iterator<Item = Transform> {
+        let mut perms = Vec::new();
+        for a in 0..4u8 {
+            for b in 0..4 {
+                for c in 0..4 {
+                    for d in 0..4 {
+                        let perm = [a, b, c, d];
+                        if (0..4).all(|i| perm.contains(&i)) {
+                            perms.push(perm);
+                        }
+                    }
+                }
+            }
+        }
+        perms.into_iter().flat_map(|perm| {
+            (0..32).map(move |k| Transform {
+                perm,
+                negations: k & 15,
+                output: k >= 16,
+            })
+        })
+    }
+}
+
+/// Small AIG: node 0 is the constant, nodes `1..=n` are the inputs,
+/// followed by the AND gates in order. Library structures have 4 inputs.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Structure {
+    pub gates: Vec<[Ref; 2]>,
+    pub output: Ref,
+}
+
+impl Structure {
+    /// Number of AND gates.
+    pub fn size(&self) -> usize {
+        self.gates.len()
+    }
+
+    /// Truth table of the output of a 4-input structure.
+    pub fn truth(&self) -> u16 {
+        let mut values = vec![0u16];
+        values.extend(VAR_TRUTH);
+        let value = |values: &[u16], r: Ref| {
+            let v = values[r.id() as usize];
+            if r.is_negated() {
+                !v
+            } else {
+                v
+            }
+        };
+        for &[a, b] in &self.gates {
+            let v = value(&values, a) & value(&values, b);
+            values.push(v);
+        }
+        value(&values, self.output)
+    }
+}
+
+/// Library of AIG structures for all 222 NPN classes of 4-input functions.
+///
+/// The structures come from a precomputed table, and have at most 10 AND gates; those
+/// with up to 5 gates are minimum-size. The classes are computed on first use, which
+/// takes a fraction of a second.
+pub struct Library {
+    /// Class index of each truth table.
+    classes: Vec<u16>,
+    /// Transformation from the representative of the class to each truth table.
+    transforms: Vec<Transform>,
+    /// Representative (the smallest truth table) of each class.
+    representatives: Vec<u16>,
+    /// Structure of the representative of each class.
+    structures: Vec<Structure>,
+}
+
+impl Library {
+    /// The library, computed on first use.
+    pub fn get() -> &'static Library {
+        static LIBRARY: OnceLock<Library> = OnceLock::new();
+        LIBRARY.get_or_init(Library::compute)
+    }
+
+    fn compute() -> Library {
+        let mut classes = vec![u16::MAX; 1 << 16];
+        let mut transforms = vec![Transform::IDENTITY; 1 << 16];
+        let mut representatives = Vec::new();
+        let all = Transform::all().collect::<Vec<_>>();
+        for truth in 0..=u16::MAX {
+            if classes[truth as usize] != u16::MAX {
+                continue;
+            }
+            let class = representatives.len() as u16;
+            representatives.push(truth);
+            for t in &all {
+                let member = t.apply(truth) as usize;
+                if classes[member] == u16::MAX {
+                    classes[member] = class;
+                    transforms[member] = *t;
+                }
+            }
+        }
+
+        let structures = STRUCTURES
+            .iter()
+            .map(|&(_, gates, output)| Structure {
+                gates: (gates.iter())
+                    .map(|&[a, b]| [Ref::from_raw(a), Ref::from_raw(b)])
+                    .collect(),
+                output: Ref::from_raw(output),
+            })
+            .collect();
+
+        Library {
+            classes,
+            transforms,
+            representatives,
+            structures,
+        }
+    }
+
+    /// Number of NPN classes.
+    pub fn num_classes(&self) -> usize {
+        self.representatives.len()
+    }
+
+    /// NPN canonical form of the function: the representative of its class and the
+    /// transformation such that `truth == transform.apply(representative)`.
+    pub fn canonical(&self, truth: u16) -> (u16, Transform) {
+        let class = self.classes[truth as usize];
+        (
+            self.representatives[class as usize],
+            self.transforms[truth as usize],
+        )
+    }
+
+    /// Structure implementing the representative of the class of the function,
+    /// and the transformation to apply to it.
+    pub fn lookup(&self, truth: u16) -> (&Structure, Transform) {
+        let class = self.classes[truth as usize];
+        (
+            &self.structures[class as usize],
+            self.transforms[truth as usize],
+        )
+    }
+}
+
+/// Structure of the representative of each class, in increasing order of the
+/// representatives: truth table, then AND gates and output as AIGER literals (`2 * id`, plus
+/// one if complemented), with the node ids of [`Structure`].
+///
+/// The table was computed by a breadth-first enumeration of the sets of functions of the
+/// AIGs with up to 8 AND gates, up to NPN equivalence, each extended by up to 2 more gates:
+/// all the classes are reached with at most 10 gates. The structures with up to 5 gates
+/// have the sizes found by an exhaustive enumeration.
+#[rustfmt::skip]
+const STRUCTURES: [(u16, &[[u32; 2]], u32); 222] = [
+    (0x0000, &[], 0),
+    (0x0001, &[[3, 9], [5, 7], [10, 12]], 14),
+    (0x0003, &[[5, 7], [9, 10]], 12),
+    (0x0006, &[[3, 5], [9, 11], [7, 12], [2, 4], [14, 17]], 18),
+    (0x0007, &[[7, 9], [2, 4], [10, 13]], 14),
+    (0x000f, &[[7, 9]], 10),
+    (0x0016, &[[3, 7], [5, 10], [4, 11], [9, 15], [13, 16], [2, 6], [18, 21]], 22),
+    (0x0017, &[[3, 7], [4, 11], [9, 13], [2, 6], [14, 17]], 18),
+    (0x0018, &[[2, 7], [4, 10], [3, 5], [6, 14], [13, 17], [9, 19]], 20),
+    (0x0019, &[[2, 7], [4, 10], [3, 5], [13, 15], [9, 17]], 18),
+    (0x001b, &[[3, 4], [2, 6], [9, 11], [13, 14]], 16),
+    (0x001e, &[[3, 5], [7, 10], [9, 13], [6, 11], [14, 17]], 18),
+    (0x001f, &[[3, 5], [6, 11], [9, 13]], 14),
+    (0x003c, &[[5, 7], [4, 6], [9, 11], [13, 14]], 16),
+    (0x003d, &[[2, 5], [7, 10], [4, 6], [9, 13], [15, 16]], 18),
+    (0x003f, &[[4, 6], [9, 11]], 12),
+    (0x0069, &[[2, 5], [3, 4], [11, 13], [7, 15], [9, 17], [6, 14], [18, 21]], 22),
+    (0x006b, &[[3, 6], [4, 10], [2, 7], [13, 15], [5, 11], [16, 19], [9, 21]], 22),
+    (0x006f, &[[3, 5], [2, 4], [11, 13], [6, 15], [9, 17]], 18),
+    (0x007e, &[[3, 7], [5, 10], [2, 4], [6, 14], [9, 13], [17, 18]], 20),
+    (0x007f, &[[2, 6], [4, 10], [9, 13]], 14),
+    (0x00ff, &[], 9),
+    (0x0116, &[[3, 9], [7, 10], [4, 12], [2, 8], [6, 11], [5, 19], [17, 20], [13, 22], [15, 25]], 27),
+    (0x0117, &[[3, 9], [7, 10], [6, 11], [5, 15], [13, 17], [2, 8], [19, 21]], 22),
+    (0x0118, &[[7, 9], [4, 10], [2, 12], [6, 8], [11, 17], [3, 18], [5, 20], [15, 23]], 25),
+    (0x0119, &[[2, 9], [7, 10], [4, 12], [6, 8], [5, 17], [3, 18], [15, 21]], 23),
+    (0x011a, &[[7, 9], [6, 8], [5, 13], [3, 14], [11, 16], [2, 10], [19, 21]], 23),
+    (0x011b, &[[2, 9], [7, 10], [6, 8], [5, 15], [3, 16], [13, 19]], 21),
+    (0x011e, &[[3, 5], [9, 10], [6, 12], [8, 11], [7, 17], [13, 18], [15, 21]], 23),
+    (0x011f, &[[3, 5], [9, 10], [6, 13], [8, 11], [15, 17]], 18),
+    (0x012c, &[[5, 7], [8, 10], [3, 12], [2, 5], [6, 17], [9, 19], [11, 20], [15, 23]], 25),
+    (0x012d, &[[2, 5], [9, 10], [6, 12], [4, 8], [7, 17], [11, 18], [15, 21]], 23),
+    (0x012f, &[[2, 5], [3, 5], [8, 13], [6, 11], [15, 17]], 18),
+    (0x013c, &[[5, 7], [3, 10], [8, 12], [4, 6], [11, 17], [9, 18], [15, 21]], 23),
+    (0x013d, &[[5, 7], [2, 10], [4, 6], [13, 15], [8, 11], [16, 19]], 20),
+    (0x013e, &[[3, 7], [5, 10], [8, 12], [4, 6], [9, 17], [13, 18], [15, 21]], 23),
+    (0x013f, &[[3, 5], [7, 10], [4, 6], [8, 13], [15, 17]], 18),
+    (0x0168, &[[3, 5], [8, 11], [2, 4], [9, 15], [6, 16], [11, 18], [13, 17], [7, 22], [21, 25]], 27),
+    (0x0169, &[[2, 9], [4, 10], [3, 5], [13, 15], [9, 16], [6, 18], [7, 17], [21, 23]], 25),
+    (0x016a, &[[5, 7], [8, 11], [4, 6], [9, 15], [3, 16], [13, 19], [2, 17], [20, 23]], 24),
+    (0x016b, &[[3, 5], [7, 10], [4, 6], [3, 14], [2, 15], [17, 19], [9, 21], [13, 23]], 25),
+    (0x016e, &[[3, 5], [8, 10], [7, 12], [2, 6], [4, 16], [11, 19], [9, 20], [15, 23]], 25),
+    (0x016f, &[[3, 5], [2, 4], [11, 13], [6, 15], [8, 11], [17, 19]], 20),
+    (0x017e, &[[3, 7], [5, 10], [9, 12], [2, 4], [6, 16], [15, 19], [8, 13], [20, 23]], 24),
+    (0x017f, &[[3, 7], [5, 10], [2, 4], [6, 14], [8, 13], [17, 19]], 20),
+    (0x0180, &[[2, 9], [6, 10], [4, 12], [3, 5], [7, 16], [8, 18], [15, 21]], 23),
+    (0x0181, &[[2, 9], [6, 10], [4, 12], [3, 5], [7, 16], [15, 19]], 21),
+    (0x0182, &[[2, 9], [6, 10], [4, 12], [3, 8], [11, 17], [5, 19], [7, 20], [15, 23]], 25),
+    (0x0183, &[[2, 4], [6, 10], [2, 8], [5, 7], [13, 17], [15, 19]], 20),
+    (0x0186, &[[7, 9], [3, 10], [2, 11], [13, 15], [9, 17], [4, 18], [7, 16], [5, 22], [21, 25]], 27),
+    (0x0187, &[[2, 4], [7, 10], [3, 5], [8, 15], [13, 17], [6, 11], [18, 21]], 22),
+    (0x0189, &[[2, 9], [4, 10], [5, 7], [3, 14], [13, 17]], 19),
+    (0x018b, &[[5, 6], [3, 5], [2, 9], [13, 15], [11, 17]], 18),
+    (0x018f, &[[3, 5], [2, 4], [6, 13], [8, 11], [15, 17]], 18),
+    (0x0196, &[[3, 5], [2, 4], [11, 13], [9, 15], [6, 16], [8, 11], [7, 21], [17, 22], [19, 25]], 27),
+    (0x0197, &[[4, 6], [9, 11], [3, 12], [2, 13], [9, 16], [15, 19], [5, 17], [7, 22], [20, 25]], 27),
+    (0x0198, &[[2, 4], [3, 5], [8, 12], [7, 14], [6, 12], [11, 19], [9, 21], [17, 23]], 25),
+    (0x0199, &[[2, 4], [9, 10], [6, 8], [3, 5], [13, 17], [15, 19]], 20),
+    (0x019a, &[[5, 6], [2, 10], [3, 11], [5, 14], [8, 16], [9, 15], [19, 21], [13, 23]], 24),
+    (0x019b, &[[2, 9], [6, 8], [5, 13], [6, 14], [3, 14], [10, 17], [19, 21]], 23),
+    (0x019e, &[[3, 5], [9, 10], [6, 12], [2, 4], [6, 17], [8, 11], [19, 21], [13, 22], [15, 25]], 27),
+    (0x019f, &[[3, 5], [9, 10], [2, 4], [13, 15], [6, 16], [8, 11], [19, 21]], 22),
+    (0x01a8, &[[5, 7], [8, 10], [3, 12], [2, 9], [11, 16], [15, 19]], 21),
+    (0x01a9, &[[5, 7], [2, 10], [2, 9], [11, 15], [13, 17]], 18),
+    (0x01aa, &[[3, 7], [5, 10], [8, 12], [2, 9], [15, 17]], 19),
+    (0x01ab, &[[3, 7], [5, 10], [2, 9], [13, 15]], 17),
+    (0x01ac, &[[5, 7], [3, 10], [8, 12], [3, 6], [11, 17], [9, 18], [15, 21]], 23),
+    (0x01ad, &[[5, 7], [2, 10], [3, 6], [9, 15], [11, 17], [13, 19]], 20),
+    (0x01ae, &[[4, 7], [3, 11], [7, 12], [8, 14], [9, 13], [17, 19]], 21),
+    (0x01af, &[[3, 5], [3, 6], [8, 11], [13, 15]], 16),
+    (0x01bc, &[[3, 6], [4, 10], [5, 7], [3, 14], [8, 16], [13, 15], [9, 20], [19, 23]], 25),
+    (0x01bd, &[[3, 4], [6, 10], [5, 7], [3, 14], [13, 15], [9, 18], [17, 21]], 23),
+    (0x01be, &[[5, 6], [4, 7], [3, 13], [11, 14], [8, 16], [7, 18], [9, 17], [21, 23]], 25),
+    (0x01bf, &[[7, 9], [3, 11], [7, 12], [4, 12], [8, 15], [17, 19]], 20),
+    (0x01e8, &[[3, 7], [5, 10], [8, 12], [2, 6], [5, 17], [9, 19], [11, 20], [15, 23]], 25),
+    (0x01e9, &[[3, 7], [5, 10], [2, 6], [5, 15], [9, 17], [11, 18], [13, 21]], 23),
+    (0x01ea, &[[4, 6], [3, 11], [8, 12], [7, 14], [5, 16], [9, 13], [19, 21]], 23),
+    (0x01eb, &[[3, 5], [6, 10], [3, 7], [9, 15], [11, 17], [13, 19]], 20),
+    (0x01ee, &[[3, 5], [7, 10], [8, 12], [9, 11], [15, 17]], 19),
+    (0x01ef, &[[3, 5], [6, 10], [8, 11], [13, 15]], 16),
+    (0x01fe, &[[3, 7], [5, 10], [9, 12], [8, 13], [15, 17]], 18),
+    (0x033c, &[[5, 9], [7, 10], [4, 8], [13, 15], [6, 11], [16, 19]], 20),
+    (0x033d, &[[5, 9], [2, 10], [7, 12], [4, 8], [15, 17], [6, 11], [18, 21]], 22),
+    (0x033f, &[[5, 9], [4, 8], [6, 11], [13, 15]], 16),
+    (0x0356, &[[3, 9], [5, 7], [10, 12], [11, 13], [15, 17]], 18),
+    (0x0357, &[[3, 9], [5, 7], [11, 13]], 15),
+    (0x0358, &[[7, 9], [3, 9], [5, 7], [13, 15], [10, 16], [11, 17], [19, 21]], 23),
+    (0x0359, &[[2, 9], [6, 9], [5, 7], [13, 15], [10, 16], [11, 17], [19, 21]], 23),
+    (0x035a, &[[3, 9], [7, 10], [4, 8], [7, 15], [11, 17], [13, 19]], 20),
+    (0x035b, &[[6, 9], [3, 10], [2, 9], [4, 15], [7, 17], [13, 19]], 21),
+    (0x035e, &[[5, 7], [9, 10], [3, 12], [2, 6], [15, 17], [8, 11], [18, 21]], 22),
+    (0x035f, &[[3, 9], [4, 8], [6, 11], [13, 15]], 16),
+    (0x0368, &[[3, 9], [5, 7], [11, 13], [4, 6], [9, 17], [14, 18], [15, 19], [21, 23]], 25),
+    (0x0369, &[[2, 9], [5, 10], [4, 11], [13, 15], [9, 17], [6, 18], [7, 16], [21, 23]], 25),
+    (0x036a, &[[3, 9], [5, 9], [4, 8], [11, 15], [6, 13], [10, 18], [16, 19], [21, 23]], 25),
+    (0x036b, &[[4, 6], [2, 10], [3, 11], [9, 15], [13, 16], [5, 7], [19, 21]], 23),
+    (0x036c, &[[6, 8], [2, 6], [9, 13], [5, 14], [11, 17], [4, 15], [18, 21]], 22),
+    (0x036d, &[[2, 9], [6, 10], [4, 9], [13, 14], [7, 11], [13, 19], [5, 21], [17, 23]], 25),
+    (0x036e, &[[2, 6], [9, 11], [6, 8], [5, 15], [3, 16], [12, 18], [13, 17], [21, 23]], 24),
+    (0x036f, &[[2, 9], [5, 10], [4, 11], [9, 14], [13, 17], [7, 15], [18, 21]], 23),
+    (0x037c, &[[2, 6], [4, 10], [5, 7], [9, 15], [13, 16], [8, 14], [19, 21]], 23),
+    (0x037d, &[[5, 7], [4, 6], [11, 13], [2, 15], [9, 16], [8, 11], [19, 21]], 22),
+    (0x037e, &[[5, 7], [9, 10], [3, 12], [2, 4], [6, 16], [15, 19], [8, 11], [20, 23]], 24),
+    (0x03c0, &[[4, 9], [6, 10], [5, 7], [8, 14], [13, 17]], 19),
+    (0x03c1, &[[4, 9], [6, 10], [2, 9], [5, 7], [15, 16], [13, 19]], 21),
+    (0x03c3, &[[4, 9], [6, 10], [5, 7], [13, 15]], 17),
+    (0x03c5, &[[7, 9], [2, 10], [4, 9], [5, 7], [15, 17], [13, 19]], 20),
+    (0x03c6, &[[3, 9], [7, 11], [9, 13], [4, 14], [5, 12], [17, 19]], 21),
+    (0x03c7, &[[2, 7], [9, 11], [4, 12], [5, 7], [15, 17]], 19),
+    (0x03cf, &[[4, 9], [5, 7], [11, 13]], 15),
+    (0x03d4, &[[5, 7], [9, 10], [4, 6], [2, 15], [9, 17], [11, 19], [13, 21]], 22),
+    (0x03d5, &[[7, 8], [5, 10], [4, 6], [2, 15], [9, 17], [13, 19]], 21),
+    (0x03d6, &[[5, 7], [4, 6], [2, 13], [9, 15], [10, 16], [11, 17], [19, 21]], 22),
+    (0x03d7, &[[4, 6], [2, 11], [9, 13], [5, 7], [15, 17]], 19),
+    (0x03d8, &[[5, 7], [8, 10], [3, 7], [2, 5], [15, 17], [9, 18], [13, 21]], 23),
+    (0x03d9, &[[3, 7], [9, 11], [5, 12], [2, 14], [5, 7], [13, 19], [17, 21]], 22),
+    (0x03db, &[[3, 7], [2, 5], [9, 13], [11, 14], [5, 7], [17, 19]], 21),
+    (0x03dc, &[[3, 6], [5, 11], [8, 12], [7, 14], [9, 13], [17, 19]], 21),
+    (0x03dd, &[[5, 8], [7, 10], [2, 5], [9, 15], [13, 17]], 19),
+    (0x03de, &[[5, 7], [2, 5], [9, 13], [10, 14], [11, 15], [17, 19]], 20),
+    (0x03fc, &[[5, 7], [9, 10], [8, 11], [13, 15]], 16),
+    (0x0660, &[[3, 5], [7, 9], [11, 13], [6, 8], [14, 17], [2, 4], [18, 21]], 22),
+    (0x0661, &[[3, 5], [2, 4], [6, 8], [7, 9], [10, 16], [15, 17], [13, 20], [11, 22], [19, 25]], 27),
+    (0x0662, &[[6, 8], [7, 9], [4, 13], [3, 14], [2, 5], [17, 19], [11, 21]], 22),
+    (0x0663, &[[6, 8], [7, 9], [3, 13], [5, 14], [11, 17], [4, 15], [18, 21]], 22),
+    (0x0666, &[[3, 5], [6, 8], [11, 13], [2, 4], [14, 17]], 18),
+    (0x0667, &[[3, 5], [8, 10], [9, 11], [6, 15], [13, 17], [2, 4], [18, 21]], 22),
+    (0x0669, &[[2, 5], [3, 4], [11, 13], [9, 15], [6, 16], [8, 14], [7, 21], [17, 22], [19, 25]], 27),
+    (0x066b, &[[3, 4], [2, 5], [11, 13], [6, 8], [15, 17], [7, 9], [10, 20], [19, 21], [23, 25]], 26),
+    (0x066f, &[[2, 5], [3, 4], [11, 13], [9, 15], [6, 17], [8, 14], [19, 21]], 22),
+    (0x0672, &[[2, 4], [4, 8], [7, 13], [3, 14], [11, 17], [6, 8], [18, 21]], 22),
+    (0x0673, &[[3, 5], [7, 11], [9, 12], [3, 15], [4, 17], [8, 13], [19, 21]], 22),
+    (0x0676, &[[3, 5], [7, 10], [6, 8], [13, 15], [2, 4], [16, 19]], 20),
+    (0x0678, &[[7, 9], [3, 5], [7, 13], [8, 15], [2, 4], [10, 18], [17, 19], [11, 22], [21, 25]], 27),
+    (0x0679, &[[3, 5], [7, 11], [9, 12], [8, 13], [2, 4], [14, 18], [17, 19], [15, 22], [21, 25]], 27),
+    (0x067a, &[[4, 8], [7, 11], [4, 13], [2, 14], [3, 12], [17, 19], [6, 8], [20, 23]], 24),
+    (0x067b, &[[2, 4], [3, 5], [7, 13], [9, 14], [3, 16], [10, 17], [19, 21], [8, 15], [22, 25]], 26),
+    (0x067e, &[[3, 7], [5, 10], [7, 9], [6, 8], [13, 17], [2, 4], [15, 20], [18, 23]], 24),
+    (0x0690, &[[3, 5], [2, 4], [11, 13], [9, 15], [6, 16], [7, 14], [8, 20], [19, 23]], 25),
+    (0x0691, &[[2, 4], [6, 10], [3, 5], [13, 15], [8, 16], [7, 18], [11, 20], [9, 17], [23, 25]], 27),
+    (0x0693, &[[2, 6], [6, 8], [3, 8], [11, 15], [4, 17], [13, 18], [5, 16], [21, 23]], 25),
+    (0x0696, &[[3, 5], [2, 4], [11, 13], [9, 15], [6, 16], [7, 14], [19, 21]], 23),
+    (0x0697, &[[2, 4], [6, 10], [3, 5], [13, 15], [9, 17], [11, 15], [7, 20], [19, 23]], 25),
+    (0x069f, &[[3, 5], [2, 4], [11, 13], [9, 15], [7, 14], [17, 19]], 21),
+    (0x06b0, &[[2, 5], [3, 4], [11, 13], [8, 15], [7, 16], [6, 13], [9, 20], [19, 23]], 25),
+    (0x06b1, &[[2, 7], [4, 7], [8, 12], [3, 4], [9, 17], [15, 19], [10, 20], [11, 21], [23, 25]], 27),
+    (0x06b2, &[[3, 4], [9, 10], [6, 9], [2, 5], [11, 17], [7, 19], [15, 21], [13, 23]], 24),
+    (0x06b3, &[[2, 6], [2, 11], [8, 13], [4, 11], [14, 16], [7, 18], [15, 17], [21, 23]], 25),
+    (0x06b4, &[[5, 8], [2, 10], [3, 4], [13, 15], [6, 16], [9, 18], [7, 17], [21, 23]], 25),
+    (0x06b5, &[[2, 7], [4, 7], [3, 4], [9, 15], [13, 17], [10, 18], [11, 19], [21, 23]], 25),
+    (0x06b6, &[[2, 5], [3, 4], [9, 13], [6, 14], [11, 13], [7, 19], [17, 21]], 23),
+    (0x06b7, &[[4, 7], [3, 10], [2, 11], [7, 14], [13, 17], [4, 15], [9, 21], [18, 23]], 25),
+    (0x06b9, &[[2, 7], [5, 10], [3, 4], [13, 15], [7, 17], [8, 18], [9, 16], [21, 23]], 25),
+    (0x06bd, &[[2, 5], [3, 4], [11, 13], [7, 15], [9, 13], [16, 18], [17, 19], [21, 23]], 24),
+    (0x06f0, &[[4, 8], [7, 11], [3, 7], [8, 15], [12, 16], [13, 17], [19, 21]], 23),
+    (0x06f1, &[[2, 4], [3, 5], [7, 13], [8, 14], [11, 16], [9, 15], [19, 21]], 23),
+    (0x06f2, &[[3, 8], [4, 10], [6, 8], [2, 5], [13, 17], [7, 18], [15, 21]], 22),
+    (0x06f6, &[[3, 4], [2, 5], [7, 13], [11, 14], [6, 8], [17, 19]], 20),
+    (0x06f9, &[[2, 4], [3, 5], [7, 13], [11, 14], [9, 16], [8, 17], [19, 21]], 22),
+    (0x0776, &[[3, 9], [7, 10], [5, 12], [2, 4], [15, 17], [6, 8], [18, 21]], 22),
+    (0x0778, &[[2, 4], [6, 8], [7, 9], [10, 14], [13, 15], [11, 18], [17, 21]], 23),
+    (0x0779, &[[7, 9], [4, 10], [3, 12], [5, 11], [2, 17], [13, 18], [15, 21], [6, 8], [22, 25]], 26),
+    (0x077a, &[[7, 9], [2, 10], [2, 4], [11, 15], [13, 17], [6, 8], [19, 21]], 22),
+    (0x077e, &[[7, 9], [5, 10], [3, 12], [6, 8], [15, 17], [2, 4], [11, 20], [18, 23]], 24),
+    (0x07b0, &[[2, 4], [7, 11], [3, 4], [9, 15], [6, 16], [8, 12], [19, 21]], 23),
+    (0x07b1, &[[7, 8], [5, 10], [2, 7], [13, 14], [3, 4], [9, 19], [11, 21], [17, 23]], 24),
+    (0x07b4, &[[5, 9], [2, 4], [11, 13], [6, 15], [9, 16], [7, 14], [19, 21]], 23),
+    (0x07b5, &[[5, 8], [2, 11], [3, 4], [9, 15], [6, 16], [7, 13], [19, 21]], 23),
+    (0x07b6, &[[2, 4], [4, 11], [9, 13], [3, 14], [6, 14], [11, 17], [7, 20], [19, 23]], 25),
+    (0x07bc, &[[2, 4], [3, 4], [9, 13], [7, 11], [14, 16], [15, 17], [19, 21]], 22),
+    (0x07e0, &[[2, 4], [7, 11], [3, 5], [9, 15], [6, 16], [8, 12], [19, 21]], 23),
+    (0x07e1, &[[2, 4], [7, 11], [3, 5], [9, 15], [6, 16], [12, 17], [19, 21]], 23),
+    (0x07e2, &[[6, 8], [3, 8], [4, 6], [13, 15], [2, 5], [16, 19], [11, 21]], 22),
+    (0x07e3, &[[4, 9], [3, 11], [9, 13], [6, 14], [4, 13], [7, 19], [17, 21]], 23),
+    (0x07e6, &[[3, 9], [5, 10], [6, 9], [2, 4], [7, 17], [15, 19], [13, 21]], 22),
+    (0x07e9, &[[2, 4], [3, 5], [9, 13], [7, 11], [14, 16], [15, 17], [19, 21]], 22),
+    (0x07f0, &[[2, 4], [8, 11], [7, 12], [6, 9], [15, 17]], 19),
+    (0x07f1, &[[2, 4], [3, 5], [9, 13], [7, 14], [7, 11], [8, 19], [17, 21]], 22),
+    (0x07f2, &[[2, 4], [3, 9], [7, 13], [11, 14], [6, 9], [17, 19]], 21),
+    (0x07f8, &[[2, 4], [7, 11], [9, 12], [8, 13], [15, 17]], 18),
+    (0x0ff0, &[[6, 9], [7, 8], [11, 13]], 15),
+    (0x1668, &[[3, 9], [2, 8], [4, 6], [13, 15], [5, 7], [11, 19], [17, 21], [16, 20], [23, 25]], 27),
+    (0x1669, &[[3, 5], [2, 4], [8, 13], [6, 14], [11, 13], [7, 9], [17, 21], [18, 22], [19, 23], [25, 27]], 29),
+    (0x166a, &[[4, 8], [5, 9], [6, 13], [2, 14], [3, 15], [10, 18], [17, 19], [11, 22], [21, 25]], 27),
+    (0x166b, &[[4, 6], [5, 7], [11, 13], [9, 14], [3, 17], [8, 15], [13, 17], [2, 23], [18, 21], [25, 27]], 29),
+    (0x166e, &[[3, 5], [2, 4], [8, 12], [11, 15], [9, 13], [6, 19], [10, 20], [16, 21], [23, 25]], 27),
+    (0x167e, &[[2, 4], [6, 10], [3, 5], [6, 14], [7, 11], [8, 19], [13, 21], [15, 22], [17, 25]], 27),
+    (0x1681, &[[2, 4], [3, 5], [6, 9], [13, 15], [7, 8], [15, 19], [11, 21], [16, 22], [17, 23], [25, 27]], 29),
+    (0x1683, &[[4, 6], [9, 10], [2, 12], [5, 7], [11, 17], [3, 8], [16, 21], [15, 23], [18, 20], [24, 27]], 29),
+    (0x1686, &[[2, 4], [6, 8], [3, 5], [11, 15], [13, 17], [6, 15], [18, 20], [19, 21], [23, 25]], 27),
+    (0x1687, &[[3, 8], [5, 10], [2, 4], [8, 14], [13, 15], [6, 19], [17, 20], [7, 18], [23, 25]], 27),
+    (0x1689, &[[3, 8], [7, 10], [2, 9], [13, 15], [6, 11], [5, 19], [16, 20], [4, 17], [23, 25]], 27),
+    (0x168b, &[[3, 8], [7, 10], [2, 9], [13, 15], [6, 11], [5, 19], [13, 20], [4, 17], [23, 25]], 27),
+    (0x168e, &[[3, 5], [8, 10], [6, 12], [2, 4], [8, 16], [11, 19], [6, 17], [20, 23], [15, 25]], 27),
+    (0x1696, &[[2, 6], [9, 10], [3, 7], [13, 15], [4, 17], [11, 15], [5, 20], [19, 23]], 25),
+    (0x1697, &[[2, 6], [9, 10], [3, 7], [8, 14], [11, 17], [5, 19], [13, 15], [4, 22], [21, 25]], 26),
+    (0x1698, &[[2, 4], [3, 5], [8, 13], [7, 14], [11, 16], [6, 12], [19, 21], [9, 10], [22, 25]], 27),
+    (0x1699, &[[2, 4], [6, 11], [3, 5], [11, 15], [8, 13], [16, 18], [17, 19], [21, 23]], 25),
+    (0x169a, &[[4, 8], [6, 10], [5, 6], [11, 15], [3, 17], [13, 18], [2, 16], [21, 23]], 25),
+    (0x169b, &[[7, 8], [3, 10], [2, 6], [13, 15], [2, 9], [4, 19], [13, 20], [5, 17], [23, 25]], 26),
+    (0x169e, &[[2, 4], [9, 10], [3, 5], [6, 14], [13, 17], [11, 15], [7, 20], [18, 23]], 25),
+    (0x16a9, &[[3, 8], [7, 10], [2, 9], [13, 15], [6, 11], [5, 19], [17, 20], [16, 21], [23, 25]], 26),
+    (0x16ac, &[[5, 7], [5, 8], [2, 9], [6, 13], [3, 17], [15, 19], [10, 20], [11, 21], [23, 25]], 27),
+    (0x16ad, &[[2, 5], [7, 10], [3, 6], [13, 15], [3, 4], [8, 19], [17, 20], [16, 21], [23, 25]], 27),
+    (0x16bc, &[[2, 8], [4, 7], [3, 4], [6, 15], [13, 17], [10, 18], [11, 19], [21, 23]], 25),
+    (0x16e9, &[[4, 6], [3, 11], [9, 12], [8, 13], [15, 17], [5, 7], [19, 20], [18, 21], [23, 25]], 27),
+    (0x177e, &[[3, 9], [4, 6], [5, 7], [10, 14], [2, 15], [8, 18], [17, 21], [11, 12], [22, 25]], 26),
+    (0x178e, &[[2, 4], [9, 10], [3, 5], [9, 14], [11, 17], [6, 15], [18, 21], [13, 23]], 25),
+    (0x1796, &[[2, 4], [9, 10], [3, 5], [13, 15], [9, 17], [7, 18], [7, 11], [16, 23], [21, 25]], 26),
+    (0x1798, &[[5, 6], [3, 10], [2, 4], [8, 15], [7, 16], [13, 19], [9, 14], [20, 23]], 25),
+    (0x179a, &[[5, 6], [4, 8], [2, 13], [11, 14], [7, 8], [11, 19], [3, 21], [17, 23]], 25),
+    (0x17ac, &[[5, 7], [3, 6], [11, 13], [8, 15], [5, 16], [2, 8], [14, 21], [19, 23]], 25),
+    (0x17e8, &[[3, 7], [2, 6], [5, 13], [11, 15], [9, 17], [8, 16], [19, 21]], 22),
+    (0x18e7, &[[2, 7], [4, 10], [3, 5], [6, 14], [13, 17], [9, 19], [8, 18], [21, 23]], 24),
+    (0x19e1, &[[2, 8], [7, 10], [4, 12], [3, 5], [6, 9], [16, 19], [15, 21], [17, 18], [22, 25]], 27),
+    (0x19e3, &[[4, 7], [2, 10], [3, 7], [3, 5], [13, 17], [9, 15], [19, 20], [18, 21], [23, 25]], 26),
+    (0x19e6, &[[4, 7], [2, 10], [3, 5], [13, 15], [8, 16], [9, 17], [19, 21]], 22),
+    (0x1bd8, &[[2, 7], [2, 5], [5, 8], [6, 9], [15, 17], [12, 18], [10, 21], [13, 19], [23, 25]], 27),
+    (0x1be4, &[[2, 7], [3, 5], [11, 13], [9, 15], [8, 14], [17, 19]], 20),
+    (0x1ee1, &[[6, 9], [7, 8], [11, 13], [3, 5], [15, 16], [14, 17], [19, 21]], 22),
+    (0x3cc3, &[[4, 7], [5, 6], [11, 13], [9, 15], [8, 14], [17, 19]], 20),
+    (0x6996, &[[4, 7], [5, 6], [11, 13], [9, 15], [8, 14], [17, 19], [2, 20], [3, 21], [23, 25]], 27),
+];
+
+/// Structurally hashed construction of a [`Structure`].
+pub(crate) struct StructureBuilder {
+    inputs: u32,
+    gates: Vec<[Ref; 2]>,
+    strash: HashMap<[Ref; 2], u32>,
+}
+
+impl StructureBuilder {
+    pub(crate) fn new(inputs: usize) -> Self {
+        Self {
+            inputs: inputs as u32,
+            gates: Vec::new(),
+            strash: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn input(&self, i: usize) -> Ref {
+        Ref::positive(i as u32 + 1)
+    }
+
+    pub(crate) fn finish(self, output: Ref) -> Structure {
+        Structure {
+            gates: self.gates,
+            output,
+        }
+    }
+
+    pub(crate) fn and(&mut self, a: Ref, b: Ref) -> Ref {
+        if let Some(r) = fold(a, b) {
+            return r;
+        }
+        let args = normalize([a, b]);
+        let next = self.gates.len() as u32 + self.inputs + 1;
+        let id = *self.strash.entry(args).or_insert(next);
+        if id == next {
+            self.gates.push(args);
+        }
+        Ref::positive(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Find minimum-size structures for all functions implementable with up to `max_size`
+    /// AND gates, by depth-first enumeration of the AIGs over 4 inputs.
+    fn enumerate_exact(max_size: usize) -> Vec<Option<Structure>> {
+        fn record(best: &mut [Option<Structure>], truth: u16, gates: &[[Ref; 2]], output: Ref) {
+            let entry = &mut best[truth as usize];
+            if entry.as_ref().is_none_or(|s| s.size() > gates.len()) {
+                *entry = Some(Structure {
+                    gates: gates.to_vec(),
+                    output,
+                });
+            }
+        }
+
+        fn search(
+            values: &mut Vec<u16>,
+            gates: &mut Vec<[Ref; 2]>,
+            best: &mut [Option<Structure>],
+            max_size: usize,
+        ) {
+            if gates.len() == max_size {
+                return;
+            }
+            // Gates are enumerated in increasing order of (j, i, k), so each set of gates
+            // is only visited once, in a topological order.
+            let n = values.len();
+            let last = gates.last().map_or((0, 0, 0), |&[a, b]| {
+                let k = a.is_negated() as usize | (b.is_negated() as usize) << 1;
+                (b.id() as usize, a.id() as usize, k)
+            });
+            for j in last.0.max(2)..n {
+                for i in 1..j {
+                    for k in 0..4 {
+                        if (j, i, k) <= last {
+                            continue;
+                        }
+                        let (ni, nj) = (k & 1 != 0, k & 2 != 0);
+                        let vi = if ni { !values[i] } else { values[i] };
+                        let vj = if nj { !values[j] } else { values[j] };
+                        let v = vi & vj;
+                        if v == 0 || values.iter().any(|&x| x == v || x == !v) {
+                            continue;
+                        }
+                        gates.push([
+                            Ref::from_raw((i as u32) << 1 | ni as u32),
+                            Ref::from_raw((j as u32) << 1 | nj as u32),
+                        ]);
+                        values.push(v);
+                        let output = Ref::positive(n as u32);
+                        record(best, v, gates, output);
+                        record(best, !v, gates, -output);
+                        search(values, gates, best, max_size);
+                        values.pop();
+                        gates.pop();
+                    }
+                }
+            }
+        }
+
+        let mut best = vec![None; 1 << 16];
+        record(&mut best, 0, &[], Ref::FALSE);
+        record(&mut best, 0xFFFF, &[], Ref::TRUE);
+        for (i, &v) in VAR_TRUTH.iter().enumerate() {
+            let input = Ref::positive(i as u32 + 1);
+            record(&mut best, v, &[], input);
+            record(&mut best, !v, &[], -input);
+        }
+        let mut values = vec![0];
+        values.extend(VAR_TRUTH);
+        search(&mut values, &mut Vec::new(), &mut best, max_size);
+        best
+    }
+
+    #[test]
+    fn test_transform() {
+        let and = VAR_TRUTH[0] & VAR_TRUTH[1];
+        let t = Transform {
+            perm: [2, 3, 0, 1],
+            negations: 0b0001,
+            output: true,
+        };
+        // ~(~x2 & x3)
+        assert_eq!(t.apply(and), !(!VAR_TRUTH[2] & VAR_TRUTH[3]));
+        assert_eq!(Transform::IDENTITY.apply(0x1234), 0x1234);
+        assert_eq!(Transform::all().count(), 768);
+    }
+
+    #[test]
+    fn test_library() {
+        let library = Library::get();
+        assert_eq!(library.num_classes(), 222);
+
+        for truth in [0u16, 0xFFFF, 0x8888, 0x6666, 0x6996, 0xE8E8, 0x1234, 0xBEEF] {
+            let (representative, transform) = library.canonical(truth);
+            assert_eq!(transform.apply(representative), truth);
+            let (structure, transform) = library.lookup(truth);
+            assert_eq!(transform.apply(structure.truth()), truth);
+        }
+
+        // Known minimum sizes: AND, XOR, majority, and XOR of three and four inputs.
+        let size = |truth: u16| library.lookup(truth).0.size();
+        assert_eq!(size(0x8888), 1);
+        assert_eq!(size(0x6666), 3);
+        assert_eq!(size(0xE8E8), 4);
+        assert_eq!(size(0x9696), 6);
+        assert_eq!(size(0x6996), 9);
+    }
+
+    #[test]
+    fn test_structures() {
+        // The table has a structure per class, in order, with the sizes of an exhaustive
+        // enumeration of the AIGs with up to 5 AND gates.
+        let library = Library::get();
+        assert_eq!(STRUCTURES.len(), library.num_classes());
+        let exact = enumerate_exact(5);
+        for (class, &(truth, gates, _)) in STRUCTURES.iter().enumerate() {
+            assert_eq!(truth, library.representatives[class]);
+            assert_eq!(library.structures[class].truth(), truth);
+            match &exact[truth as usize] {
+                Some(structure) => assert_eq!(gates.len(), structure.size()),
+                None => assert!(gates.len() > 5),
+            }
+        }
+    }
+}
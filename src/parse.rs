@@ -9,6 +9,10 @@ use crate::aiger::{Literal, Reader, Record};
 use crate::reference::Ref;
 
 impl Aig {
+    /// Read the AIG from a file.
+    ///
+    /// Both the ASCII (`aag`) and the binary (`aig`) formats are supported,
+    /// the format is detected from the header.
     pub fn from_file<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
         let path = path.as_ref();
         log::debug!("Reading AIG from {}", path.display());
@@ -27,13 +31,25 @@ impl Aig {
                     assert!(!id.is_negated());
                     aig.add_input(id.index());
                 }
-                Record::Latch { id, next } => {
+                Record::Latch { id, next, init } => {
                     assert!(!id.is_negated());
-                    aig.add_latch(id.index(), lit2ref(next));
+                    aig.add_latch(id.index(), lit2ref(next), init);
                 }
                 Record::Output { id } => {
                     aig.add_output(lit2ref(id));
                 }
+                Record::Bad { id } => {
+                    aig.add_bad(lit2ref(id));
+                }
+                Record::Constraint { id } => {
+                    aig.add_constraint(lit2ref(id));
+                }
+                Record::Justice { literals } => {
+                    aig.add_justice(literals.into_iter().map(lit2ref).collect());
+                }
+                Record::Fairness { id } => {
+                    aig.add_fairness(lit2ref(id));
+                }
                 Record::AndGate {
                     id,
                     inputs: [left, right],
@@ -42,8 +58,15 @@ impl Aig {
                     let args = [lit2ref(left), lit2ref(right)];
                     aig.add_and_gate(id.index(), args);
                 }
-                Record::Symbol { .. } => {
-                    // do nothing
+                Record::Symbol {
+                    type_spec,
+                    position,
+                    symbol,
+                } => {
+                    aig.set_symbol(type_spec, position, symbol);
+                }
+                Record::Comment { text } => {
+                    aig.add_comment(text);
                 }
             }
         }
@@ -89,4 +112,53 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_parse_symbols() {
+        let aig = Aig::from_file("data/examples/toggle-re.aag").unwrap();
+        assert_eq!(aig.input_name(1), Some("enable"));
+        assert_eq!(aig.input_name(2), Some("reset"));
+        assert_eq!(aig.latch_name(3), None);
+        assert_eq!(aig.output_name(1), Some("!Q"));
+        assert_eq!(aig.find_input("reset"), Some(2));
+        assert_eq!(aig.find_output("Q"), Some(0));
+        assert_eq!(aig.find_output("enable"), None);
+
+        let s = aig.write_to_string().unwrap();
+        let expected = std::fs::read_to_string("data/examples/toggle-re.aag").unwrap();
+        assert!(s.ends_with("i0 enable\ni1 reset\no0 Q\no1 !Q\n"));
+        assert_eq!(s, expected);
+    }
+
+    #[test]
+    fn test_parse_property_symbols() {
+        let input = indoc! {"
+            aag 3 1 1 0 1 1 1 2 1
+            2
+            4 6
+            7
+            3
+            2
+            1
+            6
+            2
+            4
+            5
+            6 2 4
+            b0 bad
+            c0 constraint
+            j1 justice
+        "};
+        let aig = parse_aig(input);
+        assert_eq!(aig.bad_name(0), Some("bad"));
+        assert_eq!(aig.constraint_name(0), Some("constraint"));
+        assert_eq!(aig.justice_name(0), None);
+        assert_eq!(aig.justice_name(1), Some("justice"));
+        assert_eq!(aig.fairness_name(0), None);
+        assert_eq!(aig.find_bad("bad"), Some(0));
+        assert_eq!(aig.find_constraint("constraint"), Some(0));
+        assert_eq!(aig.find_justice("justice"), Some(1));
+        assert_eq!(aig.find_fairness("justice"), None);
+        assert_eq!(aig.find_bad("constraint"), None);
+    }
 }
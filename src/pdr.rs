@@ -0,0 +1,649 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::aig::Aig;
+use crate::aiger::SymbolType;
+use crate::builder::AigBuilder;
+use crate::cnf::{Cnf, CnfEncoder};
+use crate::reference::Ref;
+use crate::sat::{ClauseSink, SatSolver, SolveResult, Solver};
+use crate::sim::SequentialSimulator;
+use crate::witness::{Property, Witness};
+
+/// Inductive invariant over the latches of an AIG, in conjunctive normal form.
+///
+/// Each clause is a disjunction of latch references.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Invariant {
+    pub clauses: Vec<Vec<Ref>>,
+}
+
+impl Invariant {
+    /// Export the invariant as a combinational AIG, whose inputs are the latches
+    /// of the design (in the same order and with the same names) and whose
+    /// only output is the invariant.
+    pub fn to_aig(&self, design: &Aig) -> Aig {
+        let mut builder = AigBuilder::new();
+        let mut map = vec![Ref::FALSE; design.max_id() as usize + 1];
+        for &id in design.latches() {
+            map[id as usize] = builder.input();
+        }
+        let clauses = self
+            .clauses
+            .iter()
+            .map(|clause| {
+                let lits = clause
+                    .iter()
+                    .map(|&r| Ref::new(map[r.id() as usize].id(), r.is_negated()))
+                    .collect::<Vec<_>>();
+                builder.or_many(&lits)
+            })
+            .collect::<Vec<_>>();
+        let invariant = builder.and_many(&clauses);
+        builder.output(invariant);
+
+        let mut aig = builder.build();
+        for (position, &id) in design.latches().iter().enumerate() {
+            if let Some(name) = design.latch_name(id) {
+                aig.set_symbol(SymbolType::Input, position, name.to_string());
+            }
+        }
+        aig
+    }
+
+    /// Export the invariant as a CNF formula, where variable `i + 1` is the `i`-th latch of the design.
+    pub fn to_cnf(&self, design: &Aig) -> Cnf {
+        let var = |id: u32| design.latches().iter().position(|&x| x == id).unwrap() as i32 + 1;
+        Cnf {
+            num_vars: design.latches().len(),
+            clauses: self
+                .clauses
+                .iter()
+                .map(|clause| {
+                    clause
+                        .iter()
+                        .map(|&r| {
+                            if r.is_negated() {
+                                -var(r.id())
+                            } else {
+                                var(r.id())
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+            comments: vec!["inductive invariant".to_string()],
+        }
+    }
+}
+
+/// Result of IC3/PDR.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PdrResult {
+    /// All safety properties hold, certified by the inductive invariant.
+    Proved(Invariant),
+    /// A safety property is violated, the witness can be replayed with [`Aig::check_witness`].
+    Counterexample(Witness),
+    /// Neither proved nor refuted within the maximum number of frames.
+    Unknown { frames: usize },
+}
+
+/// Cube over latches: `(latch position, value)` pairs, sorted by position.
+type Cube = Vec<(usize, bool)>;
+
+fn full_cube(state: &[bool]) -> Cube {
+    state.iter().copied().enumerate().collect()
+}
+
+/// Proof obligation: the state must be blocked in the frame.
+struct Obligation {
+    frame: usize,
+    /// Full assignment of the latches.
+    state: Vec<bool>,
+    /// Inputs applied in this state on the way to the bad state.
+    inputs: Vec<bool>,
+    /// The successor obligation, `None` for a bad state.
+    next: Option<usize>,
+}
+
+struct Pdr<'a, 's, S: SatSolver + ?Sized> {
+    aig: &'a Aig,
+    solver: &'s mut S,
+    /// Latch reset values.
+    resets: Vec<Option<bool>>,
+    state: Vec<i32>,
+    next: Vec<i32>,
+    inputs: Vec<i32>,
+    /// Literal implying that some safety property is violated.
+    bad: i32,
+    /// Activation literals of the frames; frame `0` holds the initial states.
+    activations: Vec<i32>,
+    /// Blocked cubes, stored in the highest frame they are blocked in.
+    frames: Vec<Vec<Cube>>,
+}
+
+impl<'a, 's, S: SatSolver + ?Sized> Pdr<'a, 's, S> {
+    fn new(aig: &'a Aig, solver: &'s mut S) -> Self {
+        // The transition relation, constraints hold in the current state:
+        let mut encoder = CnfEncoder::new(aig);
+        let state = aig
+            .latches()
+            .iter()
+            .map(|&id| encoder.encode(solver, Ref::positive(id)))
+            .collect::<Vec<_>>();
+        let inputs = aig
+            .inputs()
+            .iter()
+            .map(|&id| encoder.encode(solver, Ref::positive(id)))
+            .collect::<Vec<_>>();
+        let next = aig
+            .latches()
+            .iter()
+            .map(|&id| encoder.encode(solver, aig.latch(id).next))
+            .collect::<Vec<_>>();
+        for &c in aig.constraints() {
+            let lit = encoder.encode(solver, c);
+            solver.add_clause(&[lit]);
+        }
+        let bad = solver.new_var();
+        let mut clause = vec![-bad];
+        clause.extend(
+            aig.safety_properties()
+                .iter()
+                .map(|&p| encoder.encode(solver, p)),
+        );
+        solver.add_clause(&clause);
+
+        let resets = aig
+            .latches()
+            .iter()
+            .map(|&id| aig.latch(id).init.value())
+            .collect::<Vec<_>>();
+        let init = solver.new_var();
+        for (&lit, reset) in state.iter().zip(&resets) {
+            match reset {
+                Some(true) => solver.add_clause(&[-init, lit]),
+                Some(false) => solver.add_clause(&[-init, -lit]),
+                None => {}
+            }
+        }
+
+        Self {
+            aig,
+            solver,
+            resets,
+            state,
+            next,
+            inputs,
+            bad,
+            activations: vec![init],
+            frames: vec![Vec::new()],
+        }
+    }
+
+    fn add_frame(&mut self) {
+        let activation = self.solver.new_var();
+        self.activations.push(activation);
+        self.frames.push(Vec::new());
+    }
+
+    fn depth(&self) -> usize {
+        self.frames.len() - 1
+    }
+
+    /// Assumptions selecting the frame.
+    fn frame_assumptions(&self, frame: usize) -> Vec<i32> {
+        self.activations[frame..].to_vec()
+    }
+
+    fn state_lit(&self, (index, value): (usize, bool)) -> i32 {
+        if value {
+            self.state[index]
+        } else {
+            -self.state[index]
+        }
+    }
+
+    fn next_lit(&self, (index, value): (usize, bool)) -> i32 {
+        if value {
+            self.next[index]
+        } else {
+            -self.next[index]
+        }
+    }
+
+    fn model(&self, lits: &[i32]) -> Vec<bool> {
+        lits.iter()
+            .map(|&lit| self.solver.value(lit).unwrap_or(false))
+            .collect()
+    }
+
+    fn intersects_init(&self, cube: &[(usize, bool)]) -> bool {
+        cube.iter()
+            .all(|&(index, value)| self.resets[index] != Some(!value))
+    }
+
+    /// Add back a literal of `original` to make the cube disjoint from the initial states.
+    fn exclude_init(&self, mut cube: Cube, original: &[(usize, bool)]) -> Cube {
+        if self.intersects_init(&cube) {
+            let lit = *original
+                .iter()
+                .find(|&&(index, value)| self.resets[index] == Some(!value))
+                .expect("Cube must be disjoint from the initial states");
+            cube.push(lit);
+            cube.sort_unstable();
+        }
+        cube
+    }
+
+    fn is_blocked(&mut self, cube: &[(usize, bool)], frame: usize) -> bool {
+        let mut assumptions = self.frame_assumptions(frame);
+        assumptions.extend(cube.iter().map(|&l| self.state_lit(l)));
+        self.solver.solve_with(&assumptions) == SolveResult::Unsat
+    }
+
+    /// Check whether the cube is inductive relative to the previous frame:
+    /// `F[frame - 1] & ~cube & T & cube'` is unsatisfiable.
+    /// Returns the subset of the cube in the unsatisfiable core, or a predecessor state and inputs.
+    fn relative_inductive(
+        &mut self,
+        cube: &[(usize, bool)],
+        frame: usize,
+    ) -> Result<Cube, (Vec<bool>, Vec<bool>)> {
+        let activation = self.solver.new_var();
+        let mut clause = vec![-activation];
+        clause.extend(cube.iter().map(|&l| -self.state_lit(l)));
+        self.solver.add_clause(&clause);
+
+        let mut assumptions = self.frame_assumptions(frame - 1);
+        assumptions.push(activation);
+        assumptions.extend(cube.iter().map(|&l| self.next_lit(l)));
+        let result = match self.solver.solve_with(&assumptions) {
+            SolveResult::Sat => Err((self.model(&self.state), self.model(&self.inputs))),
+            SolveResult::Unsat => {
+                let failed = self.solver.failed_assumptions();
+                Ok(cube
+                    .iter()
+                    .copied()
+                    .filter(|&l| failed.contains(&self.next_lit(l)))
+                    .collect())
+            }
+        };
+        self.solver.add_clause(&[-activation]);
+        result
+    }
+
+    /// Shrink the cube while it stays inductive relative to the previous frame.
+    fn generalize(&mut self, mut cube: Cube, frame: usize) -> Cube {
+        let mut i = 0;
+        while i < cube.len() {
+            let mut candidate = cube.clone();
+            candidate.remove(i);
+            if self.intersects_init(&candidate) {
+                i += 1;
+                continue;
+            }
+            match self.relative_inductive(&candidate, frame) {
+                Ok(core) => cube = self.exclude_init(core, &candidate),
+                Err(_) => i += 1,
+            }
+        }
+        cube
+    }
+
+    fn add_blocked(&mut self, cube: Cube, frame: usize) {
+        let mut clause = vec![-self.activations[frame]];
+        clause.extend(cube.iter().map(|&l| -self.state_lit(l)));
+        self.solver.add_clause(&clause);
+        self.frames[frame].push(cube);
+    }
+
+    /// Block the bad state in the last frame, or return a counterexample.
+    fn block(&mut self, state: Vec<bool>, inputs: Vec<bool>) -> Option<Witness> {
+        let depth = self.depth();
+        let mut obligations = vec![Obligation {
+            frame: depth,
+            state,
+            inputs,
+            next: None,
+        }];
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((depth, 0)));
+
+        while let Some(Reverse((frame, index))) = queue.pop() {
+            let cube = full_cube(&obligations[index].state);
+            if frame == 0 || self.intersects_init(&cube) {
+                return Some(self.counterexample(&obligations, index));
+            }
+            if self.is_blocked(&cube, frame) {
+                continue;
+            }
+            match self.relative_inductive(&cube, frame) {
+                Err((state, inputs)) => {
+                    obligations.push(Obligation {
+                        frame: frame - 1,
+                        state,
+                        inputs,
+                        next: Some(index),
+                    });
+                    queue.push(Reverse((frame - 1, obligations.len() - 1)));
+                    queue.push(Reverse((frame, index)));
+                }
+                Ok(core) => {
+                    let core = self.exclude_init(core, &cube);
+                    let blocked = self.generalize(core, frame);
+                    // Push the cube to the highest frame it is inductive in:
+                    let mut target = frame;
+                    while target < depth && self.relative_inductive(&blocked, target + 1).is_ok() {
+                        target += 1;
+                    }
+                    self.add_blocked(blocked, target);
+                    if target < depth {
+                        obligations[index].frame = target + 1;
+                        queue.push(Reverse((target + 1, index)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn counterexample(&self, obligations: &[Obligation], start: usize) -> Witness {
+        let init = obligations[start].state.clone();
+        let mut inputs = Vec::new();
+        let mut current = Some(start);
+        while let Some(index) = current {
+            inputs.push(obligations[index].inputs.clone());
+            current = obligations[index].next;
+        }
+
+        // Find the violated property in the last cycle:
+        let mut sim = SequentialSimulator::with_state(self.aig, init.clone());
+        for input in &inputs {
+            sim.step(input);
+        }
+        let property = self
+            .aig
+            .safety_properties()
+            .iter()
+            .position(|&p| sim.value(p))
+            .expect("Counterexample must violate some property");
+        Witness::counterexample(Property::Bad(property), init, inputs)
+    }
+
+    /// Push blocked cubes forward. Returns the invariant when two frames become equal.
+    fn propagate(&mut self) -> Option<Invariant> {
+        let depth = self.depth();
+        for frame in 1..depth {
+            let cubes = std::mem::take(&mut self.frames[frame]);
+            for cube in cubes {
+                let mut assumptions = self.frame_assumptions(frame);
+                assumptions.extend(cube.iter().map(|&l| self.next_lit(l)));
+                if self.solver.solve_with(&assumptions) == SolveResult::Unsat {
+                    self.add_blocked(cube, frame + 1);
+                } else {
+                    self.frames[frame].push(cube);
+                }
+            }
+            if self.frames[frame].is_empty() {
+                log::debug!("PDR: frames {} and {} are equal", frame, frame + 1);
+                let clauses = self.frames[frame + 1..]
+                    .iter()
+                    .flatten()
+                    .map(|cube| {
+                        cube.iter()
+                            .map(|&(index, value)| Ref::new(self.aig.latches()[index], value))
+                            .collect()
+                    })
+                    .collect();
+                return Some(Invariant { clauses });
+            }
+        }
+        None
+    }
+
+    fn run(&mut self, max_frames: usize) -> PdrResult {
+        // Violation in an initial state:
+        if self.solver.solve_with(&[self.activations[0], self.bad]) == SolveResult::Sat {
+            let init = self.model(&self.state);
+            let inputs = self.model(&self.inputs);
+            let obligations = [Obligation {
+                frame: 0,
+                state: init,
+                inputs,
+                next: None,
+            }];
+            return PdrResult::Counterexample(self.counterexample(&obligations, 0));
+        }
+
+        self.add_frame();
+        loop {
+            let depth = self.depth();
+            loop {
+                let mut assumptions = self.frame_assumptions(depth);
+                assumptions.push(self.bad);
+                if self.solver.solve_with(&assumptions) == SolveResult::Unsat {
+                    break;
+                }
+                let state = self.model(&self.state);
+                let inputs = self.model(&self.inputs);
+                if let Some(witness) = self.block(state, inputs) {
+                    return PdrResult::Counterexample(witness);
+                }
+            }
+            log::debug!("PDR: frame {} is safe", depth);
+
+            if depth >= max_frames {
+                return PdrResult::Unknown { frames: depth };
+            }
+            self.add_frame();
+            if let Some(invariant) = self.propagate() {
+                return PdrResult::Proved(invariant);
+            }
+        }
+    }
+}
+
+impl Aig {
+    /// Check the safety properties (see [`Aig::safety_properties`]) with IC3/PDR,
+    /// using the built-in SAT solver and up to `max_frames` frames.
+    pub fn pdr(&self, max_frames: usize) -> PdrResult {
+        self.pdr_with(max_frames, &mut Solver::new())
+    }
+
+    /// Check the safety properties with IC3/PDR, using the given SAT solver.
+    pub fn pdr_with<S: SatSolver + ?Sized>(&self, max_frames: usize, solver: &mut S) -> PdrResult {
+        Pdr::new(self, solver).run(max_frames)
+    }
+
+    /// Check that the invariant contains the initial states, is inductive
+    /// (under the invariant constraints) and excludes all bad states.
+    ///
+    /// Invariants with literals of nodes other than latches are rejected.
+    pub fn check_invariant(&self, invariant: &Invariant) -> bool {
+        let position = |id: u32| self.latches().iter().position(|&x| x == id);
+        let Some(clauses) = invariant
+            .clauses
+            .iter()
+            .map(|clause| {
+                clause
+                    .iter()
+                    .map(|&r| Some((position(r.id())?, !r.is_negated())))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            return false;
+        };
+        let mut solver = Solver::new();
+        let pdr = Pdr::new(self, &mut solver);
+
+        // Initial states:
+        for clause in &clauses {
+            let mut assumptions = vec![pdr.activations[0]];
+            assumptions.extend(clause.iter().map(|&l| -pdr.state_lit(l)));
+            if pdr.solver.solve_with(&assumptions) == SolveResult::Sat {
+                return false;
+            }
+        }
+
+        // Invariant in the current state:
+        let activation = pdr.solver.new_var();
+        for clause in &clauses {
+            let mut lits = vec![-activation];
+            lits.extend(clause.iter().map(|&l| pdr.state_lit(l)));
+            pdr.solver.add_clause(&lits);
+        }
+        if pdr.solver.solve_with(&[activation, pdr.bad]) == SolveResult::Sat {
+            return false;
+        }
+        clauses.iter().all(|clause| {
+            let mut assumptions = vec![activation];
+            assumptions.extend(clause.iter().map(|&l| -pdr.next_lit(l)));
+            pdr.solver.solve_with(&assumptions) == SolveResult::Unsat
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bmc::BmcResult;
+    use crate::node::LatchInit;
+    use crate::sim::SplitMix64;
+    use crate::test_utils::RandomAig;
+
+    /// 3-bit counter, starting from zero, which wraps around after reaching the limit.
+    /// It is bad when the counter reaches 7.
+    fn counter(limit: u32) -> Aig {
+        let mut builder = AigBuilder::new();
+        let bits = (0..3)
+            .map(|_| builder.latch(LatchInit::Zero))
+            .collect::<Vec<_>>();
+        let (increment, _) = builder.add(&bits, &[Ref::TRUE, Ref::FALSE, Ref::FALSE], Ref::FALSE);
+        let limit_bits = (0..3)
+            .map(|i| {
+                if limit >> i & 1 != 0 {
+                    Ref::TRUE
+                } else {
+                    Ref::FALSE
+                }
+            })
+            .collect::<Vec<_>>();
+        let wrap = builder.eq(&bits, &limit_bits);
+        let next = builder.mux_word(wrap, &[Ref::FALSE; 3], &increment);
+        for (&bit, &next) in bits.iter().zip(&next) {
+            builder.set_latch_next(bit, next);
+        }
+        let bad = builder.and_many(&bits);
+        builder.bad(bad);
+        builder.build()
+    }
+
+    #[test]
+    fn test_pdr_proved() {
+        let aig = counter(5);
+        match aig.pdr(20) {
+            PdrResult::Proved(invariant) => {
+                assert!(aig.check_invariant(&invariant));
+
+                // The exported invariant holds in all reachable states and excludes 7:
+                let certificate = invariant.to_aig(&aig);
+                assert_eq!(certificate.inputs().len(), 3);
+                for value in 0..8u32 {
+                    let inputs = (0..3).map(|i| value >> i & 1 != 0).collect::<Vec<_>>();
+                    let mut sim = SequentialSimulator::with_state(&certificate, vec![]);
+                    sim.eval(&inputs);
+                    let holds = sim.value(certificate.outputs()[0]);
+                    if value <= 5 {
+                        assert!(holds, "Invariant excludes reachable state {}", value);
+                    }
+                    if value == 7 {
+                        assert!(!holds);
+                    }
+                }
+
+                let cnf = invariant.to_cnf(&aig);
+                assert_eq!(cnf.num_vars, 3);
+                assert_eq!(cnf.clauses.len(), invariant.clauses.len());
+            }
+            result => panic!("Expected a proof, got {:?}", result),
+        }
+
+        // A wrong invariant is rejected:
+        let wrong = Invariant {
+            clauses: vec![vec![-Ref::positive(aig.latches()[0])]],
+        };
+        assert!(!aig.check_invariant(&wrong));
+
+        // So is an invariant over a gate:
+        let gate = Invariant {
+            clauses: vec![vec![Ref::positive(aig.topological_order()[0])]],
+        };
+        assert!(!aig.check_invariant(&gate));
+    }
+
+    #[test]
+    fn test_pdr_counterexample() {
+        let aig = counter(7);
+        match aig.pdr(20) {
+            PdrResult::Counterexample(witness) => {
+                assert_eq!(aig.check_witness(&witness).unwrap(), 7);
+            }
+            result => panic!("Expected a counterexample, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_pdr_toggle_re() {
+        // Inputs of toggle-re are [enable, reset], output 'Q' serves as the bad property.
+        let mut aig = Aig::from_file("data/examples/toggle-re.aag").unwrap();
+        aig.add_bad(aig.outputs()[0]);
+        match aig.pdr(10) {
+            PdrResult::Counterexample(witness) => {
+                assert_eq!(aig.check_witness(&witness).unwrap(), 1);
+            }
+            result => panic!("Expected a counterexample, got {:?}", result),
+        }
+
+        aig.add_constraint(-Ref::positive(aig.inputs()[0]));
+        match aig.pdr(10) {
+            PdrResult::Proved(invariant) => assert!(aig.check_invariant(&invariant)),
+            result => panic!("Expected a proof, got {:?}", result),
+        }
+    }
+
+    /// Random sequential circuit with 2 inputs and 4 latches (16 states).
+    #[test]
+    fn test_pdr_vs_bmc() {
+        for seed in 0..100 {
+            let mut builder = AigBuilder::new();
+            let nodes = RandomAig {
+                inputs: 2,
+                latches: 4,
+                gates: 12,
+                mixed: true,
+                ..RandomAig::default()
+            }
+            .build(&mut builder, &mut SplitMix64::new(seed));
+            // The first latch is usually reset to zero, so the initial states are rarely bad:
+            let bad = builder.and(nodes[nodes.len() - 1], nodes[2]);
+            builder.bad(bad);
+            let aig = builder.build();
+            // Every reachable state is reached within 16 steps:
+            let bmc = aig.bmc(17);
+            match aig.pdr(50) {
+                PdrResult::Proved(invariant) => {
+                    assert_eq!(bmc, BmcResult::Bounded(17), "seed {}", seed);
+                    assert!(aig.check_invariant(&invariant), "seed {}", seed);
+                }
+                PdrResult::Counterexample(witness) => {
+                    assert!(matches!(bmc, BmcResult::Counterexample(_)), "seed {}", seed);
+                    assert!(aig.check_witness(&witness).is_ok(), "seed {}", seed);
+                }
+                result => panic!("Unexpected {:?} for seed {}", result, seed),
+            }
+        }
+    }
+}
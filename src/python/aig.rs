@@ -4,6 +4,7 @@ use std::fmt::{Display, Formatter};
 use pyo3::prelude::*;
 
 use crate::aig::Aig;
+use crate::cnf::CnfOptions;
 
 #[pyclass(name = "Aig", str)]
 pub struct PyAig {
@@ -38,6 +39,26 @@ impl PyAig {
         self.inner.outputs().iter().map(|r| r.get()).collect()
     }
 
+    pub fn bad(&self) -> Vec<i32> {
+        self.inner.bad().iter().map(|r| r.get()).collect()
+    }
+
+    pub fn constraints(&self) -> Vec<i32> {
+        self.inner.constraints().iter().map(|r| r.get()).collect()
+    }
+
+    pub fn justice(&self) -> Vec<Vec<i32>> {
+        self.inner
+            .justice()
+            .iter()
+            .map(|lits| lits.iter().map(|r| r.get()).collect())
+            .collect()
+    }
+
+    pub fn fairness(&self) -> Vec<i32> {
+        self.inner.fairness().iter().map(|r| r.get()).collect()
+    }
+
     pub fn gates(&self) -> Vec<u32> {
         self.inner.and_gates().map(|gate| gate.id).collect()
     }
@@ -70,4 +91,15 @@ impl PyAig {
     pub fn to_cnf(&self) -> (Vec<Vec<i32>>, HashMap<u32, u32>) {
         self.inner.to_cnf()
     }
+
+    #[pyo3(signature = (asserted = Vec::new(), polarity = false, compact = false))]
+    pub fn to_dimacs(
+        &self,
+        asserted: Vec<usize>,
+        polarity: bool,
+        compact: bool,
+    ) -> eyre::Result<String> {
+        let options = CnfOptions { polarity, compact };
+        Ok(self.inner.to_dimacs(options, &asserted)?.to_string())
+    }
 }
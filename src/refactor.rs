@@ -0,0 +1,421 @@
+use crate::aig::Aig;
+use crate::network::Network;
+use crate::npn::StructureBuilder;
+use crate::reference::Ref;
+use crate::rewrite::RewriteStats;
+use crate::window::{
+    care_set, cofactors, cone, depends_on, num_words, reconvergence_cut, simulate, var_truth,
+};
+
+/// Maximum number of leaves of the collapsed cuts.
+pub const MAX_REFACTOR_LEAVES: usize = 12;
+
+/// Options of [`Aig::refactor`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RefactorOptions {
+    /// Maximum number of leaves of the collapsed cuts, at most [`MAX_REFACTOR_LEAVES`].
+    pub max_leaves: usize,
+    /// Use observability don't-cares when resynthesising the cuts.
+    pub dont_cares: bool,
+}
+
+impl Default for RefactorOptions {
+    fn default() -> Self {
+        Self {
+            max_leaves: 10,
+            dont_cares: false,
+        }
+    }
+}
+
+/// Sum of products: each cube is a set of literals, with bit `2 * i` for variable `i`
+/// and bit `2 * i + 1` for its complement.
+type Cover = Vec<u32>;
+
+fn literals(cover: &Cover) -> u32 {
+    cover.iter().map(|cube| cube.count_ones()).sum()
+}
+
+/// Irredundant sum of products of a function between `lower` and `upper`, over the first
+/// `var` of the `vars` variables (Minato–Morreale). Cubes are added to the cover together
+/// with the literals in `cube`, and the function of the added cubes is returned.
+fn isop(
+    lower: &[u64],
+    upper: &[u64],
+    vars: usize,
+    var: usize,
+    cube: u32,
+    cover: &mut Cover,
+) -> Vec<u64> {
+    if lower.iter().all(|&w| w == 0) {
+        return vec![0; lower.len()];
+    }
+    if upper.iter().all(|&w| w == !0) {
+        cover.push(cube);
+        return vec![!0; lower.len()];
+    }
+    let var = (0..var)
+        .rev()
+        .find(|&v| depends_on(lower, v) || depends_on(upper, v))
+        .expect("Non-constant function must depend on a variable");
+    let and_not = |a: &[u64], b: &[u64]| a.iter().zip(b).map(|(x, y)| x & !y).collect::<Vec<_>>();
+
+    let (lower0, lower1) = cofactors(lower, var);
+    let (upper0, upper1) = cofactors(upper, var);
+    let negative = isop(
+        &and_not(&lower0, &upper1),
+        &upper0,
+        vars,
+        var,
+        cube | 2 << (2 * var),
+        cover,
+    );
+    let positive = isop(
+        &and_not(&lower1, &upper0),
+        &upper1,
+        vars,
+        var,
+        cube | 1 << (2 * var),
+        cover,
+    );
+    let rest_lower = and_not(&lower0, &negative)
+        .iter()
+        .zip(and_not(&lower1, &positive))
+        .map(|(x, y)| x | y)
+        .collect::<Vec<_>>();
+    let rest_upper = upper0
+        .iter()
+        .zip(&upper1)
+        .map(|(x, y)| x & y)
+        .collect::<Vec<_>>();
+    let rest = isop(&rest_lower, &rest_upper, vars, var, cube, cover);
+
+    let x = var_truth(var, vars);
+    (0..lower.len())
+        .map(|w| (negative[w] & !x[w]) | (positive[w] & x[w]) | rest[w])
+        .collect()
+}
+
+/// Factored form.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Expr {
+    Const(bool),
+    /// Literal, numbered as in [`Cover`].
+    Literal(u32),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+fn cube_expr(cube: u32) -> Expr {
+    let literals = (0..32)
+        .filter(|&l| cube & (1 << l) != 0)
+        .map(Expr::Literal)
+        .collect::<Vec<_>>();
+    match literals.len() {
+        0 => Expr::Const(true),
+        1 => literals.into_iter().next().unwrap(),
+        _ => Expr::And(literals),
+    }
+}
+
+/// Algebraic factoring of the cover: extract the cube common to all cubes, then divide
+/// by the most frequent literal, recursively.
+fn factor(cover: &[u32]) -> Expr {
+    if cover.is_empty() {
+        return Expr::Const(false);
+    }
+    if cover.contains(&0) {
+        return Expr::Const(true);
+    }
+    if cover.len() == 1 {
+        return cube_expr(cover[0]);
+    }
+
+    let common = cover.iter().fold(!0, |acc, &cube| acc & cube);
+    if common != 0 {
+        let rest = cover.iter().map(|&cube| cube & !common).collect::<Vec<_>>();
+        return Expr::And(vec![cube_expr(common), factor(&rest)]);
+    }
+
+    let (count, literal) = (0..32)
+        .map(|l| {
+            (
+                cover.iter().filter(|&&cube| cube & (1 << l) != 0).count(),
+                l,
+            )
+        })
+        .max_by_key(|&(count, l)| (count, std::cmp::Reverse(l)))
+        .unwrap();
+    if count < 2 {
+        return Expr::Or(cover.iter().map(|&cube| cube_expr(cube)).collect());
+    }
+    let bit = 1 << literal;
+    let quotient = cover
+        .iter()
+        .filter(|&&cube| cube & bit != 0)
+        .map(|&cube| cube & !bit)
+        .collect::<Vec<_>>();
+    let remainder = cover
+        .iter()
+        .copied()
+        .filter(|&cube| cube & bit == 0)
+        .collect::<Vec<_>>();
+    Expr::Or(vec![
+        Expr::And(vec![Expr::Literal(literal), factor(&quotient)]),
+        factor(&remainder),
+    ])
+}
+
+/// AND of the arguments as a balanced tree.
+fn and_tree(builder: &mut StructureBuilder, mut args: Vec<Ref>) -> Ref {
+    if args.is_empty() {
+        return Ref::TRUE;
+    }
+    while args.len() > 1 {
+        args = args
+            .chunks(2)
+            .map(|pair| match *pair {
+                [a, b] => builder.and(a, b),
+                [a] => a,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    args[0]
+}
+
+fn build(builder: &mut StructureBuilder, expr: &Expr) -> Ref {
+    match expr {
+        Expr::Const(false) => Ref::FALSE,
+        Expr::Const(true) => Ref::TRUE,
+        &Expr::Literal(l) => {
+            let input = builder.input(l as usize / 2);
+            if l % 2 == 0 {
+                input
+            } else {
+                -input
+            }
+        }
+        Expr::And(args) => {
+            let args = args.iter().map(|e| build(builder, e)).collect();
+            and_tree(builder, args)
+        }
+        Expr::Or(args) => {
+            let args = args.iter().map(|e| -build(builder, e)).collect();
+            -and_tree(builder, args)
+        }
+    }
+}
+
+/// Try to replace the cone of the gate with the factored form of its function.
+/// Returns whether it succeeded.
+fn refactor_node(network: &mut Network, id: u32, options: &RefactorOptions) -> bool {
+    let leaves = reconvergence_cut(network, id, options.max_leaves);
+    let cone = cone(network, id, &leaves);
+    let vars = leaves.len();
+    let values = simulate(network, &leaves, vars, &cone);
+    let function = &values[&id];
+    let care = options
+        .dont_cares
+        .then(|| care_set(network, id, &leaves, &cone))
+        .flatten()
+        .unwrap_or_else(|| vec![!0; num_words(vars)]);
+
+    // Covers of the function and of its complement, within the don't-cares:
+    let lower = function
+        .iter()
+        .zip(&care)
+        .map(|(f, c)| f & c)
+        .collect::<Vec<_>>();
+    let upper = function
+        .iter()
+        .zip(&care)
+        .map(|(f, c)| f | !c)
+        .collect::<Vec<_>>();
+    let not = |t: &[u64]| t.iter().map(|w| !w).collect::<Vec<_>>();
+    let mut positive = Cover::new();
+    isop(&lower, &upper, vars, vars, 0, &mut positive);
+    let mut negative = Cover::new();
+    isop(&not(&upper), &not(&lower), vars, vars, 0, &mut negative);
+    let (cover, negated) = if literals(&negative) < literals(&positive) {
+        (negative, true)
+    } else {
+        (positive, false)
+    };
+
+    let mut builder = StructureBuilder::new(vars);
+    let output = build(&mut builder, &factor(&cover));
+    let structure = builder.finish(Ref::from_raw(output.raw() ^ negated as u32));
+    let mut inputs = vec![Ref::FALSE];
+    inputs.extend(leaves.iter().map(|&leaf| Ref::positive(leaf)));
+
+    let saved = network.deref(id, &leaves);
+    let added = network.count_added(&structure.gates, &inputs, id, saved);
+    network.reref(id, &leaves);
+    if added.is_none() {
+        return false;
+    }
+    network.substitute(id, &structure.gates, structure.output, &inputs);
+    true
+}
+
+impl Aig {
+    /// Refactoring: collapse large cuts of the AND gates into truth tables, and replace
+    /// them with their factored forms when this reduces the number of gates.
+    ///
+    /// Gates are visited in topological order. The cut of each gate is grown from its
+    /// fanins so that it covers reconvergent paths (see [`RefactorOptions::max_leaves`]).
+    /// The function of the gate over the cut is turned into an irredundant sum of products
+    /// (of the function or of its complement, whichever has fewer literals), which is
+    /// factored algebraically. As in [`Aig::rewrite`], the gain accounts for the gates
+    /// shared with the rest of the AIG.
+    ///
+    /// With [`RefactorOptions::dont_cares`], the sum of products only has to agree with
+    /// the function where the gate is observable, as computed by simulating a window of
+    /// its transitive fanout.
+    ///
+    /// Dangling gates are removed (see [`Aig::remove_dangling`]).
+    pub fn refactor(&self, options: RefactorOptions) -> (Aig, RewriteStats) {
+        assert!(
+            (1..=MAX_REFACTOR_LEAVES).contains(&options.max_leaves),
+            "Cut size must be between 1 and {}",
+            MAX_REFACTOR_LEAVES
+        );
+        let mut network = Network::new(self);
+        let mut refactored = 0;
+        for &id in self.topological_order() {
+            if network.is_dead(id) || network.refs(id) == 0 {
+                continue;
+            }
+            if refactor_node(&mut network, id, &options) {
+                refactored += 1;
+            }
+        }
+        network.finish(self, "Refactoring", refactored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builder::AigBuilder;
+    use crate::cec::Pairing;
+    use crate::sim::SplitMix64;
+    use crate::test_utils::RandomAig;
+
+    #[test]
+    fn test_isop() {
+        // Majority of three, with a don't-care on the minterm 111.
+        let x = (0..3).map(|v| var_truth(v, 3)[0]).collect::<Vec<_>>();
+        let maj = (x[0] & x[1]) | (x[0] & x[2]) | (x[1] & x[2]);
+        let mut cover = Cover::new();
+        let result = isop(&[maj], &[maj], 3, 3, 0, &mut cover);
+        assert_eq!(result, vec![maj]);
+        assert_eq!(cover.len(), 3);
+        assert_eq!(literals(&cover), 6);
+
+        let dc = x[0] & x[1] & x[2];
+        let mut cover = Cover::new();
+        let result = isop(&[maj & !dc], &[maj], 3, 3, 0, &mut cover);
+        assert_eq!(result[0] & !dc, maj & !dc);
+        assert_eq!(cover.len(), 3);
+    }
+
+    #[test]
+    fn test_factor() {
+        // ab + ac + ad = a(b + c + d)
+        let (a, b, c, d) = (1, 4, 16, 64);
+        let expr = factor(&[a | b, a | c, a | d]);
+        assert_eq!(
+            expr,
+            Expr::And(vec![
+                Expr::Literal(0),
+                Expr::Or(vec![Expr::Literal(2), Expr::Literal(4), Expr::Literal(6)]),
+            ])
+        );
+        // ab + ac + d = a(b + c) + d
+        let expr = factor(&[a | b, a | c, d]);
+        assert_eq!(
+            expr,
+            Expr::Or(vec![
+                Expr::And(vec![
+                    Expr::Literal(0),
+                    Expr::Or(vec![Expr::Literal(2), Expr::Literal(4)]),
+                ]),
+                Expr::Literal(6),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_refactor_sop() {
+        // abc + abd + abe, built as a sum of products.
+        let mut builder = AigBuilder::new();
+        let x = builder.inputs(5);
+        let products = (2..5)
+            .map(|i| builder.and_many(&[x[0], x[1], x[i]]))
+            .collect::<Vec<_>>();
+        let o = builder.or_many(&products);
+        builder.output(o);
+        let aig = builder.build();
+
+        let (refactored, stats) = aig.refactor(RefactorOptions::default());
+        assert_eq!(stats.gates_before, aig.and_gates().count());
+        assert_eq!(stats.gates_after, 4);
+        assert!(stats.replaced > 0);
+        let result = aig
+            .check_equivalence(&refactored, Pairing::Position)
+            .unwrap();
+        assert!(result.is_equivalent());
+    }
+
+    #[test]
+    fn test_refactor_dont_cares() {
+        // o = (a & b) | a: with don't-cares, the gate a & b is unobservable when a is true.
+        let mut builder = AigBuilder::new();
+        let [a, b] = builder.inputs(2).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let o = builder.or(ab, a);
+        builder.output(o);
+        builder.output(-o);
+        let aig = builder.build();
+
+        let options = RefactorOptions {
+            dont_cares: true,
+            ..RefactorOptions::default()
+        };
+        let (refactored, stats) = aig.refactor(options);
+        assert_eq!(stats.gates_after, 0);
+        assert_eq!(refactored.outputs(), &[a, -a]);
+    }
+
+    #[test]
+    fn test_refactor_random() {
+        let mut rng = SplitMix64::new(11);
+        for dont_cares in [false, true] {
+            for _ in 0..20 {
+                let aig = RandomAig {
+                    inputs: 8,
+                    gates: 80,
+                    window: 10,
+                    outputs: 4,
+                    stride: 7,
+                    ..RandomAig::default()
+                }
+                .generate(&mut rng);
+
+                let options = RefactorOptions {
+                    dont_cares,
+                    ..RefactorOptions::default()
+                };
+                let (refactored, stats) = aig.refactor(options);
+                assert!(stats.gates_after <= stats.gates_before);
+                let result = aig
+                    .check_equivalence(&refactored, Pairing::Position)
+                    .unwrap();
+                assert!(result.is_equivalent());
+            }
+        }
+    }
+}
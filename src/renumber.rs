@@ -0,0 +1,106 @@
+use crate::aig::Aig;
+use crate::reference::Ref;
+
+impl Aig {
+    /// Whether the AIG is in the canonical AIGER order: inputs are numbered `1..=I`,
+    /// latches `I+1..=I+L`, and AND gates `I+L+1..=M`, with each gate defined
+    /// after its arguments.
+    pub fn is_canonical(&self) -> bool {
+        let i = self.inputs().len() as u32;
+        let l = self.latches().len() as u32;
+        let m = self.max_id();
+        m as usize == self.inputs().len() + self.latches().len() + self.and_gates().count()
+            && (1..).zip(self.inputs()).all(|(k, &id)| id == k)
+            && (i + 1..).zip(self.latches()).all(|(k, &id)| id == k)
+            && (i + l + 1..=m)
+                .all(|id| self.is_gate(id) && self.gate(id).args.iter().all(|arg| arg.id() < id))
+    }
+
+    /// Renumber the nodes into the canonical AIGER order (see [`Aig::is_canonical`]):
+    /// inputs, then latches, then AND gates in topological order, without gaps.
+    ///
+    /// All nodes are kept, including dangling ones. Returns the renumbered AIG and the
+    /// mapping from the old ids to the new (positive) references, with [`Ref::FALSE`]
+    /// for the constant and unused ids.
+    pub fn renumber(&self) -> (Aig, Vec<Ref>) {
+        let mask = (0..=self.max_id())
+            .map(|id| id != 0 && self.contains(id))
+            .collect::<Vec<_>>();
+        let (roots, kept) = self.roots();
+        self.compact(roots, &kept, &mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::aiger::SymbolType;
+    use crate::node::LatchInit;
+
+    #[test]
+    fn test_renumber() {
+        // Gaps, inputs out of order, and gates defined before their arguments.
+        let mut aig = Aig::default();
+        aig.add_input(9);
+        aig.add_input(3);
+        aig.add_latch(5, Ref::negative(12), LatchInit::One);
+        aig.add_and_gate(7, [Ref::positive(12), Ref::negative(9)]);
+        aig.add_and_gate(12, [Ref::positive(3), Ref::positive(5)]);
+        aig.add_output(Ref::negative(7));
+        aig.add_bad(Ref::positive(12));
+        aig.set_symbol(SymbolType::Input, 1, "x".to_string());
+        aig.set_symbol(SymbolType::Bad, 0, "p".to_string());
+        assert!(!aig.is_canonical());
+
+        let (canonical, map) = aig.renumber();
+        assert!(canonical.is_canonical());
+        assert_eq!(canonical.inputs(), &[1, 2]);
+        assert_eq!(canonical.latches(), &[3]);
+        assert_eq!(map[9], Ref::positive(1));
+        assert_eq!(map[3], Ref::positive(2));
+        assert_eq!(map[5], Ref::positive(3));
+        assert_eq!(map[12], Ref::positive(4));
+        assert_eq!(map[7], Ref::positive(5));
+        assert_eq!(map[1], Ref::FALSE);
+        assert_eq!(canonical.gate(5).args, [Ref::positive(4), Ref::negative(1)]);
+        assert_eq!(canonical.latch(3).next, Ref::negative(4));
+        assert_eq!(canonical.latch(3).init, LatchInit::One);
+        assert_eq!(canonical.outputs(), &[Ref::negative(5)]);
+        assert_eq!(canonical.bad(), &[Ref::positive(4)]);
+        assert_eq!(canonical.input_name(2), Some("x"));
+        assert_eq!(canonical.symbols().name(SymbolType::Bad, 0), Some("p"));
+
+        // Renumbering a canonical AIG is the identity:
+        let (again, map) = canonical.renumber();
+        assert_eq!(
+            again.write_to_string().unwrap(),
+            canonical.write_to_string().unwrap()
+        );
+        assert!((1..=5).all(|id| map[id as usize] == Ref::positive(id)));
+    }
+
+    #[test]
+    fn test_write_binary_renumbered() {
+        // Not in canonical order, so it can only be written in the binary format after renumbering.
+        let mut aig = Aig::default();
+        aig.add_input(2);
+        aig.add_input(1);
+        aig.add_and_gate(5, [Ref::positive(1), Ref::negative(2)]);
+        aig.add_output(Ref::positive(5));
+        assert!(aig.write_binary_to_vec().is_err());
+
+        let bytes = aig.renumber().0.write_binary_to_vec().unwrap();
+        let parsed = Aig::parse(bytes.as_slice()).unwrap();
+        let expected = indoc! {"
+            aag 3 2 0 1 1
+            2
+            4
+            6
+            6 4 3
+        "};
+        assert_eq!(parsed.write_to_string().unwrap(), expected);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::aig::Aig;
+use crate::network::Network;
+use crate::reference::Ref;
+use crate::rewrite::RewriteStats;
+use crate::window::{care_set, cone, num_words, reconvergence_cut, simulate};
+
+/// Maximum number of leaves of the windows.
+pub const MAX_RESUB_LEAVES: usize = 12;
+
+/// Maximum number of candidate literals combined by the 2-resubstitutions.
+const MAX_PAIR_CANDIDATES: usize = 24;
+
+/// Options of [`Aig::resub`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ResubOptions {
+    /// Maximum number of leaves of the windows, at most [`MAX_RESUB_LEAVES`].
+    pub max_leaves: usize,
+    /// Maximum number of divisors per node.
+    pub max_divisors: usize,
+    /// Use observability don't-cares when comparing the node with the divisors.
+    pub dont_cares: bool,
+}
+
+impl Default for ResubOptions {
+    fn default() -> Self {
+        Self {
+            max_leaves: 8,
+            max_divisors: 100,
+            dont_cares: false,
+        }
+    }
+}
+
+/// Replacement of a node: `gates` over the `inputs`, as in [`Network::substitute`].
+struct Candidate {
+    gates: Vec<[Ref; 2]>,
+    output: Ref,
+    inputs: Vec<Ref>,
+}
+
+impl Candidate {
+    fn new(args: &[Ref], gates: &[[Ref; 2]], output: Ref) -> Self {
+        let mut inputs = vec![Ref::FALSE];
+        inputs.extend_from_slice(args);
+        Self {
+            gates: gates.to_vec(),
+            output,
+            inputs,
+        }
+    }
+}
+
+/// Whether `x` implies `y` on the care set.
+fn implies(care: &[u64], x: &[u64], y: &[u64]) -> bool {
+    care.iter().zip(x).zip(y).all(|((c, x), y)| c & x & !y == 0)
+}
+
+fn and(x: &[u64], y: &[u64]) -> Vec<u64> {
+    x.iter().zip(y).map(|(x, y)| x & y).collect()
+}
+
+fn or(x: &[u64], y: &[u64]) -> Vec<u64> {
+    x.iter().zip(y).map(|(x, y)| x | y).collect()
+}
+
+/// Find a replacement of the root (whose cone is dereferenced) using the divisors,
+/// adding fewer than `saved` gates. Simpler replacements are tried first.
+fn find_replacement(
+    network: &Network,
+    root: u32,
+    leaves: &[u32],
+    cone: &[u32],
+    care: &[u64],
+    saved: usize,
+    max_divisors: usize,
+) -> Option<Candidate> {
+    // Divisors: the leaves, the gates of the cone outside of the fanout-free cone
+    // of the root, and the gates over the divisors (which cannot depend on the root).
+    let mut divisors = leaves.to_vec();
+    for &id in cone {
+        if id != root && network.refs(id) > 0 && divisors.len() < max_divisors {
+            divisors.push(id);
+        }
+    }
+    let mut extras = Vec::new();
+    let mut i = 0;
+    while i < divisors.len() && divisors.len() < max_divisors {
+        for f in network.fanouts(divisors[i]) {
+            let args = network.fanins(f).unwrap();
+            if f != root
+                && network.refs(f) > 0
+                && !divisors.contains(&f)
+                && args.iter().all(|r| divisors.contains(&r.id()))
+                && divisors.len() < max_divisors
+            {
+                divisors.push(f);
+                extras.push(f);
+            }
+        }
+        i += 1;
+    }
+    let nodes = cone.iter().chain(&extras).copied().collect::<Vec<_>>();
+    let values = simulate(network, leaves, leaves.len(), &nodes);
+    let function = &values[&root];
+    let complement = function.iter().map(|w| !w).collect::<Vec<_>>();
+
+    let try_candidate = |candidate: Candidate| {
+        network
+            .count_added(&candidate.gates, &candidate.inputs, root, saved)
+            .map(|_| candidate)
+    };
+
+    // Constant:
+    if implies(care, function, &values[&0]) {
+        return Some(Candidate::new(&[], &[], Ref::FALSE));
+    }
+    if implies(care, &complement, &values[&0]) {
+        return Some(Candidate::new(&[], &[], Ref::TRUE));
+    }
+
+    // Literals of the divisors, with their truth tables:
+    let literals = divisors
+        .iter()
+        .flat_map(|&d| {
+            let truth = &values[&d];
+            [
+                (Ref::positive(d), truth.clone()),
+                (-Ref::positive(d), truth.iter().map(|w| !w).collect()),
+            ]
+        })
+        .collect::<Vec<(Ref, Vec<u64>)>>();
+
+    // Inputs and gates of the candidates, as in `Candidate::new`:
+    let [x, y, z] = [1, 2, 3].map(Ref::positive);
+
+    // 0-resubstitution: an existing divisor.
+    for (r, truth) in &literals {
+        if implies(care, truth, function) && implies(care, function, truth) {
+            if let Some(candidate) = try_candidate(Candidate::new(&[*r], &[], x)) {
+                return Some(candidate);
+            }
+        }
+    }
+    if saved < 2 {
+        return None;
+    }
+
+    // Literals containing the function, and literals contained in it:
+    let upper = literals
+        .iter()
+        .filter(|(_, truth)| implies(care, function, truth))
+        .collect::<Vec<_>>();
+    let lower = literals
+        .iter()
+        .filter(|(_, truth)| implies(care, truth, function))
+        .collect::<Vec<_>>();
+
+    // 1-resubstitution: AND or OR of two divisors.
+    let g = Ref::positive(3);
+    for (i, (a, ta)) in upper.iter().enumerate() {
+        for (b, tb) in &upper[i + 1..] {
+            if implies(care, &and(ta, tb), function) {
+                let candidate = Candidate::new(&[*a, *b], &[[x, y]], g);
+                if let Some(candidate) = try_candidate(candidate) {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+    for (i, (a, ta)) in lower.iter().enumerate() {
+        for (b, tb) in &lower[i + 1..] {
+            if implies(care, function, &or(ta, tb)) {
+                let candidate = Candidate::new(&[*a, *b], &[[-x, -y]], -g);
+                if let Some(candidate) = try_candidate(candidate) {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+    if saved < 3 {
+        return None;
+    }
+
+    // 2-resubstitution: AND or OR of three divisors, or AND-OR of three divisors.
+    let (g, h) = (Ref::positive(4), Ref::positive(5));
+    let upper = &upper[..upper.len().min(MAX_PAIR_CANDIDATES)];
+    let lower = &lower[..lower.len().min(MAX_PAIR_CANDIDATES)];
+    for (i, (a, ta)) in upper.iter().enumerate() {
+        for (j, (b, tb)) in upper.iter().enumerate().skip(i + 1) {
+            let tab = and(ta, tb);
+            for (c, tc) in &upper[j + 1..] {
+                if implies(care, &and(&tab, tc), function) {
+                    let candidate = Candidate::new(&[*a, *b, *c], &[[y, z], [x, g]], h);
+                    if let Some(candidate) = try_candidate(candidate) {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+    }
+    for (i, (a, ta)) in lower.iter().enumerate() {
+        for (j, (b, tb)) in lower.iter().enumerate().skip(i + 1) {
+            let tab = or(ta, tb);
+            for (c, tc) in &lower[j + 1..] {
+                if implies(care, function, &or(&tab, tc)) {
+                    let candidate = Candidate::new(&[*a, *b, *c], &[[-y, -z], [-x, g]], -h);
+                    if let Some(candidate) = try_candidate(candidate) {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+    }
+    // f = a & (b | c), where b and c are contained in the function within a:
+    for (a, ta) in upper {
+        let inner = literals
+            .iter()
+            .filter(|(_, tb)| implies(care, &and(ta, tb), function))
+            .take(MAX_PAIR_CANDIDATES)
+            .collect::<Vec<_>>();
+        for (i, (b, tb)) in inner.iter().enumerate() {
+            for (c, tc) in &inner[i + 1..] {
+                if implies(care, function, &or(tb, tc)) {
+                    let candidate = Candidate::new(&[*a, *b, *c], &[[-y, -z], [x, -g]], h);
+                    if let Some(candidate) = try_candidate(candidate) {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+    }
+    // f = a | (b & c), where b and c contain the function outside of a:
+    for (a, ta) in lower {
+        let inner = literals
+            .iter()
+            .filter(|(_, tb)| implies(care, function, &or(ta, tb)))
+            .take(MAX_PAIR_CANDIDATES)
+            .collect::<Vec<_>>();
+        for (i, (b, tb)) in inner.iter().enumerate() {
+            for (c, tc) in &inner[i + 1..] {
+                if implies(care, &and(tb, tc), function) {
+                    let candidate = Candidate::new(&[*a, *b, *c], &[[y, z], [-x, -g]], -h);
+                    if let Some(candidate) = try_candidate(candidate) {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Try to re-express the gate using divisors. Returns whether it succeeded.
+fn resub_node(network: &mut Network, id: u32, options: &ResubOptions) -> bool {
+    let leaves = reconvergence_cut(network, id, options.max_leaves);
+    let cone = cone(network, id, &leaves);
+    let care = options
+        .dont_cares
+        .then(|| care_set(network, id, &leaves, &cone))
+        .flatten()
+        .unwrap_or_else(|| vec![!0; num_words(leaves.len())]);
+
+    let saved = network.deref(id, &leaves);
+    let candidate = find_replacement(
+        network,
+        id,
+        &leaves,
+        &cone,
+        &care,
+        saved,
+        options.max_divisors,
+    );
+    network.reref(id, &leaves);
+    let Some(candidate) = candidate else {
+        return false;
+    };
+    network.substitute(id, &candidate.gates, candidate.output, &candidate.inputs);
+    true
+}
+
+impl Aig {
+    /// Resubstitution: re-express the AND gates using existing nodes (divisors),
+    /// when this reduces the number of gates.
+    ///
+    /// Gates are visited in topological order. The divisors of a gate are the nodes
+    /// of a reconvergence-driven window (see [`ResubOptions::max_leaves`]) which do not
+    /// depend on the gate, and which are not removed with it. Their truth tables over
+    /// the window are compared with the function of the gate, trying, in order: a constant,
+    /// a divisor (0-resubstitution), the AND or OR of two divisors (1-resubstitution),
+    /// and combinations of three divisors with two gates (2-resubstitution).
+    ///
+    /// With [`ResubOptions::dont_cares`], the replacement only has to agree with the gate
+    /// where it is observable, as computed by simulating a window of its transitive fanout.
+    ///
+    /// Dangling gates are removed (see [`Aig::remove_dangling`]).
+    pub fn resub(&self, options: ResubOptions) -> (Aig, RewriteStats) {
+        assert!(
+            (1..=MAX_RESUB_LEAVES).contains(&options.max_leaves),
+            "Window size must be between 1 and {}",
+            MAX_RESUB_LEAVES
+        );
+        let mut network = Network::new(self);
+        let mut resubstituted = 0;
+        for &id in self.topological_order() {
+            if network.is_dead(id) || network.refs(id) == 0 {
+                continue;
+            }
+            if resub_node(&mut network, id, &options) {
+                resubstituted += 1;
+            }
+        }
+        network.finish(self, "Resubstitution", resubstituted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builder::AigBuilder;
+    use crate::cec::Pairing;
+    use crate::sim::SplitMix64;
+    use crate::test_utils::RandomAig;
+
+    #[test]
+    fn test_resub_divisor() {
+        // a & (b & c) is the existing (a & b) & c.
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let abc = builder.and(ab, c);
+        let bc = builder.and(b, c);
+        let abc2 = builder.and(a, bc);
+        builder.output(abc);
+        builder.output(abc2);
+        builder.output(ab);
+        let aig = builder.build();
+        assert_eq!(aig.and_gates().count(), 4);
+
+        let (resubstituted, stats) = aig.resub(ResubOptions::default());
+        assert_eq!(stats.gates_after, 2);
+        assert!(stats.replaced > 0);
+        assert_eq!(resubstituted.outputs()[0], resubstituted.outputs()[1]);
+        let result = aig
+            .check_equivalence(&resubstituted, Pairing::Position)
+            .unwrap();
+        assert!(result.is_equivalent());
+    }
+
+    #[test]
+    fn test_resub_and() {
+        // (a & b & c) | (a & b & d) is the AND of the existing a & b and c | d.
+        let mut builder = AigBuilder::new();
+        let [a, b, c, d] = builder.inputs(4).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let cd = builder.or(c, d);
+        let abc = builder.and_many(&[a, b, c]);
+        let abd = builder.and_many(&[a, d, b]);
+        let o = builder.or(abc, abd);
+        builder.output(o);
+        builder.output(ab);
+        builder.output(cd);
+        let aig = builder.build();
+
+        let (resubstituted, stats) = aig.resub(ResubOptions::default());
+        assert_eq!(stats.gates_after, 3);
+        let result = aig
+            .check_equivalence(&resubstituted, Pairing::Position)
+            .unwrap();
+        assert!(result.is_equivalent());
+    }
+
+    #[test]
+    fn test_resub_dont_cares() {
+        // o = a & ((a & b) | (~a & c)), where both products are only observable when a is
+        // true: a & b can be replaced with b, then ~a & c with false. The small windows
+        // hide the redundancy otherwise.
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let ac = builder.and(-a, c);
+        let n = builder.or(ab, ac);
+        let o = builder.and(a, n);
+        builder.output(o);
+        let aig = builder.build();
+
+        let options = ResubOptions {
+            max_leaves: 2,
+            ..ResubOptions::default()
+        };
+        let (_, stats) = aig.resub(options);
+        assert_eq!(stats.gates_after, 4);
+        let options = ResubOptions {
+            dont_cares: true,
+            ..options
+        };
+        let (resubstituted, stats) = aig.resub(options);
+        assert_eq!(stats.gates_after, 1);
+        assert_eq!(stats.replaced, 2);
+        let result = aig
+            .check_equivalence(&resubstituted, Pairing::Position)
+            .unwrap();
+        assert!(result.is_equivalent());
+    }
+
+    #[test]
+    fn test_resub_random() {
+        let mut rng = SplitMix64::new(13);
+        for dont_cares in [false, true] {
+            for _ in 0..20 {
+                let aig = RandomAig {
+                    inputs: 8,
+                    gates: 80,
+                    window: 12,
+                    outputs: 4,
+                    stride: 7,
+                    ..RandomAig::default()
+                }
+                .generate(&mut rng);
+
+                let options = ResubOptions {
+                    dont_cares,
+                    ..ResubOptions::default()
+                };
+                let (resubstituted, stats) = aig.resub(options);
+                assert!(stats.gates_after <= stats.gates_before);
+                let result = aig
+                    .check_equivalence(&resubstituted, Pairing::Position)
+                    .unwrap();
+                assert!(result.is_equivalent());
+            }
+        }
+    }
+}
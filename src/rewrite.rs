@@ -0,0 +1,240 @@
+use crate::aig::Aig;
+use crate::cut::{node_cuts, Cut};
+use crate::network::Network;
+use crate::npn::{Library, Structure};
+use crate::reference::Ref;
+
+/// Statistics of the local rewriting passes: [`Aig::rewrite`], [`Aig::refactor`]
+/// and [`Aig::resub`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RewriteStats {
+    /// AND gates in the original AIG.
+    pub gates_before: usize,
+    /// AND gates in the resulting AIG.
+    pub gates_after: usize,
+    /// Cuts or nodes replaced with a smaller structure.
+    pub replaced: usize,
+}
+
+/// Number of leaves of the rewritten cuts.
+const CUT_SIZE: usize = 4;
+/// Maximum number of non-trivial cuts per node.
+const MAX_CUTS: usize = 8;
+
+/// Compute the cuts of the gate from the cuts of its fanins.
+fn compute_cuts(network: &Network, cuts: &mut [Vec<Cut>], id: u32) {
+    let args = network.fanins(id).expect("Node must be a gate");
+    let [a, b] = args.map(|r| &cuts[r.id() as usize]);
+    cuts[id as usize] = node_cuts(id, args, a, b, CUT_SIZE, MAX_CUTS);
+}
+
+/// Try to replace a cut of the gate with a smaller structure. Returns whether it succeeded.
+fn rewrite_node(
+    network: &mut Network,
+    cuts: &mut Vec<Vec<Cut>>,
+    id: u32,
+    library: &Library,
+) -> bool {
+    let mut best: Option<(usize, Vec<Ref>, &Structure, bool)> = None;
+    for cut in &cuts[id as usize] {
+        if cut.is_trivial(id) || cut.leaves.iter().any(|&leaf| network.is_dead(leaf)) {
+            continue;
+        }
+        let (structure, transform) = library.lookup(cut.truth as u16);
+        let mut inputs = vec![Ref::FALSE];
+        for (i, &p) in transform.perm.iter().enumerate() {
+            let leaf = cut
+                .leaves
+                .get(p as usize)
+                .map_or(Ref::FALSE, |&leaf| Ref::positive(leaf));
+            inputs.push(Ref::from_raw(
+                leaf.raw() ^ ((transform.negations >> i) & 1) as u32,
+            ));
+        }
+
+        let saved = network.deref(id, &cut.leaves);
+        let added = network.count_added(&structure.gates, &inputs, id, saved);
+        network.reref(id, &cut.leaves);
+        if let Some(added) = added {
+            let gain = saved - added;
+            if best
+                .as_ref()
+                .is_none_or(|&(best_gain, ..)| gain > best_gain)
+            {
+                best = Some((gain, inputs, structure, transform.output));
+            }
+        }
+    }
+
+    let Some((_, inputs, structure, negated)) = best else {
+        return false;
+    };
+    let output = Ref::from_raw(structure.output.raw() ^ negated as u32);
+    network.substitute(id, &structure.gates, output, &inputs);
+    // Cuts of the new gates, which are created in topological order:
+    while cuts.len() < network.len() {
+        let id = cuts.len() as u32;
+        cuts.push(Vec::new());
+        if !network.is_dead(id) {
+            compute_cuts(network, cuts, id);
+        }
+    }
+    true
+}
+
+impl Aig {
+    /// DAG-aware rewriting: replace 4-input cuts of the AND gates by smaller structures
+    /// from the NPN library (see [`Library`]).
+    ///
+    /// Gates are visited in topological order. For each cut of a gate, the structure
+    /// of its NPN class is matched against the existing gates: the gain is the number of
+    /// gates removed with the cone of the gate (the gates used only inside the cut),
+    /// minus the number of gates which the structure does not share with the rest of
+    /// the AIG. The cut with the best positive gain is replaced.
+    ///
+    /// The result is deterministic, and never has more AND gates than the original.
+    /// It is not minimal in general: run the pass repeatedly (or interleaved with
+    /// [`Aig::balance`] and [`Aig::fraig`]) for further reductions.
+    ///
+    /// Dangling gates are removed (see [`Aig::remove_dangling`]).
+    pub fn rewrite(&self) -> (Aig, RewriteStats) {
+        let library = Library::get();
+        let mut network = Network::new(self);
+        let mut cuts = vec![Vec::new(); network.len()];
+        cuts[0] = vec![Cut::constant()];
+        for &id in self.inputs().iter().chain(self.latches()) {
+            cuts[id as usize] = vec![Cut::trivial(id)];
+        }
+        let mut rewrites = 0;
+        for &id in self.topological_order() {
+            if network.is_dead(id) {
+                continue;
+            }
+            compute_cuts(&network, &mut cuts, id);
+            if network.refs(id) > 0 && rewrite_node(&mut network, &mut cuts, id, library) {
+                rewrites += 1;
+            }
+        }
+
+        network.finish(self, "Rewriting", rewrites)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builder::AigBuilder;
+    use crate::cec::Pairing;
+    use crate::node::LatchInit;
+    use crate::sim::SplitMix64;
+    use crate::test_utils::RandomAig;
+
+    #[test]
+    fn test_rewrite_factoring() {
+        // (a & b) | (a & c) = a & (b | c)
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let ac = builder.and(a, c);
+        let o = builder.or(ab, ac);
+        builder.output(o);
+        let aig = builder.build();
+        assert_eq!(aig.and_gates().count(), 3);
+
+        let (rewritten, stats) = aig.rewrite();
+        assert_eq!(stats.gates_before, 3);
+        assert_eq!(stats.gates_after, 2);
+        assert_eq!(stats.replaced, 1);
+        assert_eq!(rewritten.and_gates().count(), 2);
+        let result = aig
+            .check_equivalence(&rewritten, Pairing::Position)
+            .unwrap();
+        assert!(result.is_equivalent());
+    }
+
+    #[test]
+    fn test_rewrite_xorxormiter() {
+        // The miter of two XOR implementations is constant.
+        let aig = Aig::from_file("data/examples/xorxormiter.aag").unwrap();
+        let (rewritten, stats) = aig.rewrite();
+        assert_eq!(rewritten.outputs(), &[Ref::FALSE]);
+        assert_eq!(stats.gates_after, 0);
+    }
+
+    #[test]
+    fn test_rewrite_sharing() {
+        // The gates shared with another output are not counted as saved.
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let ac = builder.and(a, c);
+        let o = builder.or(ab, ac);
+        builder.output(o);
+        builder.output(ab);
+        builder.output(ac);
+        let aig = builder.build();
+
+        let (rewritten, stats) = aig.rewrite();
+        assert_eq!(stats.replaced, 0);
+        assert_eq!(rewritten.and_gates().count(), 3);
+    }
+
+    #[test]
+    fn test_rewrite_random() {
+        let mut rng = SplitMix64::new(7);
+        for _ in 0..30 {
+            let aig = RandomAig {
+                inputs: 6,
+                gates: 50,
+                outputs: 4,
+                ..RandomAig::default()
+            }
+            .generate(&mut rng);
+
+            let (rewritten, stats) = aig.rewrite();
+            assert_eq!(stats.gates_before, aig.and_gates().count());
+            assert!(stats.gates_after <= stats.gates_before);
+            assert_eq!(stats.gates_after, rewritten.and_gates().count());
+            let result = aig
+                .check_equivalence(&rewritten, Pairing::Position)
+                .unwrap();
+            assert!(result.is_equivalent());
+
+            // Deterministic:
+            assert_eq!(
+                aig.rewrite().0.write_to_string().unwrap(),
+                rewritten.write_to_string().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_rewrite_sequential() {
+        // A latch toggling on (x & y) | (x & l), with a redundant output cone.
+        let mut builder = AigBuilder::new();
+        let [x, y] = builder.inputs(2).try_into().unwrap();
+        let l = builder.latch(LatchInit::Zero);
+        let xy = builder.and(x, y);
+        let xl = builder.and(x, l);
+        let enable = builder.or(xy, xl);
+        let next = builder.xor(l, enable);
+        builder.set_latch_next(l, next);
+        builder.output(enable);
+        let aig = builder.build();
+
+        let (rewritten, stats) = aig.rewrite();
+        assert!(stats.replaced > 0);
+        assert!(stats.gates_after < stats.gates_before);
+        assert_eq!(rewritten.latches().len(), 1);
+
+        let inputs = [[true, true], [true, false], [false, true], [true, false]]
+            .iter()
+            .map(|v| v.to_vec())
+            .collect::<Vec<_>>();
+        let expected = aig.simulate_sequential(None, &inputs).unwrap();
+        let actual = rewritten.simulate_sequential(None, &inputs).unwrap();
+        assert_eq!(actual.outputs, expected.outputs);
+        assert_eq!(actual.states, expected.states);
+    }
+}
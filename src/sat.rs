@@ -0,0 +1,903 @@
+//! SAT solver interface and a built-in CDCL solver.
+//!
+//! Literals are given in the DIMACS convention: a variable is a positive `i32`,
+//! its negation is the corresponding negative number. Variables are created
+//! implicitly when they first appear in a clause.
+
+/// Result of a SAT solver call.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SolveResult {
+    Sat,
+    Unsat,
+}
+
+impl SolveResult {
+    pub const fn is_sat(self) -> bool {
+        matches!(self, SolveResult::Sat)
+    }
+    pub const fn is_unsat(self) -> bool {
+        matches!(self, SolveResult::Unsat)
+    }
+}
+
+/// Receiver of clauses in the DIMACS convention: a SAT solver or a CNF formula.
+pub trait ClauseSink {
+    /// Create a fresh variable.
+    fn new_var(&mut self) -> i32;
+
+    /// Add a clause. Variables mentioned in the clause are created if needed.
+    fn add_clause(&mut self, lits: &[i32]);
+}
+
+/// Incremental SAT solver interface.
+///
+/// Clauses may be added between calls to [`SatSolver::solve_with`],
+/// which solves the formula under the given assumptions.
+pub trait SatSolver: ClauseSink {
+    /// The number of variables, which are numbered from 1.
+    fn num_vars(&self) -> usize;
+
+    /// Solve the formula under the assumptions, which are literals forced to be true.
+    fn solve_with(&mut self, assumptions: &[i32]) -> SolveResult;
+
+    fn solve(&mut self) -> SolveResult {
+        self.solve_with(&[])
+    }
+
+    /// Value of the literal in the model, after a satisfiable call.
+    fn value(&self, lit: i32) -> Option<bool>;
+
+    /// Subset of the assumptions sufficient for unsatisfiability, after an unsatisfiable call.
+    /// Empty when the formula is unsatisfiable by itself.
+    fn failed_assumptions(&self) -> Vec<i32>;
+}
+
+// Internal literal: `2 * var + sign`, with 0-based variables.
+type Lit = u32;
+
+const fn var(lit: Lit) -> usize {
+    (lit >> 1) as usize
+}
+
+fn from_dimacs(lit: i32) -> Lit {
+    assert!(lit != 0, "Literal must be non-zero");
+    let var = lit.unsigned_abs() - 1;
+    (var << 1) | (lit < 0) as u32
+}
+
+const fn to_dimacs(lit: Lit) -> i32 {
+    let var = (lit >> 1) as i32 + 1;
+    if lit & 1 != 0 {
+        -var
+    } else {
+        var
+    }
+}
+
+// Lifted boolean values:
+const L_TRUE: i8 = 1;
+const L_FALSE: i8 = -1;
+const L_UNDEF: i8 = 0;
+
+/// Value of the literal under the given variable assignment.
+fn lit_value(assigns: &[i8], lit: Lit) -> i8 {
+    let value = assigns[var(lit)];
+    if lit & 1 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Clause {
+    lits: Vec<Lit>,
+    learnt: bool,
+    deleted: bool,
+    activity: f64,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Watcher {
+    clause: u32,
+    /// Some other literal of the clause; if it is true, the clause need not be inspected.
+    blocker: Lit,
+}
+
+/// Binary max-heap of variables ordered by activity.
+#[derive(Debug, Default, Clone)]
+struct VarOrder {
+    heap: Vec<usize>,
+    /// Position of each variable in the heap, `usize::MAX` if absent.
+    positions: Vec<usize>,
+}
+
+impl VarOrder {
+    fn contains(&self, v: usize) -> bool {
+        self.positions[v] != usize::MAX
+    }
+
+    fn grow(&mut self, num_vars: usize) {
+        self.positions.resize(num_vars, usize::MAX);
+    }
+
+    fn insert(&mut self, v: usize, activity: &[f64]) {
+        if self.contains(v) {
+            return;
+        }
+        self.positions[v] = self.heap.len();
+        self.heap.push(v);
+        self.sift_up(self.heap.len() - 1, activity);
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let top = self.heap.swap_remove(0);
+        self.positions[top] = usize::MAX;
+        if !self.heap.is_empty() {
+            self.positions[self.heap[0]] = 0;
+            self.sift_down(0, activity);
+        }
+        Some(top)
+    }
+
+    fn update(&mut self, v: usize, activity: &[f64]) {
+        if self.contains(v) {
+            self.sift_up(self.positions[v], activity);
+        }
+    }
+
+    fn sift_up(&mut self, mut i: usize, activity: &[f64]) {
+        let v = self.heap[i];
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if activity[self.heap[parent]] >= activity[v] {
+                break;
+            }
+            self.heap[i] = self.heap[parent];
+            self.positions[self.heap[i]] = i;
+            i = parent;
+        }
+        self.heap[i] = v;
+        self.positions[v] = i;
+    }
+
+    fn sift_down(&mut self, mut i: usize, activity: &[f64]) {
+        let v = self.heap[i];
+        loop {
+            let left = 2 * i + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < self.heap.len()
+                && activity[self.heap[right]] > activity[self.heap[left]]
+            {
+                right
+            } else {
+                left
+            };
+            if activity[self.heap[child]] <= activity[v] {
+                break;
+            }
+            self.heap[i] = self.heap[child];
+            self.positions[self.heap[i]] = i;
+            i = child;
+        }
+        self.heap[i] = v;
+        self.positions[v] = i;
+    }
+}
+
+/// Conflict-driven clause learning SAT solver, in the spirit of MiniSat:
+/// two watched literals, VSIDS branching with phase saving, first-UIP learning
+/// with clause minimisation, Luby restarts and activity-based clause deletion.
+///
+/// It is meant for tests and small instances, not as a replacement for
+/// state-of-the-art solvers.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    /// False if the clauses are unsatisfiable at the top level.
+    ok: bool,
+    clauses: Vec<Clause>,
+    learnts: Vec<u32>,
+    /// Clauses watching each literal, indexed by literal.
+    watches: Vec<Vec<Watcher>>,
+    assigns: Vec<i8>,
+    level: Vec<u32>,
+    reason: Vec<Option<u32>>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    qhead: usize,
+    activity: Vec<f64>,
+    var_inc: f64,
+    cla_inc: f64,
+    order: VarOrder,
+    /// Saved phases: the sign of the last assigned literal of each variable.
+    polarity: Vec<bool>,
+    seen: Vec<bool>,
+    max_learnts: f64,
+    model: Vec<bool>,
+    /// Assumptions of the current call.
+    assumptions: Vec<Lit>,
+    /// Failed assumptions of the last unsatisfiable call.
+    failed: Vec<i32>,
+    conflicts: u64,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self {
+            ok: true,
+            clauses: Vec::new(),
+            learnts: Vec::new(),
+            watches: Vec::new(),
+            assigns: Vec::new(),
+            level: Vec::new(),
+            reason: Vec::new(),
+            trail: Vec::new(),
+            trail_lim: Vec::new(),
+            qhead: 0,
+            activity: Vec::new(),
+            var_inc: 1.0,
+            cla_inc: 1.0,
+            order: VarOrder::default(),
+            polarity: Vec::new(),
+            seen: Vec::new(),
+            max_learnts: 0.0,
+            model: Vec::new(),
+            assumptions: Vec::new(),
+            failed: Vec::new(),
+            conflicts: 0,
+        }
+    }
+
+    pub fn num_clauses(&self) -> usize {
+        self.clauses.len() - self.learnts.len()
+    }
+
+    /// The total number of conflicts encountered so far.
+    pub fn num_conflicts(&self) -> u64 {
+        self.conflicts
+    }
+
+    /// Make sure that variables `1..=n` exist.
+    pub fn reserve_vars(&mut self, n: usize) {
+        if n <= self.num_vars() {
+            return;
+        }
+        let old = self.num_vars();
+        self.watches.resize(2 * n, Vec::new());
+        self.assigns.resize(n, L_UNDEF);
+        self.level.resize(n, 0);
+        self.reason.resize(n, None);
+        self.activity.resize(n, 0.0);
+        self.polarity.resize(n, true);
+        self.seen.resize(n, false);
+        self.order.grow(n);
+        for v in old..n {
+            self.order.insert(v, &self.activity);
+        }
+    }
+
+    fn decision_level(&self) -> u32 {
+        self.trail_lim.len() as u32
+    }
+
+    fn value_lit(&self, lit: Lit) -> i8 {
+        lit_value(&self.assigns, lit)
+    }
+
+    /// Add a clause. Returns false if the solver became trivially unsatisfiable.
+    fn add_clause_inner(&mut self, lits: &[i32]) -> bool {
+        assert_eq!(self.decision_level(), 0);
+        if !self.ok {
+            return false;
+        }
+
+        let mut clause = lits.iter().map(|&l| from_dimacs(l)).collect::<Vec<_>>();
+        if let Some(max) = clause.iter().map(|&l| var(l)).max() {
+            self.reserve_vars(max + 1);
+        }
+        clause.sort_unstable();
+        clause.dedup();
+        // Tautologies and satisfied clauses are skipped, false literals are removed:
+        if clause.windows(2).any(|w| w[0] ^ 1 == w[1]) {
+            return true;
+        }
+        if clause.iter().any(|&l| self.value_lit(l) == L_TRUE) {
+            return true;
+        }
+        clause.retain(|&l| self.value_lit(l) != L_FALSE);
+
+        match clause.len() {
+            0 => {
+                self.ok = false;
+            }
+            1 => {
+                self.enqueue(clause[0], None);
+                if self.propagate().is_some() {
+                    self.ok = false;
+                }
+            }
+            _ => {
+                self.attach_clause(clause, false);
+            }
+        }
+        self.ok
+    }
+
+    fn attach_clause(&mut self, lits: Vec<Lit>, learnt: bool) -> u32 {
+        debug_assert!(lits.len() >= 2);
+        let index = self.clauses.len() as u32;
+        self.watches[lits[0] as usize].push(Watcher {
+            clause: index,
+            blocker: lits[1],
+        });
+        self.watches[lits[1] as usize].push(Watcher {
+            clause: index,
+            blocker: lits[0],
+        });
+        self.clauses.push(Clause {
+            lits,
+            learnt,
+            deleted: false,
+            activity: 0.0,
+        });
+        if learnt {
+            self.learnts.push(index);
+        }
+        index
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<u32>) {
+        let v = var(lit);
+        debug_assert_eq!(self.assigns[v], L_UNDEF);
+        self.assigns[v] = if lit & 1 != 0 { L_FALSE } else { L_TRUE };
+        self.level[v] = self.decision_level();
+        self.reason[v] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Unit propagation. Returns the conflicting clause, if any.
+    fn propagate(&mut self) -> Option<u32> {
+        let mut conflict = None;
+        while self.qhead < self.trail.len() && conflict.is_none() {
+            let p = self.trail[self.qhead];
+            self.qhead += 1;
+            let false_lit = p ^ 1;
+            let decision_level = self.trail_lim.len() as u32;
+
+            let mut ws = std::mem::take(&mut self.watches[false_lit as usize]);
+            let mut i = 0;
+            let mut j = 0;
+            while i < ws.len() {
+                let w = ws[i];
+                i += 1;
+                if lit_value(&self.assigns, w.blocker) == L_TRUE {
+                    ws[j] = w;
+                    j += 1;
+                    continue;
+                }
+                let clause = &mut self.clauses[w.clause as usize];
+                if clause.deleted {
+                    // Drop the watcher of a deleted clause.
+                    continue;
+                }
+
+                // Make sure the false literal is the second one:
+                if clause.lits[0] == false_lit {
+                    clause.lits.swap(0, 1);
+                }
+                let first = clause.lits[0];
+                let watcher = Watcher {
+                    clause: w.clause,
+                    blocker: first,
+                };
+                if first != w.blocker && lit_value(&self.assigns, first) == L_TRUE {
+                    ws[j] = watcher;
+                    j += 1;
+                    continue;
+                }
+
+                // Look for a new literal to watch:
+                if let Some(k) = (2..clause.lits.len())
+                    .find(|&k| lit_value(&self.assigns, clause.lits[k]) != L_FALSE)
+                {
+                    clause.lits.swap(1, k);
+                    self.watches[clause.lits[1] as usize].push(watcher);
+                    continue;
+                }
+
+                // The clause is unit or conflicting:
+                ws[j] = watcher;
+                j += 1;
+                if lit_value(&self.assigns, first) == L_FALSE {
+                    conflict = Some(w.clause);
+                    self.qhead = self.trail.len();
+                    while i < ws.len() {
+                        ws[j] = ws[i];
+                        j += 1;
+                        i += 1;
+                    }
+                } else {
+                    let v = var(first);
+                    self.assigns[v] = if first & 1 != 0 { L_FALSE } else { L_TRUE };
+                    self.level[v] = decision_level;
+                    self.reason[v] = Some(w.clause);
+                    self.trail.push(first);
+                }
+            }
+            ws.truncate(j);
+            self.watches[false_lit as usize] = ws;
+        }
+        conflict
+    }
+
+    fn bump_var(&mut self, v: usize) {
+        self.activity[v] += self.var_inc;
+        if self.activity[v] > 1e100 {
+            for a in self.activity.iter_mut() {
+                *a *= 1e-100;
+            }
+            self.var_inc *= 1e-100;
+        }
+        self.order.update(v, &self.activity);
+    }
+
+    fn bump_clause(&mut self, index: u32) {
+        let clause = &mut self.clauses[index as usize];
+        clause.activity += self.cla_inc;
+        if clause.activity > 1e20 {
+            for &k in &self.learnts {
+                self.clauses[k as usize].activity *= 1e-20;
+            }
+            self.cla_inc *= 1e-20;
+        }
+    }
+
+    fn decay_activities(&mut self) {
+        self.var_inc /= 0.95;
+        self.cla_inc /= 0.999;
+    }
+
+    /// First-UIP conflict analysis.
+    /// Returns the learnt clause, with the asserting literal first and a literal
+    /// of the backtrack level second, and the backtrack level.
+    fn analyze(&mut self, conflict: u32) -> (Vec<Lit>, u32) {
+        let mut learnt = vec![0];
+        let mut path_count = 0;
+        let mut p: Option<Lit> = None;
+        let mut index = self.trail.len();
+        let mut conflict = conflict;
+
+        loop {
+            if self.clauses[conflict as usize].learnt {
+                self.bump_clause(conflict);
+            }
+            let start = if p.is_none() { 0 } else { 1 };
+            for k in start..self.clauses[conflict as usize].lits.len() {
+                let q = self.clauses[conflict as usize].lits[k];
+                let v = var(q);
+                if !self.seen[v] && self.level[v] > 0 {
+                    self.bump_var(v);
+                    self.seen[v] = true;
+                    if self.level[v] >= self.decision_level() {
+                        path_count += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+
+            // Select the next literal to look at:
+            loop {
+                index -= 1;
+                if self.seen[var(self.trail[index])] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            p = Some(lit);
+            self.seen[var(lit)] = false;
+            path_count -= 1;
+            if path_count == 0 {
+                break;
+            }
+            conflict = self.reason[var(lit)].expect("Implied literal must have a reason");
+        }
+        learnt[0] = p.unwrap() ^ 1;
+
+        // Minimisation: drop literals implied by other literals of the clause.
+        let mut minimized = vec![learnt[0]];
+        for &lit in &learnt[1..] {
+            let redundant = match self.reason[var(lit)] {
+                None => false,
+                Some(r) => self.clauses[r as usize].lits[1..]
+                    .iter()
+                    .all(|&q| self.seen[var(q)] || self.level[var(q)] == 0),
+            };
+            if !redundant {
+                minimized.push(lit);
+            }
+        }
+        for &lit in &learnt {
+            self.seen[var(lit)] = false;
+        }
+        let mut learnt = minimized;
+
+        // Find the backtrack level, putting its literal second:
+        let backtrack_level = if learnt.len() == 1 {
+            0
+        } else {
+            let k = (1..learnt.len())
+                .max_by_key(|&k| self.level[var(learnt[k])])
+                .unwrap();
+            learnt.swap(1, k);
+            self.level[var(learnt[1])]
+        };
+        (learnt, backtrack_level)
+    }
+
+    fn cancel_until(&mut self, level: u32) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let lim = self.trail_lim[level as usize];
+        for k in (lim..self.trail.len()).rev() {
+            let lit = self.trail[k];
+            let v = var(lit);
+            self.assigns[v] = L_UNDEF;
+            self.reason[v] = None;
+            self.polarity[v] = lit & 1 != 0;
+            self.order.insert(v, &self.activity);
+        }
+        self.trail.truncate(lim);
+        self.trail_lim.truncate(level as usize);
+        self.qhead = lim;
+    }
+
+    fn pick_branch_lit(&mut self) -> Option<Lit> {
+        while let Some(v) = self.order.pop(&self.activity) {
+            if self.assigns[v] == L_UNDEF {
+                return Some(((v as u32) << 1) | self.polarity[v] as u32);
+            }
+        }
+        None
+    }
+
+    fn is_locked(&self, index: u32) -> bool {
+        let lit = self.clauses[index as usize].lits[0];
+        self.value_lit(lit) == L_TRUE && self.reason[var(lit)] == Some(index)
+    }
+
+    /// Remove half of the learnt clauses, the least active ones.
+    fn reduce_db(&mut self) {
+        let mut learnts = std::mem::take(&mut self.learnts);
+        learnts.sort_by(|&a, &b| {
+            let a = &self.clauses[a as usize];
+            let b = &self.clauses[b as usize];
+            (a.lits.len() > 2)
+                .cmp(&(b.lits.len() > 2))
+                .reverse()
+                .then(a.activity.total_cmp(&b.activity))
+        });
+        let half = learnts.len() / 2;
+        let mut kept = Vec::with_capacity(learnts.len() - half);
+        for (k, &index) in learnts.iter().enumerate() {
+            let clause = &self.clauses[index as usize];
+            if k < half && clause.lits.len() > 2 && !self.is_locked(index) {
+                let clause = &mut self.clauses[index as usize];
+                clause.deleted = true;
+                clause.lits = Vec::new();
+            } else {
+                kept.push(index);
+            }
+        }
+        self.learnts = kept;
+    }
+
+    /// Collect the assumptions responsible for the failed assumption `p`,
+    /// which is false under the current assignment.
+    fn analyze_final(&mut self, p: Lit) {
+        self.failed = vec![to_dimacs(p)];
+        if self.decision_level() == 0 {
+            return;
+        }
+        self.seen[var(p)] = true;
+        for k in (self.trail_lim[0]..self.trail.len()).rev() {
+            let lit = self.trail[k];
+            let v = var(lit);
+            if !self.seen[v] {
+                continue;
+            }
+            match self.reason[v] {
+                // Decisions below the assumption levels are assumptions.
+                None => self.failed.push(to_dimacs(lit)),
+                Some(r) => {
+                    for &q in &self.clauses[r as usize].lits[1..] {
+                        if self.level[var(q)] > 0 {
+                            self.seen[var(q)] = true;
+                        }
+                    }
+                }
+            }
+            self.seen[v] = false;
+        }
+        self.seen[var(p)] = false;
+    }
+
+    /// Search for a model for up to the given number of conflicts.
+    fn search(&mut self, max_conflicts: u64) -> i8 {
+        let mut conflicts = 0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                conflicts += 1;
+                self.conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.ok = false;
+                    return L_FALSE;
+                }
+                let (learnt, backtrack_level) = self.analyze(conflict);
+                self.cancel_until(backtrack_level);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let lit = learnt[0];
+                    let index = self.attach_clause(learnt, true);
+                    self.bump_clause(index);
+                    self.enqueue(lit, Some(index));
+                }
+                self.decay_activities();
+            } else {
+                if conflicts >= max_conflicts {
+                    self.cancel_until(0);
+                    return L_UNDEF;
+                }
+                if self.learnts.len() as f64 >= self.max_learnts + self.trail.len() as f64 {
+                    self.reduce_db();
+                }
+
+                // Assumptions are decided first, one per decision level:
+                let mut next = None;
+                while (self.decision_level() as usize) < self.assumptions.len() {
+                    let p = self.assumptions[self.decision_level() as usize];
+                    match self.value_lit(p) {
+                        L_TRUE => self.trail_lim.push(self.trail.len()),
+                        L_FALSE => {
+                            self.analyze_final(p);
+                            return L_FALSE;
+                        }
+                        _ => {
+                            next = Some(p);
+                            break;
+                        }
+                    }
+                }
+
+                match next.or_else(|| self.pick_branch_lit()) {
+                    None => return L_TRUE,
+                    Some(lit) => {
+                        self.trail_lim.push(self.trail.len());
+                        self.enqueue(lit, None);
+                    }
+                }
+            }
+        }
+    }
+
+    fn solve_assuming(&mut self, assumptions: &[i32]) -> SolveResult {
+        self.model.clear();
+        self.failed.clear();
+        if !self.ok {
+            return SolveResult::Unsat;
+        }
+        self.assumptions = assumptions.iter().map(|&l| from_dimacs(l)).collect();
+        if let Some(max) = self.assumptions.iter().map(|&l| var(l)).max() {
+            self.reserve_vars(max + 1);
+        }
+
+        self.max_learnts = (self.num_clauses() as f64 / 3.0).max(1000.0);
+        let mut restarts = 0;
+        let status = loop {
+            let max_conflicts = (luby(2.0, restarts) * 100.0) as u64;
+            let status = self.search(max_conflicts);
+            if status != L_UNDEF {
+                break status;
+            }
+            restarts += 1;
+            self.max_learnts *= 1.1;
+        };
+
+        if status == L_TRUE {
+            self.model = self.assigns.iter().map(|&v| v == L_TRUE).collect();
+        }
+        self.cancel_until(0);
+        self.assumptions.clear();
+        if status == L_TRUE {
+            SolveResult::Sat
+        } else {
+            SolveResult::Unsat
+        }
+    }
+
+    /// The model found by the last successful call, as a list of DIMACS literals.
+    pub fn model(&self) -> Vec<i32> {
+        (0..self.model.len())
+            .map(|v| to_dimacs(((v as u32) << 1) | !self.model[v] as u32))
+            .collect()
+    }
+}
+
+impl ClauseSink for Solver {
+    fn new_var(&mut self) -> i32 {
+        let n = self.num_vars() + 1;
+        self.reserve_vars(n);
+        n as i32
+    }
+
+    fn add_clause(&mut self, lits: &[i32]) {
+        self.add_clause_inner(lits);
+    }
+}
+
+impl SatSolver for Solver {
+    fn num_vars(&self) -> usize {
+        self.assigns.len()
+    }
+
+    fn solve_with(&mut self, assumptions: &[i32]) -> SolveResult {
+        self.solve_assuming(assumptions)
+    }
+
+    fn value(&self, lit: i32) -> Option<bool> {
+        let l = from_dimacs(lit);
+        self.model.get(var(l)).map(|&v| v ^ (l & 1 != 0))
+    }
+
+    fn failed_assumptions(&self) -> Vec<i32> {
+        self.failed.clone()
+    }
+}
+
+/// The Luby sequence scaled by `y`: 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ...
+fn luby(y: f64, mut x: u32) -> f64 {
+    let mut size = 1;
+    let mut seq = 0;
+    while size < x + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != x {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        x %= size;
+    }
+    y.powi(seq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::sim::SplitMix64;
+
+    fn check_model(clauses: &[Vec<i32>], solver: &Solver) {
+        for clause in clauses {
+            assert!(
+                clause.iter().any(|&l| solver.value(l) == Some(true)),
+                "Clause {:?} is not satisfied",
+                clause
+            );
+        }
+    }
+
+    #[test]
+    fn test_simple() {
+        let mut solver = Solver::new();
+        let clauses = vec![vec![1, 2], vec![-1, 2], vec![1, -2]];
+        for clause in &clauses {
+            solver.add_clause(clause);
+        }
+        assert_eq!(solver.solve(), SolveResult::Sat);
+        check_model(&clauses, &solver);
+        assert_eq!(solver.value(1), Some(true));
+        assert_eq!(solver.value(-2), Some(false));
+
+        solver.add_clause(&[-1, -2]);
+        assert_eq!(solver.solve(), SolveResult::Unsat);
+    }
+
+    #[test]
+    fn test_assumptions() {
+        // (1 | 2) & (-1 | 3) & (-2 | 3)
+        let mut solver = Solver::new();
+        solver.add_clause(&[1, 2]);
+        solver.add_clause(&[-1, 3]);
+        solver.add_clause(&[-2, 3]);
+        solver.new_var(); // 4 is unconstrained
+
+        assert_eq!(solver.solve_with(&[-3, 4]), SolveResult::Unsat);
+        assert_eq!(solver.failed_assumptions(), vec![-3]);
+
+        assert_eq!(solver.solve_with(&[4, -1]), SolveResult::Sat);
+        assert_eq!(solver.value(4), Some(true));
+        assert_eq!(solver.value(2), Some(true));
+        assert_eq!(solver.value(3), Some(true));
+
+        // Failed assumptions do not make the formula unsatisfiable:
+        assert_eq!(solver.solve_with(&[-1, -2]), SolveResult::Unsat);
+        let mut failed = solver.failed_assumptions();
+        failed.sort();
+        assert_eq!(failed, vec![-2, -1]);
+        assert_eq!(solver.solve(), SolveResult::Sat);
+        assert!(solver.failed_assumptions().is_empty());
+    }
+
+    #[test]
+    fn test_pigeonhole() {
+        // 5 pigeons, 4 holes: p(i, j) = pigeon i in hole j
+        let (pigeons, holes) = (5, 4);
+        let p = |i: i32, j: i32| i * holes + j + 1;
+        let mut solver = Solver::new();
+        for i in 0..pigeons {
+            solver.add_clause(&(0..holes).map(|j| p(i, j)).collect::<Vec<_>>());
+        }
+        for j in 0..holes {
+            for a in 0..pigeons {
+                for b in (a + 1)..pigeons {
+                    solver.add_clause(&[-p(a, j), -p(b, j)]);
+                }
+            }
+        }
+        assert_eq!(solver.solve(), SolveResult::Unsat);
+    }
+
+    #[test]
+    fn test_random_3sat() {
+        let mut rng = SplitMix64::new(7);
+        let n = 12;
+        for _ in 0..50 {
+            let clauses = (0..52)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| {
+                            let v = (rng.next_u64() % n) as i32 + 1;
+                            if rng.next_u64() & 1 == 0 {
+                                v
+                            } else {
+                                -v
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            // Brute force:
+            let expected = (0..(1u32 << n)).any(|m| {
+                clauses.iter().all(|c| {
+                    c.iter()
+                        .any(|&l| ((m >> (l.unsigned_abs() - 1)) & 1 != 0) == (l > 0))
+                })
+            });
+
+            let mut solver = Solver::new();
+            for clause in &clauses {
+                solver.add_clause(clause);
+            }
+            let result = solver.solve();
+            assert_eq!(result.is_sat(), expected);
+            if expected {
+                check_model(&clauses, &solver);
+            }
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use eyre::eyre;
+
+use crate::aig::Aig;
+use crate::reference::Ref;
+
+/// Bit-parallel simulation values of all nodes in an AIG.
+///
+/// Each node holds `N` 64-bit words, so `64 * N` patterns are simulated at once.
+/// Bit `k` of word `w` corresponds to the pattern `64 * w + k`.
+#[derive(Debug, Clone)]
+pub struct Simulation<const N: usize> {
+    /// Values indexed by node id.
+    values: Vec<[u64; N]>,
+    /// Values of the outputs.
+    outputs: Vec<[u64; N]>,
+}
+
+impl<const N: usize> Simulation<N> {
+    /// The number of simulated patterns.
+    pub const PATTERNS: usize = 64 * N;
+
+    /// Return the values of the node with the given id.
+    pub fn node(&self, id: u32) -> [u64; N] {
+        self.values[id as usize]
+    }
+
+    /// Return the values of the reference, taking the negation into account.
+    pub fn get(&self, r: Ref) -> [u64; N] {
+        let mut words = self.values[r.id() as usize];
+        if r.is_negated() {
+            for w in words.iter_mut() {
+                *w = !*w;
+            }
+        }
+        words
+    }
+
+    /// Return the value of the reference in the given pattern.
+    pub fn bit(&self, r: Ref, pattern: usize) -> bool {
+        let word = self.values[r.id() as usize][pattern / 64];
+        ((word >> (pattern % 64)) & 1 != 0) ^ r.is_negated()
+    }
+
+    /// Return the values of the outputs.
+    pub fn outputs(&self) -> &[[u64; N]] {
+        &self.outputs
+    }
+
+    /// Return the values of all nodes, indexed by id.
+    pub fn values(&self) -> &[[u64; N]] {
+        &self.values
+    }
+}
+
+/// Small and fast pseudo-random generator (SplitMix64) used for random simulation.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_words<const N: usize>(&mut self) -> [u64; N] {
+        std::array::from_fn(|_| self.next_u64())
+    }
+}
+
+impl Aig {
+    /// Simulate the AIG on `64 * N` patterns given by the input values.
+    ///
+    /// Latches take their initial values, non-deterministic latches are set to zero.
+    pub fn simulate<const N: usize>(&self, inputs: &[[u64; N]]) -> Simulation<N> {
+        let state = self
+            .latches()
+            .iter()
+            .map(|&id| {
+                let value = self.latch(id).init.value().unwrap_or(false);
+                [if value { !0 } else { 0 }; N]
+            })
+            .collect::<Vec<_>>();
+        self.simulate_with_state(inputs, &state)
+    }
+
+    /// Simulate the AIG on `64 * N` patterns given by the input and latch values.
+    pub fn simulate_with_state<const N: usize>(
+        &self,
+        inputs: &[[u64; N]],
+        state: &[[u64; N]],
+    ) -> Simulation<N> {
+        assert_eq!(inputs.len(), self.inputs().len());
+        assert_eq!(state.len(), self.latches().len());
+
+        let mut values = vec![[0; N]; self.max_id() as usize + 1];
+        for (&id, &words) in self.inputs().iter().zip(inputs) {
+            values[id as usize] = words;
+        }
+        for (&id, &words) in self.latches().iter().zip(state) {
+            values[id as usize] = words;
+        }
+
+        for &id in self.topological_order() {
+            let [left, right] = self.gate(id).args;
+            let left_mask = if left.is_negated() { !0 } else { 0 };
+            let right_mask = if right.is_negated() { !0 } else { 0 };
+            let left = values[left.id() as usize];
+            let right = values[right.id() as usize];
+            values[id as usize] =
+                std::array::from_fn(|w| (left[w] ^ left_mask) & (right[w] ^ right_mask));
+        }
+
+        let mut simulation = Simulation {
+            values,
+            outputs: Vec::new(),
+        };
+        simulation.outputs = self.outputs().iter().map(|&r| simulation.get(r)).collect();
+        simulation
+    }
+
+    /// Simulate the AIG on `64 * N` random patterns, with random input and latch values.
+    pub fn simulate_random<const N: usize>(&self, seed: u64) -> Simulation<N> {
+        let mut rng = SplitMix64::new(seed);
+        let inputs = self
+            .inputs()
+            .iter()
+            .map(|_| rng.next_words())
+            .collect::<Vec<_>>();
+        let state = self
+            .latches()
+            .iter()
+            .map(|_| rng.next_words())
+            .collect::<Vec<_>>();
+        self.simulate_with_state(&inputs, &state)
+    }
+}
+
+/// Trace of a sequential simulation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Trace {
+    /// Latch values at the beginning of each cycle, followed by the final state.
+    pub states: Vec<Vec<bool>>,
+    /// Output values in each cycle.
+    pub outputs: Vec<Vec<bool>>,
+    /// Bad state property values in each cycle.
+    pub bad: Vec<Vec<bool>>,
+    /// Invariant constraint values in each cycle.
+    pub constraints: Vec<Vec<bool>>,
+}
+
+/// Cycle-by-cycle simulator of a sequential AIG.
+pub struct SequentialSimulator<'a> {
+    aig: &'a Aig,
+    state: Vec<bool>,
+    /// Values of all nodes in the last simulated cycle, indexed by id.
+    values: Vec<bool>,
+}
+
+impl<'a> SequentialSimulator<'a> {
+    /// Create a simulator starting from the initial state of the AIG,
+    /// see [`Aig::initial_state`].
+    pub fn new(aig: &'a Aig) -> Self {
+        Self::with_state(aig, aig.initial_state())
+    }
+
+    /// Create a simulator starting from an arbitrary state, given by latch values.
+    pub fn with_state(aig: &'a Aig, state: Vec<bool>) -> Self {
+        assert_eq!(state.len(), aig.latches().len());
+        Self {
+            aig,
+            state,
+            values: vec![false; aig.max_id() as usize + 1],
+        }
+    }
+
+    /// Return the current latch values.
+    pub fn state(&self) -> &[bool] {
+        &self.state
+    }
+
+    /// Return the value of the reference in the last simulated cycle.
+    pub fn value(&self, r: Ref) -> bool {
+        self.values[r.id() as usize] ^ r.is_negated()
+    }
+
+    /// Evaluate the combinational logic for the given inputs in the current state,
+    /// without advancing to the next state.
+    pub fn eval(&mut self, inputs: &[bool]) {
+        let aig = self.aig;
+        assert_eq!(inputs.len(), aig.inputs().len());
+
+        self.values[0] = false;
+        for (&id, &value) in aig.inputs().iter().zip(inputs) {
+            self.values[id as usize] = value;
+        }
+        for (&id, &value) in aig.latches().iter().zip(&self.state) {
+            self.values[id as usize] = value;
+        }
+        for &id in aig.topological_order() {
+            let [left, right] = aig.gate(id).args;
+            self.values[id as usize] = self.value(left) && self.value(right);
+        }
+    }
+
+    /// Simulate a single cycle: evaluate the combinational logic and update the latches.
+    /// Returns the output values.
+    pub fn step(&mut self, inputs: &[bool]) -> Vec<bool> {
+        self.eval(inputs);
+        let aig = self.aig;
+        let outputs = aig.outputs().iter().map(|&r| self.value(r)).collect();
+        self.state = aig
+            .latches()
+            .iter()
+            .map(|&id| self.value(aig.latch(id).next))
+            .collect();
+        outputs
+    }
+}
+
+impl Aig {
+    /// Return the initial state given by the latch reset values,
+    /// uninitialised latches are set to zero.
+    pub fn initial_state(&self) -> Vec<bool> {
+        self.latches()
+            .iter()
+            .map(|&id| self.latch(id).init.value().unwrap_or(false))
+            .collect()
+    }
+
+    /// Simulate the AIG for as many cycles as there are input vectors.
+    ///
+    /// The initial state defaults to [`Aig::initial_state`]. When given explicitly,
+    /// it must agree with the reset values of all initialised latches.
+    ///
+    /// Returns an error if the initial state or some input vector has the wrong length,
+    /// or if the initial state contradicts a reset value.
+    pub fn simulate_sequential(
+        &self,
+        initial: Option<&[bool]>,
+        inputs: &[Vec<bool>],
+    ) -> eyre::Result<Trace> {
+        let state = match initial {
+            Some(state) => {
+                if state.len() != self.latches().len() {
+                    return Err(eyre!(
+                        "Initial state has {} values, expected {}",
+                        state.len(),
+                        self.latches().len()
+                    ));
+                }
+                for (&id, &value) in self.latches().iter().zip(state) {
+                    if self
+                        .latch(id)
+                        .init
+                        .value()
+                        .is_some_and(|reset| reset != value)
+                    {
+                        return Err(eyre!(
+                            "Initial value of latch {} contradicts its reset value",
+                            id
+                        ));
+                    }
+                }
+                state.to_vec()
+            }
+            None => self.initial_state(),
+        };
+        if let Some((k, input)) = inputs
+            .iter()
+            .enumerate()
+            .find(|(_, input)| input.len() != self.inputs().len())
+        {
+            return Err(eyre!(
+                "Input vector {} has {} values, expected {}",
+                k,
+                input.len(),
+                self.inputs().len()
+            ));
+        }
+
+        let mut sim = SequentialSimulator::with_state(self, state);
+        let mut trace = Trace {
+            states: Vec::new(),
+            outputs: Vec::new(),
+            bad: Vec::new(),
+            constraints: Vec::new(),
+        };
+        for input in inputs {
+            trace.states.push(sim.state().to_vec());
+            trace.outputs.push(sim.step(input));
+            // Node values still correspond to the simulated cycle:
+            trace
+                .bad
+                .push(self.bad().iter().map(|&r| sim.value(r)).collect());
+            trace
+                .constraints
+                .push(self.constraints().iter().map(|&r| sim.value(r)).collect());
+        }
+        trace.states.push(sim.state().to_vec());
+        Ok(trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_exhaustive() {
+        let aig = Aig::from_file("data/examples/halfadder.aag").unwrap();
+        assert_eq!(aig.inputs().len(), 2);
+
+        // All four combinations of two inputs:
+        let sim = aig.simulate::<1>(&[[0b1010], [0b1100]]);
+        assert_eq!(sim.outputs().len(), 2);
+        assert_eq!(sim.outputs()[0][0] & 0b1111, 0b0110); // sum
+        assert_eq!(sim.outputs()[1][0] & 0b1111, 0b1000); // carry
+    }
+
+    #[test]
+    fn test_simulate_matches_eval() {
+        let aig = Aig::from_file("data/arithmetic/adder.aag").unwrap();
+        let sim = aig.simulate_random::<2>(42);
+
+        for pattern in [0, 1, 63, 64, 100, 127] {
+            let inputs = aig
+                .inputs()
+                .iter()
+                .map(|&id| sim.bit(Ref::positive(id), pattern))
+                .collect::<Vec<_>>();
+            let values = aig.eval(inputs);
+            for &output in aig.outputs() {
+                let expected = match output.get_const() {
+                    Some(c) => c,
+                    None => values[&output.id()] ^ output.is_negated(),
+                };
+                assert_eq!(sim.bit(output, pattern), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_simulate_toggle() {
+        let aig = Aig::from_file("data/examples/toggle.aag").unwrap();
+        let trace = aig
+            .simulate_sequential(None, &[vec![], vec![], vec![]])
+            .unwrap();
+        assert_eq!(
+            trace.states,
+            vec![vec![false], vec![true], vec![false], vec![true]]
+        );
+        assert_eq!(
+            trace.outputs,
+            vec![vec![false, true], vec![true, false], vec![false, true]]
+        );
+
+        // The latch is reset to zero:
+        assert!(aig.simulate_sequential(Some(&[false]), &[vec![]]).is_ok());
+        assert!(aig.simulate_sequential(Some(&[true]), &[vec![]]).is_err());
+        assert!(aig.simulate_sequential(Some(&[]), &[vec![]]).is_err());
+        assert!(aig.simulate_sequential(None, &[vec![true]]).is_err());
+    }
+
+    #[test]
+    fn test_simulate_toggle_re() {
+        let aig = Aig::from_file("data/examples/toggle-re.aag").unwrap();
+        let enable = aig.find_input("enable").unwrap();
+        let reset = aig.find_input("reset").unwrap();
+        assert_eq!(aig.inputs(), &[enable, reset]);
+
+        // Inputs are [enable, reset], the reset is active-low.
+        let inputs = vec![
+            vec![true, true],
+            vec![false, true],
+            vec![true, true],
+            vec![true, false],
+        ];
+        let trace = aig.simulate_sequential(None, &inputs).unwrap();
+        let q = trace.outputs.iter().map(|o| o[0]).collect::<Vec<_>>();
+        assert_eq!(q, vec![false, true, true, false]);
+        assert_eq!(trace.states.last().unwrap(), &vec![false]);
+    }
+}
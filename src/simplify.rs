@@ -0,0 +1,185 @@
+use crate::aig::Aig;
+use crate::builder::{image, AigBuilder};
+use crate::node::LatchInit;
+use crate::reference::Ref;
+
+/// Statistics of [`Aig::simplify`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SimplifyStats {
+    /// AND gates removed: constant gates, buffers, duplicates and dangling gates.
+    pub removed_gates: usize,
+    /// Latches stuck at their reset value, replaced by constants.
+    pub removed_latches: usize,
+}
+
+impl SimplifyStats {
+    /// Total number of removed nodes.
+    pub fn removed_nodes(&self) -> usize {
+        self.removed_gates + self.removed_latches
+    }
+}
+
+impl Aig {
+    /// Simplify the AIG: propagate constants through gates, outputs, properties and
+    /// latch next-states, fold `x & x` and `x & ~x`, merge structurally identical gates,
+    /// replace latches stuck at their reset value by constants, and remove dangling gates
+    /// (see [`Aig::remove_dangling`]).
+    ///
+    /// A set of latches is stuck if, assuming they all hold their reset values,
+    /// their next-state functions reduce to the same constants. The largest such set
+    /// is found iteratively, so latches which only feed each other are removed too.
+    pub fn simplify(&self) -> (Aig, SimplifyStats) {
+        let mut stuck = self
+            .latches()
+            .iter()
+            .map(|&id| self.latch(id).init != LatchInit::Nondet)
+            .collect::<Vec<_>>();
+        let aig = loop {
+            let (aig, map) = self.rebuild(&stuck);
+            let mut changed = false;
+            for (k, &id) in self.latches().iter().enumerate() {
+                if stuck[k] && image(&map, self.latch(id).next) != map[id as usize] {
+                    stuck[k] = false;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break aig;
+            }
+        };
+
+        let kept = stuck.iter().map(|&s| !s).collect::<Vec<_>>();
+        let aig = self.finish_rebuild(aig, Some(&kept));
+
+        let stats = SimplifyStats {
+            removed_gates: self.and_gates().count() - aig.and_gates().count(),
+            removed_latches: self.latches().len() - aig.latches().len(),
+        };
+        log::debug!(
+            "Simplification removed {} gates and {} latches",
+            stats.removed_gates,
+            stats.removed_latches
+        );
+        (aig, stats)
+    }
+
+    /// Copy the AIG through a structurally hashing builder, replacing the `stuck`
+    /// latches (given by position) with their reset values.
+    /// Returns the copy and the mapping from the old ids to the new references.
+    fn rebuild(&self, stuck: &[bool]) -> (Aig, Vec<Ref>) {
+        let mut builder = AigBuilder::new();
+        let mut map = vec![Ref::FALSE; self.max_id() as usize + 1];
+        for &id in self.inputs() {
+            map[id as usize] = builder.input();
+        }
+        let mut latches = Vec::new();
+        for (&id, &stuck) in self.latches().iter().zip(stuck) {
+            let init = self.latch(id).init;
+            map[id as usize] = if stuck {
+                match init {
+                    LatchInit::One => Ref::TRUE,
+                    _ => Ref::FALSE,
+                }
+            } else {
+                let latch = builder.latch(init);
+                latches.push((id, latch));
+                latch
+            };
+        }
+        builder.import(self, &mut map);
+
+        for (id, latch) in latches {
+            builder.set_latch_next(latch, image(&map, self.latch(id).next));
+        }
+        builder.import_properties(self, &map);
+        (builder.build(), map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::aiger::SymbolType;
+
+    #[test]
+    fn test_simplify_gates() {
+        let mut aig = Aig::default();
+        aig.add_input(1);
+        aig.add_input(2);
+        // Constant: ~g3 & 0
+        aig.add_and_gate(3, [Ref::negative(1), Ref::positive(2)]);
+        aig.add_and_gate(4, [Ref::negative(3), Ref::FALSE]);
+        // Buffer: g3 & g3
+        aig.add_and_gate(5, [Ref::positive(3), Ref::positive(3)]);
+        // Duplicate of g3, with swapped arguments
+        aig.add_and_gate(6, [Ref::positive(2), Ref::negative(1)]);
+        // Constant: g6 & ~g3
+        aig.add_and_gate(7, [Ref::positive(6), Ref::negative(3)]);
+        // Dangling
+        aig.add_and_gate(8, [Ref::positive(1), Ref::positive(2)]);
+        aig.add_output(Ref::positive(4));
+        aig.add_output(Ref::negative(5));
+        aig.add_output(Ref::positive(6));
+        aig.add_output(Ref::positive(7));
+        aig.set_symbol(SymbolType::Output, 2, "g6".to_string());
+
+        let (simple, stats) = aig.simplify();
+        assert_eq!(stats.removed_gates, 5);
+        assert_eq!(stats.removed_nodes(), 5);
+        assert_eq!(simple.inputs(), &[1, 2]);
+        assert_eq!(simple.and_gates().count(), 1);
+        assert_eq!(
+            simple.outputs(),
+            &[Ref::FALSE, Ref::negative(3), Ref::positive(3), Ref::FALSE]
+        );
+        assert_eq!(simple.output_name(2), Some("g6"));
+    }
+
+    #[test]
+    fn test_simplify_latches() {
+        let mut builder = AigBuilder::new();
+        let x = builder.input();
+        // Stuck at zero: next = l0 & x
+        let l0 = builder.latch(LatchInit::Zero);
+        let n0 = builder.and(l0, x);
+        builder.set_latch_next(l0, n0);
+        // Stuck at one, together: next(l1) = l2, next(l2) = l1
+        let l1 = builder.latch(LatchInit::One);
+        let l2 = builder.latch(LatchInit::One);
+        builder.set_latch_next(l1, l2);
+        builder.set_latch_next(l2, l1);
+        // Not stuck: toggles when enabled
+        let l3 = builder.latch(LatchInit::Zero);
+        let n3 = builder.xor(l3, x);
+        builder.set_latch_next(l3, n3);
+        // Not stuck: non-deterministic reset
+        let l4 = builder.latch(LatchInit::Nondet);
+        builder.set_latch_next(l4, l4);
+
+        let t = builder.and(l1, l3);
+        let o = builder.or(l0, t);
+        builder.output(o);
+        builder.output(l4);
+        let mut aig = builder.build();
+        aig.set_symbol(SymbolType::Latch, 3, "toggle".to_string());
+
+        let (simple, stats) = aig.simplify();
+        assert_eq!(stats.removed_latches, 3);
+        assert_eq!(simple.latches().len(), 2);
+        assert_eq!(simple.latch_name(simple.latches()[0]), Some("toggle"));
+        // The output reduces to the toggle latch:
+        assert_eq!(simple.outputs()[0], Ref::positive(simple.latches()[0]));
+
+        let inputs = [true, false, true, true, false]
+            .iter()
+            .map(|&x| vec![x])
+            .collect::<Vec<_>>();
+        let initial = [false, true, true, false, true];
+        let expected = aig.simulate_sequential(Some(&initial), &inputs).unwrap();
+        let actual = simple
+            .simulate_sequential(Some(&[false, true]), &inputs)
+            .unwrap();
+        assert_eq!(actual.outputs, expected.outputs);
+    }
+}
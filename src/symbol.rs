@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::aiger::SymbolType;
+
+/// Symbol table, mapping positions of inputs, latches, outputs and properties to their names.
+///
+/// Names are not required to be unique: several symbols of the same type may share a name,
+/// see [`SymbolTable::positions`].
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct SymbolTable {
+    names: BTreeMap<(SymbolType, usize), String>,
+    /// Positions of the symbols with each name, per type.
+    positions: HashMap<SymbolType, HashMap<String, BTreeSet<usize>>>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Return the name of the symbol at the given position.
+    pub fn name(&self, type_spec: SymbolType, position: usize) -> Option<&str> {
+        self.names.get(&(type_spec, position)).map(|s| s.as_str())
+    }
+
+    /// Return the position of the symbol with the given name.
+    /// When the name is not unique, the first position is returned.
+    pub fn position(&self, type_spec: SymbolType, name: &str) -> Option<usize> {
+        self.positions(type_spec, name).next()
+    }
+
+    /// Return the positions of all symbols with the given name, in increasing order.
+    pub fn positions(&self, type_spec: SymbolType, name: &str) -> impl Iterator<Item = usize> + '_ {
+        self.positions
+            .get(&type_spec)
+            .and_then(|positions| positions.get(name))
+            .into_iter()
+            .flatten()
+            .copied()
+    }
+
+    /// Set the name of the symbol at the given position, replacing the previous name.
+    pub fn insert(&mut self, type_spec: SymbolType, position: usize, name: String) {
+        if let Some(old) = self.names.insert((type_spec, position), name.clone()) {
+            self.unlink(type_spec, position, &old);
+        }
+        self.positions
+            .entry(type_spec)
+            .or_default()
+            .entry(name)
+            .or_default()
+            .insert(position);
+    }
+
+    /// Remove the name of the symbol at the given position.
+    pub fn remove(&mut self, type_spec: SymbolType, position: usize) -> Option<String> {
+        let name = self.names.remove(&(type_spec, position))?;
+        self.unlink(type_spec, position, &name);
+        Some(name)
+    }
+
+    /// Remove the position from the reverse map.
+    fn unlink(&mut self, type_spec: SymbolType, position: usize, name: &str) {
+        let positions = self.positions.get_mut(&type_spec).unwrap();
+        let set = positions.get_mut(name).unwrap();
+        set.remove(&position);
+        if set.is_empty() {
+            positions.remove(name);
+        }
+    }
+
+    /// Iterate over all symbols, ordered by type and position.
+    pub fn iter(&self) -> impl Iterator<Item = (SymbolType, usize, &str)> {
+        self.names
+            .iter()
+            .map(|(&(type_spec, position), name)| (type_spec, position, name.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_table() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(SymbolType::Output, 0, "out".to_string());
+        symbols.insert(SymbolType::Input, 1, "b".to_string());
+        symbols.insert(SymbolType::Input, 0, "a".to_string());
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(symbols.name(SymbolType::Input, 0), Some("a"));
+        assert_eq!(symbols.position(SymbolType::Input, "b"), Some(1));
+        assert_eq!(symbols.position(SymbolType::Latch, "b"), None);
+
+        // Renaming removes the old name:
+        symbols.insert(SymbolType::Input, 1, "c".to_string());
+        assert_eq!(symbols.position(SymbolType::Input, "b"), None);
+        assert_eq!(symbols.position(SymbolType::Input, "c"), Some(1));
+
+        let all = symbols.iter().collect::<Vec<_>>();
+        assert_eq!(
+            all,
+            vec![
+                (SymbolType::Input, 0, "a"),
+                (SymbolType::Input, 1, "c"),
+                (SymbolType::Output, 0, "out"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(SymbolType::Input, 0, "x".to_string());
+        symbols.insert(SymbolType::Input, 2, "x".to_string());
+        symbols.insert(SymbolType::Latch, 0, "x".to_string());
+        assert_eq!(symbols.position(SymbolType::Input, "x"), Some(0));
+        assert_eq!(
+            symbols
+                .positions(SymbolType::Input, "x")
+                .collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert_eq!(symbols.position(SymbolType::Latch, "x"), Some(0));
+
+        // Removing or renaming one symbol keeps the others:
+        assert_eq!(symbols.remove(SymbolType::Input, 0), Some("x".to_string()));
+        assert_eq!(symbols.position(SymbolType::Input, "x"), Some(2));
+        assert_eq!(symbols.position(SymbolType::Latch, "x"), Some(0));
+        symbols.insert(SymbolType::Input, 2, "y".to_string());
+        assert_eq!(symbols.position(SymbolType::Input, "x"), None);
+        assert_eq!(symbols.position(SymbolType::Latch, "x"), Some(0));
+        assert_eq!(symbols.position(SymbolType::Input, "y"), Some(2));
+        assert_eq!(symbols.len(), 2);
+    }
+}
@@ -0,0 +1,106 @@
+//! Fixtures shared by the unit tests.
+
+use crate::aig::Aig;
+use crate::builder::AigBuilder;
+use crate::node::LatchInit;
+use crate::reference::Ref;
+use crate::sim::SplitMix64;
+
+/// Shape of a random AIG, generated with [`RandomAig::generate`].
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct RandomAig {
+    pub inputs: usize,
+    /// Latches, with random initial values and next-state functions.
+    pub latches: usize,
+    pub gates: usize,
+    /// The fanins of each gate are picked, with random polarities, among the last
+    /// `window` nodes.
+    pub window: usize,
+    /// Also generate XOR, MUX and 3-input AND gates, not only 2-input AND gates.
+    pub mixed: bool,
+    /// Outputs, taken every `stride` nodes from the last one.
+    pub outputs: usize,
+    pub stride: usize,
+}
+
+impl Default for RandomAig {
+    fn default() -> Self {
+        Self {
+            inputs: 0,
+            latches: 0,
+            gates: 0,
+            window: usize::MAX,
+            mixed: false,
+            outputs: 0,
+            stride: 1,
+        }
+    }
+}
+
+impl RandomAig {
+    pub(crate) fn generate(&self, rng: &mut SplitMix64) -> Aig {
+        let mut builder = AigBuilder::new();
+        let nodes = self.build(&mut builder, rng);
+        for i in 0..self.outputs {
+            builder.output(nodes[nodes.len() - 1 - i * self.stride]);
+        }
+        builder.build()
+    }
+
+    /// Add the inputs, latches and gates to the builder, without outputs.
+    /// Returns the inputs, the latches and the gates, in this order.
+    pub(crate) fn build(&self, builder: &mut AigBuilder, rng: &mut SplitMix64) -> Vec<Ref> {
+        let mut nodes = builder.inputs(self.inputs);
+        let latches = (0..self.latches)
+            .map(|_| {
+                let init = match rng.next_u64() % 4 {
+                    0 => LatchInit::One,
+                    1 => LatchInit::Nondet,
+                    _ => LatchInit::Zero,
+                };
+                builder.latch(init)
+            })
+            .collect::<Vec<_>>();
+        nodes.extend(&latches);
+
+        let pick = |nodes: &[Ref], rng: &mut SplitMix64| {
+            let n = nodes.len() as u64;
+            let r = nodes[(n - 1 - rng.next_u64() % n.min(self.window as u64)) as usize];
+            if rng.next_u64().is_multiple_of(2) {
+                r
+            } else {
+                -r
+            }
+        };
+        for _ in 0..self.gates {
+            let r = if self.mixed {
+                let (a, b, c) = (pick(&nodes, rng), pick(&nodes, rng), pick(&nodes, rng));
+                match rng.next_u64() % 4 {
+                    0 => builder.xor(a, b),
+                    1 => builder.mux(a, b, c),
+                    2 => builder.and_many(&[a, b, c]),
+                    _ => builder.and(a, b),
+                }
+            } else {
+                let (a, b) = (pick(&nodes, rng), pick(&nodes, rng));
+                builder.and(a, b)
+            };
+            nodes.push(r);
+        }
+
+        for &latch in &latches {
+            let next = pick(&nodes, rng);
+            builder.set_latch_next(latch, next);
+        }
+        nodes
+    }
+}
+
+/// Values of the outputs, with [`Aig::eval`].
+pub(crate) fn eval_outputs(aig: &Aig, inputs: &[bool]) -> Vec<bool> {
+    let values = aig.eval(inputs.to_vec());
+    aig.outputs()
+        .iter()
+        .map(|r| values.get(&r.id()).copied().unwrap_or(false) ^ r.is_negated())
+        .collect()
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use crate::cut::VAR_TRUTH;
+use crate::network::Network;
+
+/// Maximum number of variables of the truth tables of a window.
+pub(crate) const MAX_VARS: usize = 14;
+
+/// Number of fanout levels above the root included in the observability window.
+const ODC_LEVELS: u32 = 3;
+/// Maximum number of gates in the observability window.
+const ODC_MAX_GATES: usize = 64;
+
+/// Truth tables are stored as 64-bit words. Functions of fewer than 6 variables
+/// use one word, in which the table is repeated.
+pub(crate) fn num_words(vars: usize) -> usize {
+    1 << vars.saturating_sub(6)
+}
+
+/// Truth table of the variable.
+pub(crate) fn var_truth(var: usize, vars: usize) -> Vec<u64> {
+    if var < 6 {
+        vec![VAR_TRUTH[var]; num_words(vars)]
+    } else {
+        (0..num_words(vars))
+            .map(|w| if (w >> (var - 6)) & 1 != 0 { !0 } else { 0 })
+            .collect()
+    }
+}
+
+/// Cofactors of the function with respect to the variable, as functions of all variables.
+pub(crate) fn cofactors(truth: &[u64], var: usize) -> (Vec<u64>, Vec<u64>) {
+    if var < 6 {
+        let mask = VAR_TRUTH[var];
+        let shift = 1 << var;
+        truth
+            .iter()
+            .map(|&w| {
+                let (negative, positive) = (w & !mask, w & mask);
+                (negative | negative << shift, positive | positive >> shift)
+            })
+            .unzip()
+    } else {
+        let stride = 1 << (var - 6);
+        let cofactor = |value: usize| {
+            (0..truth.len())
+                .map(|w| truth[(w & !stride) | (value * stride)])
+                .collect()
+        };
+        (cofactor(0), cofactor(1))
+    }
+}
+
+/// Whether the function depends on the variable.
+pub(crate) fn depends_on(truth: &[u64], var: usize) -> bool {
+    let (negative, positive) = cofactors(truth, var);
+    negative != positive
+}
+
+/// Reconvergence-driven cut of the gate, with at most `max_leaves` leaves (sorted).
+///
+/// Starting from the fanins, the cut is expanded greedily, each time through the leaf
+/// adding the fewest new leaves, so that reconvergent paths end up inside the cut.
+pub(crate) fn reconvergence_cut(network: &Network, root: u32, max_leaves: usize) -> Vec<u32> {
+    let mut visited = vec![root];
+    let mut leaves = Vec::new();
+    for r in network.fanins(root).unwrap() {
+        if r.id() != 0 && !visited.contains(&r.id()) {
+            visited.push(r.id());
+            leaves.push(r.id());
+        }
+    }
+    loop {
+        let mut best: Option<(usize, usize)> = None;
+        for (i, &leaf) in leaves.iter().enumerate() {
+            let Some(args) = network.fanins(leaf) else {
+                continue;
+            };
+            let mut cost = 0;
+            for (k, r) in args.iter().enumerate() {
+                let new = r.id() != 0 && !visited.contains(&r.id());
+                if new && (k == 0 || args[0].id() != r.id()) {
+                    cost += 1;
+                }
+            }
+            if leaves.len() - 1 + cost <= max_leaves && best.is_none_or(|(c, _)| cost < c) {
+                best = Some((cost, i));
+            }
+        }
+        let Some((_, i)) = best else {
+            break;
+        };
+        let leaf = leaves.swap_remove(i);
+        for r in network.fanins(leaf).unwrap() {
+            if r.id() != 0 && !visited.contains(&r.id()) {
+                visited.push(r.id());
+                leaves.push(r.id());
+            }
+        }
+    }
+    leaves.sort_unstable();
+    leaves
+}
+
+/// Gates between the leaves and the root, in topological order (the root last).
+pub(crate) fn cone(network: &Network, root: u32, leaves: &[u32]) -> Vec<u32> {
+    let mut cone = Vec::new();
+    let mut stack = vec![(root, false)];
+    while let Some((id, ready)) = stack.pop() {
+        if ready {
+            cone.push(id);
+            continue;
+        }
+        if cone.contains(&id) || leaves.contains(&id) || !network.is_gate(id) {
+            continue;
+        }
+        stack.push((id, true));
+        for r in network.fanins(id).unwrap() {
+            stack.push((r.id(), false));
+        }
+    }
+    // Nodes reached along several paths were pushed more than once:
+    let mut seen = Vec::new();
+    cone.retain(|&id| {
+        let new = !seen.contains(&id);
+        seen.push(id);
+        new
+    });
+    cone
+}
+
+/// Truth tables of the nodes over `vars` variables, where leaf `i` is variable `i`.
+/// The nodes must be in topological order, with their fanins among the leaves,
+/// the constant and the previous nodes.
+pub(crate) fn simulate(
+    network: &Network,
+    leaves: &[u32],
+    vars: usize,
+    nodes: &[u32],
+) -> HashMap<u32, Vec<u64>> {
+    let mut values = HashMap::new();
+    values.insert(0, vec![0; num_words(vars)]);
+    for (i, &leaf) in leaves.iter().enumerate() {
+        values.insert(leaf, var_truth(i, vars));
+    }
+    for &id in nodes {
+        let truth = gate_truth(network, &values, id);
+        values.insert(id, truth);
+    }
+    values
+}
+
+fn gate_truth(network: &Network, values: &HashMap<u32, Vec<u64>>, id: u32) -> Vec<u64> {
+    let [a, b] = network.fanins(id).unwrap().map(|r| {
+        let mask = if r.is_negated() { !0 } else { 0 };
+        values[&r.id()].iter().map(move |&w| w ^ mask)
+    });
+    a.zip(b).map(|(x, y)| x & y).collect()
+}
+
+/// Observability care set of the gate, as a function of the leaves of its cut
+/// (with the given cone): the minterms for which changing the value of the gate may
+/// change an output, a property or a latch.
+///
+/// The complement are observability don't-cares: the gate can be replaced with any
+/// function which agrees with it on the care set. They are computed by exhaustive
+/// simulation of a window of the transitive fanout, up to a few levels above the gate;
+/// the other inputs of the window are treated as free variables, so the result is exact
+/// for the window and safe for the whole AIG.
+///
+/// Returns `None` if all minterms are care, or if the window is too large.
+pub(crate) fn care_set(
+    network: &Network,
+    root: u32,
+    leaves: &[u32],
+    cone: &[u32],
+) -> Option<Vec<u64>> {
+    if network.is_external(root) {
+        return None;
+    }
+
+    // All the fanout of the root up to the level limit. Since fanins have lower levels,
+    // the other inputs of the window do not depend on the root.
+    let limit = network.level(root) + ODC_LEVELS;
+    let mut window = vec![root];
+    let mut i = 0;
+    while i < window.len() {
+        for f in network.fanouts(window[i]) {
+            if network.level(f) <= limit && !window.contains(&f) {
+                window.push(f);
+            }
+        }
+        if window.len() > ODC_MAX_GATES {
+            return None;
+        }
+        i += 1;
+    }
+    window.sort_by_key(|&id| (network.level(id), id));
+
+    // The window outputs are its gates used outside of it:
+    let outputs = window
+        .iter()
+        .copied()
+        .filter(|&id| {
+            network.is_external(id) || network.fanouts(id).iter().any(|f| !window.contains(f))
+        })
+        .collect::<Vec<_>>();
+    if outputs.contains(&root) {
+        return None;
+    }
+
+    // Other inputs of the window, outside of the cone of the root, are new variables:
+    let mut inputs = leaves.to_vec();
+    for &id in &window[1..] {
+        for r in network.fanins(id).unwrap() {
+            let x = r.id();
+            if x != 0 && !window.contains(&x) && !cone.contains(&x) && !inputs.contains(&x) {
+                inputs.push(x);
+            }
+        }
+    }
+    let vars = inputs.len();
+    if vars > MAX_VARS {
+        return None;
+    }
+
+    // Simulate the window with the value of the root and with its complement:
+    let mut first = simulate(network, &inputs, vars, cone);
+    let mut second = first.clone();
+    for w in second.get_mut(&root).unwrap() {
+        *w = !*w;
+    }
+    for &id in &window[1..] {
+        let truth = gate_truth(network, &first, id);
+        first.insert(id, truth);
+        let truth = gate_truth(network, &second, id);
+        second.insert(id, truth);
+    }
+    let mut care = vec![0; num_words(vars)];
+    for &id in &outputs {
+        for ((c, &x), &y) in care.iter_mut().zip(&first[&id]).zip(&second[&id]) {
+            *c |= x ^ y;
+        }
+    }
+
+    // Quantify the window inputs existentially:
+    for var in leaves.len()..vars {
+        let (negative, positive) = cofactors(&care, var);
+        care = negative.iter().zip(&positive).map(|(x, y)| x | y).collect();
+    }
+    care.truncate(num_words(leaves.len()));
+    Some(care)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::builder::AigBuilder;
+
+    #[test]
+    fn test_cofactors() {
+        for vars in [3, 8] {
+            for var in 0..vars {
+                let x = var_truth(var, vars);
+                let y = var_truth((var + 1) % vars, vars);
+                let f = x.iter().zip(&y).map(|(a, b)| a & !b).collect::<Vec<_>>();
+                let (f0, f1) = cofactors(&f, var);
+                assert!(f0.iter().all(|&w| w == 0));
+                assert_eq!(f1, y.iter().map(|w| !w).collect::<Vec<_>>());
+                assert!(depends_on(&f, var));
+                assert!(!depends_on(&f1, var));
+            }
+        }
+    }
+
+    #[test]
+    fn test_window() {
+        // o = (a & b) | c
+        let mut builder = AigBuilder::new();
+        let [a, b, c] = builder.inputs(3).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let o = builder.or(ab, c);
+        builder.output(o);
+        let aig = builder.build();
+        let network = Network::new(&aig);
+
+        let cut = reconvergence_cut(&network, o.id(), 10);
+        assert_eq!(cut, vec![a.id(), b.id(), c.id()]);
+        let cone = cone(&network, o.id(), &cut);
+        assert_eq!(cone, vec![ab.id(), o.id()]);
+        let values = simulate(&network, &cut, 3, &cone);
+        // The OR is the complement of a gate:
+        let expected = (VAR_TRUTH[0] & VAR_TRUTH[1]) | VAR_TRUTH[2];
+        assert!(o.is_negated());
+        assert_eq!(values[&o.id()], vec![!expected]);
+
+        // The output itself is always observable, and so is `a & b` for some value of `c`:
+        assert_eq!(care_set(&network, o.id(), &cut, &cone), None);
+        let care = care_set(&network, ab.id(), &cut[..2], &cone[..1]);
+        assert_eq!(care, Some(vec![!0]));
+    }
+
+    #[test]
+    fn test_dont_cares() {
+        // o = (a & b) | a, where a & b is only observable when a is false.
+        let mut builder = AigBuilder::new();
+        let [a, b] = builder.inputs(2).try_into().unwrap();
+        let ab = builder.and(a, b);
+        let o = builder.or(ab, a);
+        builder.output(o);
+        let aig = builder.build();
+        let network = Network::new(&aig);
+
+        let leaves = [a.id(), b.id()];
+        let care = care_set(&network, ab.id(), &leaves, &[ab.id()]);
+        assert_eq!(care, Some(vec![!VAR_TRUTH[0]]));
+    }
+}
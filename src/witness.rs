@@ -0,0 +1,341 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use eyre::{eyre, WrapErr};
+
+use crate::aig::Aig;
+use crate::sim::SequentialSimulator;
+
+/// Result of a model checking run, as reported in the first line of a witness.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WitnessResult {
+    /// The property holds (`0`).
+    Unsat,
+    /// The property is violated, the witness contains a counterexample (`1`).
+    Sat,
+    /// The result is unknown (`2`).
+    Unknown,
+}
+
+/// Property referenced in a witness, e.g. `b0` or `j1`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Property {
+    Bad(usize),
+    Justice(usize),
+}
+
+impl Display for Property {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Property::Bad(i) => write!(f, "b{}", i),
+            Property::Justice(i) => write!(f, "j{}", i),
+        }
+    }
+}
+
+impl FromStr for Property {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        let (kind, index) = s.split_at(s.find(|c: char| c.is_ascii_digit()).unwrap_or(0));
+        let index = index
+            .parse::<usize>()
+            .map_err(|_| eyre!("Invalid property index in '{}'", s))?;
+        match kind {
+            "b" => Ok(Property::Bad(index)),
+            "j" => Ok(Property::Justice(index)),
+            _ => Err(eyre!("Invalid property '{}', expected 'b<n>' or 'j<n>'", s)),
+        }
+    }
+}
+
+/// AIGER witness, the output format of model checkers in the HWMCC.
+///
+/// ```text
+/// 1      result: 1 for a counterexample
+/// b0     violated properties
+/// 0x1    initial latch values
+/// 10     input values, one line per cycle
+/// 0x
+/// .      end of the witness
+/// ```
+///
+/// Unknown values are denoted by `x` and stored as `None`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Witness {
+    pub result: WitnessResult,
+    pub properties: Vec<Property>,
+    /// Initial latch values.
+    pub init: Vec<Option<bool>>,
+    /// Input values for each cycle.
+    pub inputs: Vec<Vec<Option<bool>>>,
+}
+
+fn parse_values(line: &str) -> eyre::Result<Vec<Option<bool>>> {
+    line.chars()
+        .map(|c| match c {
+            '0' => Ok(Some(false)),
+            '1' => Ok(Some(true)),
+            'x' => Ok(None),
+            _ => Err(eyre!("Invalid value '{}', expected '0', '1' or 'x'", c)),
+        })
+        .collect()
+}
+
+fn format_values(values: &[Option<bool>]) -> String {
+    values
+        .iter()
+        .map(|v| match v {
+            Some(false) => '0',
+            Some(true) => '1',
+            None => 'x',
+        })
+        .collect()
+}
+
+impl Witness {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        log::debug!("Reading witness from {}", path.display());
+        let f = File::open(path).wrap_err_with(|| format!("Failed to open {}", path.display()))?;
+        Self::parse(BufReader::new(f))
+    }
+
+    pub fn parse(r: impl BufRead) -> eyre::Result<Self> {
+        let mut lines = r
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.starts_with('c')));
+        let mut next_line = || -> eyre::Result<String> {
+            let line = lines
+                .next()
+                .ok_or_else(|| eyre!("Unexpected end of witness"))??;
+            Ok(line.trim_end().to_string())
+        };
+
+        let line = next_line()?;
+        let result = match line.as_str() {
+            "0" => WitnessResult::Unsat,
+            "1" => WitnessResult::Sat,
+            "2" => WitnessResult::Unknown,
+            _ => return Err(eyre!("Invalid result '{}', expected 0, 1 or 2", line)),
+        };
+
+        let line = next_line()?;
+        if line == "." {
+            return Ok(Witness {
+                result,
+                properties: Vec::new(),
+                init: Vec::new(),
+                inputs: Vec::new(),
+            });
+        }
+        let properties = line
+            .split_whitespace()
+            .map(|s| s.parse::<Property>())
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let mut init = Vec::new();
+        let mut inputs = Vec::new();
+        if result == WitnessResult::Sat {
+            init = parse_values(&next_line()?).wrap_err("Invalid initial state")?;
+            loop {
+                let line = next_line()?;
+                if line == "." {
+                    break;
+                }
+                inputs.push(parse_values(&line).wrap_err("Invalid input vector")?);
+            }
+        } else {
+            let line = next_line()?;
+            if line != "." {
+                return Err(eyre!("Expected '.', got '{}'", line));
+            }
+        }
+
+        Ok(Witness {
+            result,
+            properties,
+            init,
+            inputs,
+        })
+    }
+}
+
+impl FromStr for Witness {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        Self::parse(s.as_bytes())
+    }
+}
+
+impl Display for Witness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let result = match self.result {
+            WitnessResult::Unsat => 0,
+            WitnessResult::Sat => 1,
+            WitnessResult::Unknown => 2,
+        };
+        writeln!(f, "{}", result)?;
+        if !self.properties.is_empty() {
+            let properties = self
+                .properties
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", properties.join(" "))?;
+        }
+        if self.result == WitnessResult::Sat {
+            writeln!(f, "{}", format_values(&self.init))?;
+            for input in &self.inputs {
+                writeln!(f, "{}", format_values(input))?;
+            }
+        }
+        writeln!(f, ".")
+    }
+}
+
+impl Aig {
+    /// Replay the witness and check that it violates the first claimed bad state property.
+    ///
+    /// When the AIG has no bad state properties, its outputs are treated as such.
+    /// Unknown input values are set to zero, unknown initial values of latches
+    /// are set to their reset values (or zero, for uninitialised latches).
+    /// Invariant constraints must hold in every cycle up to the violation.
+    ///
+    /// Returns the cycle in which the property is violated.
+    pub fn check_witness(&self, witness: &Witness) -> eyre::Result<usize> {
+        if witness.result != WitnessResult::Sat {
+            return Err(eyre!("Witness does not contain a counterexample"));
+        }
+        let bad = match witness.properties.first() {
+            Some(&Property::Bad(index)) => *self
+                .safety_properties()
+                .get(index)
+                .ok_or_else(|| eyre!("Bad state property {} does not exist", index))?,
+            Some(property) => {
+                return Err(eyre!("Checking property {} is not supported", property));
+            }
+            None => return Err(eyre!("Witness does not reference any property")),
+        };
+
+        if witness.init.len() != self.latches().len() {
+            return Err(eyre!(
+                "Invalid initial state length: expected {}, got {}",
+                self.latches().len(),
+                witness.init.len()
+            ));
+        }
+        let mut state = Vec::with_capacity(witness.init.len());
+        for (&id, &value) in self.latches().iter().zip(&witness.init) {
+            let reset = self.latch(id).init.value();
+            match (value, reset) {
+                (Some(v), Some(r)) if v != r => {
+                    return Err(eyre!(
+                        "Initial value of latch {} contradicts its reset value",
+                        id
+                    ));
+                }
+                _ => state.push(value.or(reset).unwrap_or(false)),
+            }
+        }
+
+        let mut sim = SequentialSimulator::with_state(self, state);
+        for (step, input) in witness.inputs.iter().enumerate() {
+            if input.len() != self.inputs().len() {
+                return Err(eyre!(
+                    "Invalid input vector length in cycle {}: expected {}, got {}",
+                    step,
+                    self.inputs().len(),
+                    input.len()
+                ));
+            }
+            let input = input.iter().map(|v| v.unwrap_or(false)).collect::<Vec<_>>();
+            sim.step(&input);
+            if let Some(&c) = self.constraints().iter().find(|&&c| !sim.value(c)) {
+                return Err(eyre!(
+                    "Invariant constraint {} violated in cycle {}",
+                    c,
+                    step
+                ));
+            }
+            if sim.value(bad) {
+                return Ok(step);
+            }
+        }
+
+        Err(eyre!("Bad state property {} is not violated", bad))
+    }
+}
+
+impl Witness {
+    /// Build a counterexample for the property from the initial state and the inputs in each cycle.
+    pub fn counterexample(property: Property, init: Vec<bool>, inputs: Vec<Vec<bool>>) -> Self {
+        Witness {
+            result: WitnessResult::Sat,
+            properties: vec![property],
+            init: init.into_iter().map(Some).collect(),
+            inputs: inputs
+                .into_iter()
+                .map(|input| input.into_iter().map(Some).collect())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    #[test]
+    fn test_parse_witness() {
+        let input = indoc! {"
+            c comment
+            1
+            b0
+            0x
+            10
+            x1
+            .
+        "};
+        let witness = input.parse::<Witness>().unwrap();
+        assert_eq!(witness.result, WitnessResult::Sat);
+        assert_eq!(witness.properties, vec![Property::Bad(0)]);
+        assert_eq!(witness.init, vec![Some(false), None]);
+        assert_eq!(
+            witness.inputs,
+            vec![vec![Some(true), Some(false)], vec![None, Some(true)]]
+        );
+        assert_eq!(witness.to_string(), input.replace("c comment\n", ""));
+
+        let witness = "0\nb1 j0\n.\n".parse::<Witness>().unwrap();
+        assert_eq!(witness.result, WitnessResult::Unsat);
+        assert_eq!(
+            witness.properties,
+            vec![Property::Bad(1), Property::Justice(0)]
+        );
+        assert_eq!(witness.to_string(), "0\nb1 j0\n.\n");
+    }
+
+    #[test]
+    fn test_check_witness() {
+        // Output 'Q' of toggle-re, with inputs [enable, reset], serves as the bad property.
+        let aig = Aig::from_file("data/examples/toggle-re.aag").unwrap();
+
+        let witness = "1\nb0\n0\n11\n00\n.\n".parse::<Witness>().unwrap();
+        assert_eq!(aig.check_witness(&witness).unwrap(), 1);
+
+        // Disabled toggle never reaches Q:
+        let witness = "1\nb0\nx\n01\n01\n.\n".parse::<Witness>().unwrap();
+        assert!(aig.check_witness(&witness).is_err());
+
+        // Initial state contradicts the reset value:
+        let witness = "1\nb0\n1\n11\n.\n".parse::<Witness>().unwrap();
+        assert!(aig.check_witness(&witness).is_err());
+    }
+}
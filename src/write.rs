@@ -2,19 +2,36 @@ use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::Path;
 
-use eyre::WrapErr;
+use eyre::{eyre, WrapErr};
 
 use crate::aig::Aig;
-use crate::aiger::TAG;
+use crate::aiger::{write_delta, Format, Header};
+use crate::node::LatchInit;
 
 impl Aig {
+    /// Write the AIG to a file.
+    ///
+    /// The binary format is used for files with the `.aig` extension,
+    /// the ASCII format is used otherwise. AIGs which are not in canonical order
+    /// are renumbered with [`Aig::renumber`] before writing them in the binary format.
     pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> eyre::Result<()> {
         let path = path.as_ref();
         log::debug!("Writing AIG to {}", path.display());
         let f =
             File::create(path).wrap_err_with(|| format!("Failed to create {}", path.display()))?;
         let mut f = BufWriter::new(f);
-        self.write(&mut f)
+        if path.extension().is_some_and(|ext| ext == "aig") {
+            if self.is_canonical() {
+                self.write_binary(&mut f)?;
+            } else {
+                log::debug!("Renumbering the AIG into canonical order");
+                self.renumber().0.write_binary(&mut f)?;
+            }
+        } else {
+            self.write(&mut f)?;
+        }
+        f.flush()?;
+        Ok(())
     }
 
     pub fn write_to_string(&self) -> eyre::Result<String> {
@@ -25,14 +42,25 @@ impl Aig {
         Ok(s)
     }
 
+    fn header(&self, format: Format) -> Header {
+        Header {
+            format,
+            m: self.max_id() as usize,
+            i: self.inputs().len(),
+            l: self.latches().len(),
+            o: self.outputs().len(),
+            a: self.and_gates().count(),
+            b: self.bad().len(),
+            c: self.constraints().len(),
+            j: self.justice().len(),
+            f: self.fairness().len(),
+        }
+    }
+
+    /// Write the AIG in the ASCII AIGER format.
     pub fn write(&self, writer: &mut impl Write) -> eyre::Result<()> {
         // Header:
-        let m = *self.nodes().keys().max().unwrap() as usize;
-        let i = self.inputs().len();
-        let l = self.latches().len();
-        let o = self.outputs().len();
-        let a = self.and_gates().count();
-        writeln!(writer, "{} {} {} {} {} {}", TAG, m, i, l, o, a)?;
+        writeln!(writer, "{}", self.header(Format::Ascii))?;
 
         // Inputs:
         for &id in self.inputs() {
@@ -42,7 +70,9 @@ impl Aig {
         // Latches:
         for &id in self.latches() {
             let latch = self.latch(id);
-            writeln!(writer, "{} {}", id * 2, latch.next.raw())?;
+            write!(writer, "{} {}", id * 2, latch.next.raw())?;
+            write_latch_init(writer, id, latch.init)?;
+            writeln!(writer)?;
         }
 
         // Outputs:
@@ -50,6 +80,8 @@ impl Aig {
             writeln!(writer, "{}", output.raw())?;
         }
 
+        self.write_properties(writer)?;
+
         // Gates:
         let mut gates: Vec<u32> = self.and_gates().map(|g| g.id).collect();
         gates.sort();
@@ -59,8 +91,150 @@ impl Aig {
             writeln!(writer, "{} {} {}", id * 2, left.raw(), right.raw())?;
         }
 
+        self.write_symbols(writer)?;
+
+        Ok(())
+    }
+
+    /// Write the AIGER 1.9 property sections: bad states, invariant constraints,
+    /// justice properties and fairness constraints.
+    fn write_properties(&self, writer: &mut impl Write) -> eyre::Result<()> {
+        // Bad state properties:
+        for bad in self.bad() {
+            writeln!(writer, "{}", bad.raw())?;
+        }
+
+        // Invariant constraints:
+        for constraint in self.constraints() {
+            writeln!(writer, "{}", constraint.raw())?;
+        }
+
+        // Justice properties:
+        for justice in self.justice() {
+            writeln!(writer, "{}", justice.len())?;
+        }
+        for justice in self.justice() {
+            for lit in justice {
+                writeln!(writer, "{}", lit.raw())?;
+            }
+        }
+
+        // Fairness constraints:
+        for fairness in self.fairness() {
+            writeln!(writer, "{}", fairness.raw())?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the symbol table and the comment section.
+    fn write_symbols(&self, writer: &mut impl Write) -> eyre::Result<()> {
+        // Symbols:
+        for (type_spec, position, name) in self.symbols().iter() {
+            writeln!(writer, "{}{} {}", type_spec.prefix(), position, name)?;
+        }
+
+        // Comments:
+        if !self.comments().is_empty() {
+            writeln!(writer, "c")?;
+            for comment in self.comments() {
+                writeln!(writer, "{}", comment)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the AIG in the binary AIGER format.
+    ///
+    /// The binary format requires the AIG to be in canonical order: inputs are numbered
+    /// `1..=I`, latches `I+1..=I+L`, and AND gates `I+L+1..=M`, with each gate
+    /// defined after its arguments (see [`Aig::is_canonical`] and [`Aig::renumber`]).
+    pub fn write_binary(&self, writer: &mut impl Write) -> eyre::Result<()> {
+        let header = self.header(Format::Binary);
+        let i = header.i as u32;
+        let l = header.l as u32;
+
+        // Check the canonical order:
+        if header.m != header.i + header.l + header.a {
+            return Err(eyre!(
+                "AIG has gaps in the variable indices (m = {}, i + l + a = {})",
+                header.m,
+                header.i + header.l + header.a
+            ));
+        }
+        for (k, &id) in self.inputs().iter().enumerate() {
+            if id != k as u32 + 1 {
+                return Err(eyre!("Input {} is not in canonical order", id));
+            }
+        }
+        for (k, &id) in self.latches().iter().enumerate() {
+            if id != i + k as u32 + 1 {
+                return Err(eyre!("Latch {} is not in canonical order", id));
+            }
+        }
+        for id in (i + l + 1)..=(header.m as u32) {
+            if !self.is_gate(id) {
+                return Err(eyre!("Node {} is not an AND gate", id));
+            }
+            let gate = self.gate(id);
+            if gate.args.iter().any(|arg| arg.id() >= id) {
+                return Err(eyre!("AND gate {} is defined before its arguments", id));
+            }
+        }
+
+        // Header:
+        writeln!(writer, "{}", header)?;
+
+        // Latches:
+        for &id in self.latches() {
+            let latch = self.latch(id);
+            write!(writer, "{}", latch.next.raw())?;
+            write_latch_init(writer, id, latch.init)?;
+            writeln!(writer)?;
+        }
+
+        // Outputs:
+        for output in self.outputs() {
+            writeln!(writer, "{}", output.raw())?;
+        }
+
+        self.write_properties(writer)?;
+
+        // Gates:
+        for id in (i + l + 1)..=(header.m as u32) {
+            let gate = self.gate(id);
+            let [left, right] = gate.args;
+            let (left, right) = if left.raw() >= right.raw() {
+                (left, right)
+            } else {
+                (right, left)
+            };
+            write_delta(writer, id * 2 - left.raw())?;
+            write_delta(writer, left.raw() - right.raw())?;
+        }
+
+        self.write_symbols(writer)?;
+
         Ok(())
     }
+
+    pub fn write_binary_to_vec(&self) -> eyre::Result<Vec<u8>> {
+        log::debug!("Writing binary AIG to vector");
+        let mut buf = Vec::new();
+        self.write_binary(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// Write the latch reset value, which is omitted when it is zero.
+fn write_latch_init(writer: &mut impl Write, id: u32, init: LatchInit) -> eyre::Result<()> {
+    match init {
+        LatchInit::Zero => {}
+        LatchInit::One => write!(writer, " 1")?,
+        LatchInit::Nondet => write!(writer, " {}", id * 2)?,
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -96,7 +270,7 @@ mod tests {
     #[test]
     fn test_write_toggle() {
         let mut aig = Aig::default();
-        aig.add_latch(1, Ref::negative(1));
+        aig.add_latch(1, Ref::negative(1), LatchInit::Zero);
         aig.add_output(Ref::positive(1));
         aig.add_output(Ref::negative(1));
         let s = aig.write_to_string().unwrap();
@@ -108,4 +282,98 @@ mod tests {
         "};
         assert_eq!(s, expected);
     }
+
+    #[test]
+    fn test_write_latch_init() {
+        let mut aig = Aig::default();
+        aig.add_input(1);
+        aig.add_latch(2, Ref::positive(1), LatchInit::Zero);
+        aig.add_latch(3, Ref::negative(2), LatchInit::One);
+        aig.add_latch(4, Ref::positive(3), LatchInit::Nondet);
+        aig.add_output(Ref::positive(4));
+        let s = aig.write_to_string().unwrap();
+        let expected = indoc! {"
+            aag 4 1 3 1 0
+            2
+            4 2
+            6 5 1
+            8 6 8
+            8
+        "};
+        assert_eq!(s, expected);
+
+        let bytes = aig.write_binary_to_vec().unwrap();
+        assert_eq!(bytes, b"aig 4 1 3 1 0\n2\n5 1\n6 8\n8\n");
+        let parsed = Aig::parse(bytes.as_slice()).unwrap();
+        assert_eq!(parsed.latch(3).init, LatchInit::One);
+        assert_eq!(parsed.latch(4).init, LatchInit::Nondet);
+    }
+
+    #[test]
+    fn test_write_properties() {
+        let input = indoc! {"
+            aag 3 1 1 0 1 1 1 2 1
+            2
+            4 6
+            7
+            3
+            2
+            1
+            6
+            2
+            4
+            5
+            6 4 2
+        "};
+        let aig = Aig::parse(input.as_bytes()).unwrap();
+        assert_eq!(aig.bad(), &[Ref::negative(3)]);
+        assert_eq!(aig.constraints(), &[Ref::negative(1)]);
+        assert_eq!(
+            aig.justice(),
+            &[
+                vec![Ref::positive(3), Ref::positive(1)],
+                vec![Ref::positive(2)]
+            ]
+        );
+        assert_eq!(aig.fairness(), &[Ref::negative(2)]);
+        let s = aig.write_to_string().unwrap();
+        assert_eq!(s, input);
+
+        let bytes = aig.write_binary_to_vec().unwrap();
+        let parsed = Aig::parse(bytes.as_slice()).unwrap();
+        assert_eq!(parsed.write_to_string().unwrap(), input);
+    }
+
+    #[test]
+    fn test_write_binary() {
+        let mut aig = Aig::default();
+        aig.add_input(1);
+        aig.add_input(2);
+        aig.add_latch(3, Ref::negative(4), LatchInit::Zero);
+        aig.add_and_gate(4, [Ref::positive(3), Ref::negative(1)]);
+        aig.add_and_gate(5, [Ref::negative(4), Ref::positive(2)]);
+        aig.add_output(Ref::negative(5));
+        let bytes = aig.write_binary_to_vec().unwrap();
+
+        let mut expected = b"aig 5 2 1 1 2\n9\n11\n".to_vec();
+        expected.extend([2, 3]); // 8 6 3
+        expected.extend([1, 5]); // 10 9 4
+        assert_eq!(bytes, expected);
+
+        let parsed = Aig::parse(bytes.as_slice()).unwrap();
+        assert_eq!(
+            parsed.write_to_string().unwrap(),
+            aig.write_to_string().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_binary_non_canonical() {
+        let mut aig = Aig::default();
+        aig.add_input(2);
+        aig.add_input(1);
+        aig.add_and_gate(3, [Ref::positive(1), Ref::positive(2)]);
+        aig.add_output(Ref::positive(3));
+        assert!(aig.write_binary_to_vec().is_err());
+    }
 }